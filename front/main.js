@@ -1,7 +1,14 @@
 // Fire Simulation with GPU Rendering
 // Uses WebGPU for both compute simulation and rendering
 
-import init, { initialize, start, SimulationController } from './pkg/firesim.js';
+import init, {
+  initialize,
+  start,
+  start_with_scenario,
+  parameter_schema,
+  RenderMode,
+  SimulationController,
+} from './pkg/firesim.js';
 
 let isPaused = false;
 let isStopped = false;
@@ -20,139 +27,68 @@ function resizeCanvas(canvas) {
   }
 }
 
-// Parameter definitions with metadata
-const PARAMETERS = {
-  months_per_second: {
-    label: "Simulation Speed (months/sec)",
-    min: 1,
-    max: 1000,
-    step: 1,
-    default: 36,
-    setter: (v) => SimulationController.set_months_per_second(v)
-  },
-  ticks_per_month: {
-    label: "Resolution (ticks/month)",
-    min: 1,
-    max: 20,
-    step: 1,
-    default: 2,
-    setter: (v) => SimulationController.set_ticks_per_month(v)
-  },
-  lightning_frequency: {
-    label: "Lightning (strikes/year/acre)",
-    min: 0,
-    max: 0.1,
-    step: 0.001,
-    default: 1/45,
-    setter: (v) => SimulationController.set_lightning_frequency(v)
-  },
-  fire_spread_rate: {
-    label: "Fire Spread Rate",
-    min: 0,
-    max: 1,
-    step: 0.05,
-    default: 1.0,
-    setter: (v) => SimulationController.set_fire_spread_rate(v)
-  },
-  tree_growth_years: {
-    label: "Tree Growth (years)",
-    min: 10,
-    max: 500,
-    step: 10,
-    default: 150,
-    setter: (v) => SimulationController.set_tree_growth_years(v)
-  },
-  tree_death_years: {
-    label: "Tree Death (years)",
-    min: 50,
-    max: 500,
-    step: 10,
-    default: 200,
-    setter: (v) => SimulationController.set_tree_death_years(v)
-  },
-  tree_flammability: {
-    label: "Tree Flammability",
-    min: 0,
-    max: 1,
-    step: 0.05,
-    default: 0.5,
-    setter: (v) => SimulationController.set_tree_flammability(v)
-  },
-  underbrush_flammability: {
-    label: "Underbrush Flammability",
-    min: 0,
-    max: 2,
-    step: 0.1,
-    default: 1.0,
-    setter: (v) => SimulationController.set_underbrush_flammability(v)
-  },
-  tree_underbrush_generation: {
-    label: "Underbrush Generation",
-    min: 0,
-    max: 0.01,
-    step: 0.00001,
-    default: 0.0001,
-    setter: (v) => SimulationController.set_tree_underbrush_generation(v)
-  },
-  underbrush_tree_growth_hindrance: {
-    label: "Underbrush Growth Hindrance",
-    min: 0,
-    max: 1,
-    step: 0.1,
-    default: 0,
-    setter: (v) => SimulationController.set_underbrush_tree_growth_hindrance(v)
-  },
-  tree_fire_duration: {
-    label: "Tree Fire Duration (ticks)",
-    min: 1,
-    max: 10,
-    step: 1,
-    default: 1,
-    setter: (v) => SimulationController.set_tree_fire_duration(v)
-  },
-  underbrush_fire_duration: {
-    label: "Underbrush Fire Duration (ticks)",
-    min: 1,
-    max: 10,
-    step: 1,
-    default: 1,
-    setter: (v) => SimulationController.set_underbrush_fire_duration(v)
-  }
-};
-
 function formatValue(value, step) {
   if (step >= 1) return Math.round(value).toString();
   const decimals = Math.max(0, -Math.floor(Math.log10(step)));
   return value.toFixed(decimals);
 }
 
-function createParameterControl(key, param) {
+function createParameterControl(param) {
   const container = document.createElement('div');
   container.className = 'param-control';
+  container.title = param.description;
   
   const label = document.createElement('label');
-  label.textContent = param.label;
-  label.htmlFor = `param-${key}`;
+  label.textContent = param.unit ? `${param.label} (${param.unit})` : param.label;
+  label.htmlFor = `param-${param.name}`;
   
   const inputContainer = document.createElement('div');
   inputContainer.className = 'input-container';
   
-  const input = document.createElement('input');
-  input.type = 'range';
-  input.id = `param-${key}`;
-  input.min = param.min;
-  input.max = param.max;
-  input.step = param.step;
-  input.value = param.default;
+  // Start from the running simulation's value, which may come from a scenario
+  const initial = SimulationController.get_parameter(param.name) ?? param.default;
+
+  const isChoice = param.kind === 'choice';
+  const describe = (value) =>
+    isChoice ? param.options[Math.round(value)] : formatValue(value, param.step);
+
+  // Choices get a dropdown, everything else a slider
+  let input;
+  if (isChoice) {
+    input = document.createElement('select');
+    param.options.forEach((option, index) => {
+      const element = document.createElement('option');
+      element.value = index;
+      element.textContent = option.replace(/_/g, ' ');
+      input.appendChild(element);
+    });
+  } else {
+    input = document.createElement('input');
+    input.type = 'range';
+    input.min = param.min;
+    input.max = param.max;
+    input.step = param.step;
+  }
+  input.id = `param-${param.name}`;
+  input.value = initial;
   
   const valueDisplay = document.createElement('span');
   valueDisplay.className = 'value-display';
-  valueDisplay.textContent = formatValue(param.default, param.step);
+  valueDisplay.textContent = isChoice ? '' : describe(initial);
   
-  input.addEventListener('input', (e) => {
+  input.addEventListener(isChoice ? 'change' : 'input', (e) => {
     const value = parseFloat(e.target.value);
-    valueDisplay.textContent = formatValue(value, param.step);
-    param.setter(value);
+    if (!isChoice) {
+      valueDisplay.textContent = describe(value);
+    }
+    try {
+      SimulationController.set_parameter(param.name, value);
+      container.classList.remove('invalid');
+    } catch (err) {
+      // The simulation keeps its previous value
+      container.classList.add('invalid');
+      console.warn(err);
+    }
   });
   
   inputContainer.appendChild(input);
@@ -175,9 +111,9 @@ function initParametersPanel() {
   panel.innerHTML = '';
   if (header) panel.appendChild(header);
   
-  // Add parameter controls
-  for (const [key, param] of Object.entries(PARAMETERS)) {
-    panel.appendChild(createParameterControl(key, param));
+  // Add a control for every parameter in the Rust-side registry
+  for (const param of JSON.parse(parameter_schema())) {
+    panel.appendChild(createParameterControl(param));
   }
 }
 
@@ -207,12 +143,19 @@ async function run() {
   let canvas = document.getElementById("sim-surface");
   resizeCanvas(canvas);
   
-  // Initialize the parameters panel
-  initParametersPanel();
-  
   // Start the GPU-based simulation with integrated rendering
-  // The start() function uses winit event loop and handles everything internally
-  start();
+  // The start() function uses winit event loop and handles everything internally.
+  // A scenario file can be given with `?scenario=scenarios/example.toml`
+  const scenarioUrl = new URLSearchParams(window.location.search).get('scenario');
+  if (scenarioUrl) {
+    const source = await (await fetch(scenarioUrl)).text();
+    start_with_scenario(source);
+  } else {
+    start();
+  }
+
+  // Initialize the parameters panel once the simulation's parameters are set
+  initParametersPanel();
   
   console.log("Fire simulation started with GPU rendering");
 }
@@ -249,3 +192,8 @@ document.getElementById("pauseButton").addEventListener("click", () => {
     console.log(isPaused ? "Simulation paused" : "Simulation resumed");
   }
 });
+
+// Render mode selector
+document.getElementById("renderMode").addEventListener("change", (e) => {
+  SimulationController.set_render_mode(RenderMode[e.target.value]);
+});
@@ -0,0 +1,73 @@
+//! Run a scenario file (TOML or JSON) headlessly on a native GPU and print
+//! its summary statistics as CSV
+//!
+//! ```text
+//! cargo run --release --bin run_scenario -- front/scenarios/default.toml > run.csv
+//! ```
+
+use std::{
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+use anyhow::Context as _;
+use firesim::{
+    scenario::Scenario,
+    sim::{
+        gpucompute::GpuResources,
+        sweep::{SweepSpec, run_sweep},
+    },
+};
+
+fn main() -> Result<(), anyhow::Error> {
+    fern::Dispatch::new()
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "[{} {}] {}",
+                record.level(),
+                record.target(),
+                message
+            ))
+        })
+        .level(log::LevelFilter::Info)
+        .chain(std::io::stderr())
+        .apply()?;
+
+    let path = std::env::args()
+        .nth(1)
+        .context("usage: run_scenario <scenario file>")?;
+    let source =
+        std::fs::read_to_string(&path).with_context(|| format!("failed to read {path}"))?;
+    let scenario = Scenario::parse(&source).with_context(|| format!("failed to parse {path}"))?;
+    if !scenario.name.is_empty() {
+        log::info!("Scenario: {}", scenario.name);
+    }
+
+    let resources = block_on(GpuResources::new(), None)?;
+    let table = block_on(
+        run_sweep(&resources, &SweepSpec::from(&scenario)),
+        Some(&resources.device),
+    )?;
+    print!("{}", table.to_csv());
+    Ok(())
+}
+
+/// Run a future to completion on this thread. Buffer readbacks only finish
+/// when the device is polled, so poll `device` whenever the future is stuck.
+fn block_on<T>(future: impl Future<Output = T>, device: Option<&wgpu::Device>) -> T {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(value) = future.as_mut().poll(&mut context) {
+            return value;
+        }
+        match device {
+            Some(device) => {
+                if let Err(e) = device.poll(wgpu::PollType::wait_indefinitely()) {
+                    log::warn!("failed to poll the device: {e}");
+                }
+            }
+            None => std::thread::yield_now(),
+        }
+    }
+}
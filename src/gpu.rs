@@ -3,12 +3,15 @@
 //! This module provides `GpuSimRenderer` which combines the compute shader simulation
 //! with GPU-accelerated rendering, sharing the same device, queue, and cell buffers.
 
-use std::sync::Arc;
+use std::mem::offset_of;
+use std::sync::{Arc, Mutex};
+
+use bytemuck::{Pod, Zeroable};
 
 use js_sys::Date;
 
 use wgpu::{
-    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
     BindGroupLayoutEntry, Buffer, BufferUsages, CommandEncoderDescriptor, Device, FragmentState,
     Instance, LoadOp, MultisampleState, Operations, PipelineLayoutDescriptor, PrimitiveState,
     Queue, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
@@ -18,7 +21,15 @@ use wgpu::{
 };
 use winit::window::Window;
 
-use crate::sim::{BurnState, SimulationFrame, SimulationParameters, gpucompute::GpuCell};
+use crate::rendering::RenderMode;
+use crate::sim::{
+    Climate, ConfigurableParameters, EventLog, FireCatalogue, Management, Neighbourhood,
+    SimulationFrame, SimulationParameters, SnapshotLog, Timeline, TreatmentLog, WeatherSeries,
+    gpucompute::{self, SimulationPipeline},
+    stats::FrameSummary,
+    storms::GpuStormTracks,
+    timeline::TimedEvent,
+};
 
 /// Integrated GPU context for simulation and rendering
 ///
@@ -32,7 +43,7 @@ pub struct GpuSimRenderer {
     instance: Instance, // Keep instance alive for the lifetime of the renderer
     device: Arc<Device>,
     queue: Arc<Queue>,
-    compute: ComputeContextIntegrated,
+    compute: SimulationPipeline,
     render: RenderContextIntegrated,
     width: usize,
     height: usize,
@@ -47,23 +58,21 @@ pub struct GpuSimRenderer {
     last_tick_log_time: f64,
     /// For debug logging: ticks since last log
     ticks_since_last_log: u32,
+    /// The simulation never runs past this many steps
+    step_limit: Option<u32>,
+    /// Whether recent storm tracks are drawn over the grid
+    show_storms: bool,
 }
 
-/// Compute context adapted for integrated rendering
-struct ComputeContextIntegrated {
-    buf_1: Buffer,
-    buf_2: Buffer,
-    cells_bg: BindGroup,
-    cells_bg_rev: BindGroup,
-    params_bind_group: BindGroup,
-    params_buf: Buffer,
-    size_bind_group: BindGroup,
-    flipped_bufs: bool,
-    time_bind_group: BindGroup,
-    time_buf: Buffer,
-    old_params: SimulationParameters,
-    pipeline: wgpu::ComputePipeline,
-    steps: u32,
+/// The grid layout and render mode, as laid out in the render size uniform
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct RenderSize {
+    width: u32,
+    height: u32,
+    /// 1 if the cells are drawn as hexagons
+    hexagonal: u32,
+    render_mode: u32,
 }
 
 /// Render context for integrated GPU simulation
@@ -71,9 +80,12 @@ struct RenderContextIntegrated {
     surface: Surface<'static>,
     surface_config: SurfaceConfiguration,
     render_pipeline: RenderPipeline,
+    cells_bind_group_layout: BindGroupLayout,
     cells_bind_group_1: BindGroup, // Bind group for buf_1
     cells_bind_group_2: BindGroup, // Bind group for buf_2
     size_bind_group: BindGroup,
+    size_buffer: Buffer,
+    storm_buffer: Buffer,
 }
 
 impl GpuSimRenderer {
@@ -83,10 +95,12 @@ impl GpuSimRenderer {
     /// * `window` - The window to render to
     /// * `start` - Initial simulation frame
     /// * `parameters` - Simulation parameters
+    /// * `neighbourhood` - Neighbourhood that fire spreads through
     pub async fn new(
         window: Arc<Window>,
         start: SimulationFrame,
         parameters: SimulationParameters,
+        neighbourhood: Neighbourhood,
     ) -> Result<Self, anyhow::Error> {
         let instance = Instance::new(&wgpu::InstanceDescriptor::default());
 
@@ -118,7 +132,7 @@ impl GpuSimRenderer {
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("firesim integrated device"),
                 required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::downlevel_defaults(),
+                required_limits: gpucompute::required_limits(),
                 experimental_features: wgpu::ExperimentalFeatures::disabled(),
                 memory_hints: wgpu::MemoryHints::MemoryUsage,
                 trace: wgpu::Trace::Off,
@@ -154,18 +168,29 @@ impl GpuSimRenderer {
         surface.configure(&device, &surface_config);
 
         // Create compute context
-        let compute = Self::create_compute_context(&device, &start, parameters)?;
+        let compute = SimulationPipeline::new(
+            Arc::clone(&device),
+            Arc::clone(&queue),
+            &start,
+            parameters,
+            neighbourhood,
+        )?;
 
         // Create render context
+        let (buf_1, buf_2) = compute.buffers();
         let render = Self::create_render_context(
             &device,
             surface,
             surface_config,
             surface_format,
-            &compute.buf_1,
-            &compute.buf_2,
-            start.width as u32,
-            start.height as u32,
+            buf_1,
+            buf_2,
+            RenderSize {
+                width: start.width as u32,
+                height: start.height as u32,
+                hexagonal: neighbourhood.is_hexagonal() as u32,
+                render_mode: RenderMode::Standard as u32,
+            },
         )?;
 
         Ok(Self {
@@ -182,6 +207,8 @@ impl GpuSimRenderer {
             last_logged_params: None,
             last_tick_log_time: 0.0,
             ticks_since_last_log: 0,
+            step_limit: None,
+            show_storms: true,
         })
     }
 
@@ -196,229 +223,6 @@ impl GpuSimRenderer {
         &self.window
     }
 
-    fn create_compute_context(
-        device: &Device,
-        start: &SimulationFrame,
-        parameters: SimulationParameters,
-    ) -> Result<ComputeContextIntegrated, anyhow::Error> {
-        let start_data: Vec<_> = start
-            .grid
-            .iter()
-            .map(|i| GpuCell {
-                burning: match i.burning {
-                    BurnState::NotBurning => 0,
-                    BurnState::Burning { ticks_remaining } => ticks_remaining,
-                },
-                tree: if i.tree { 1.0 } else { 0.0 },
-                underbrush: i.underbrush,
-                padding: 0,
-            })
-            .collect();
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("simulation compute shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("./sim/shader.wgsl").into()),
-        });
-
-        // Create cell buffers with STORAGE usage
-        let buf_1 = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("cells buffer 1"),
-            contents: bytemuck::cast_slice(&start_data),
-            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
-        });
-
-        let buf_2 = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("cells buffer 2"),
-            size: buf_1.size(),
-            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-
-        let params_buf = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("simulation parameters buffer"),
-            contents: bytemuck::bytes_of(&parameters),
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-        });
-
-        // Cells bind group layout
-        let cells_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("cells bind group layout"),
-            entries: &[
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-
-        let cells_bg = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("cells bind group (buf1 -> buf2)"),
-            layout: &cells_bg_layout,
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: buf_1.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: buf_2.as_entire_binding(),
-                },
-            ],
-        });
-
-        let cells_bg_rev = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("cells bind group (buf2 -> buf1)"),
-            layout: &cells_bg_layout,
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: buf_2.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: buf_1.as_entire_binding(),
-                },
-            ],
-        });
-
-        // Parameters bind group
-        let params_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("parameters bind group layout"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-        let params_bg = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("parameters bind group"),
-            layout: &params_bg_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: params_buf.as_entire_binding(),
-            }],
-        });
-
-        // Time bind group
-        let time_buf = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("time buffer"),
-            contents: &[0, 0, 0, 0],
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-        });
-
-        let time_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("time bind group layout"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-        let time_bg = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("time bind group"),
-            layout: &time_bg_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: time_buf.as_entire_binding(),
-            }],
-        });
-
-        // Size bind group
-        let size_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("grid size bind group layout"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-        let size_buf = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("grid size buffer"),
-            contents: bytemuck::cast_slice(&[start.width as u32, start.height as u32]),
-            usage: BufferUsages::UNIFORM,
-        });
-
-        let size_bg = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("grid size bind group"),
-            layout: &size_bg_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: size_buf.as_entire_binding(),
-            }],
-        });
-
-        // Create compute pipeline
-        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
-            label: Some("simulation pipeline layout"),
-            bind_group_layouts: &[
-                &cells_bg_layout,
-                &params_bg_layout,
-                &size_bg_layout,
-                &time_bg_layout,
-            ],
-            push_constant_ranges: &[],
-        });
-
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("simulation compute pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: None,
-            compilation_options: Default::default(),
-            cache: None,
-        });
-
-        Ok(ComputeContextIntegrated {
-            buf_1,
-            buf_2,
-            cells_bg,
-            cells_bg_rev,
-            params_buf,
-            params_bind_group: params_bg,
-            size_bind_group: size_bg,
-            flipped_bufs: false,
-            time_bind_group: time_bg,
-            time_buf,
-            old_params: parameters,
-            pipeline,
-            steps: 0,
-        })
-    }
-
     fn create_render_context(
         device: &Device,
         surface: Surface<'static>,
@@ -426,8 +230,7 @@ impl GpuSimRenderer {
         surface_format: wgpu::TextureFormat,
         buf_1: &Buffer,
         buf_2: &Buffer,
-        grid_width: u32,
-        grid_height: u32,
+        size: RenderSize,
     ) -> Result<RenderContextIntegrated, anyhow::Error> {
         // Load render shader
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -451,52 +254,61 @@ impl GpuSimRenderer {
         });
 
         // Create bind groups for both buffers
-        let cells_bind_group_1 = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("render cells bind group (buf1)"),
-            layout: &cells_bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: buf_1.as_entire_binding(),
-            }],
-        });
-
-        let cells_bind_group_2 = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("render cells bind group (buf2)"),
-            layout: &cells_bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: buf_2.as_entire_binding(),
-            }],
-        });
+        let (cells_bind_group_1, cells_bind_group_2) =
+            Self::create_cells_bind_groups(device, &cells_bind_group_layout, buf_1, buf_2);
 
         // Size bind group
         let size_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("render size bind group layout"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
         });
 
         let size_buffer = device.create_buffer_init(&BufferInitDescriptor {
             label: Some("render size buffer"),
-            contents: bytemuck::cast_slice(&[grid_width, grid_height]),
+            contents: bytemuck::bytes_of(&size),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let storm_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("render storm buffer"),
+            contents: bytemuck::bytes_of(&GpuStormTracks::default()),
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
         let size_bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: Some("render size bind group"),
             layout: &size_bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: size_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: size_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: storm_buffer.as_entire_binding(),
+                },
+            ],
         });
 
         // Create render pipeline
@@ -548,62 +360,48 @@ impl GpuSimRenderer {
             surface,
             surface_config,
             render_pipeline,
+            cells_bind_group_layout,
             cells_bind_group_1,
             cells_bind_group_2,
             size_bind_group,
+            size_buffer,
+            storm_buffer,
         })
     }
 
-    /// Execute one simulation step
-    pub fn compute_step(&mut self, parameters: SimulationParameters) {
-        // Update parameters if changed
-        if parameters != self.compute.old_params {
-            self.compute.old_params = parameters;
-            self.queue
-                .write_buffer(&self.compute.params_buf, 0, bytemuck::bytes_of(&parameters));
-        }
-
-        // Update time
-        self.queue.write_buffer(
-            &self.compute.time_buf,
-            0,
-            bytemuck::bytes_of(&self.compute.steps),
-        );
-
-        let num_dispatches = (self.width * self.height).div_ceil(64) as u32;
-
-        let mut encoder = self
-            .device
-            .create_command_encoder(&CommandEncoderDescriptor {
-                label: Some("compute encoder"),
-            });
-
-        {
-            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("simulation step compute pass"),
-                ..Default::default()
-            });
-
-            pass.set_pipeline(&self.compute.pipeline);
-            pass.set_bind_group(
-                0,
-                if self.compute.flipped_bufs {
-                    &self.compute.cells_bg_rev
-                } else {
-                    &self.compute.cells_bg
-                },
-                &[],
-            );
-            pass.set_bind_group(1, &self.compute.params_bind_group, &[]);
-            pass.set_bind_group(2, &self.compute.size_bind_group, &[]);
-            pass.set_bind_group(3, &self.compute.time_bind_group, &[]);
-            pass.dispatch_workgroups(num_dispatches, 1, 1);
-        }
+    /// Create the render bind groups of the two cell buffers
+    fn create_cells_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buf_1: &Buffer,
+        buf_2: &Buffer,
+    ) -> (BindGroup, BindGroup) {
+        let cells_bind_group_1 = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("render cells bind group (buf1)"),
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buf_1.as_entire_binding(),
+            }],
+        });
 
-        self.queue.submit(std::iter::once(encoder.finish()));
+        let cells_bind_group_2 = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("render cells bind group (buf2)"),
+            layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: buf_2.as_entire_binding(),
+            }],
+        });
+        (cells_bind_group_1, cells_bind_group_2)
+    }
 
-        self.compute.flipped_bufs = !self.compute.flipped_bufs;
-        self.compute.steps += 1;
+    /// Execute one simulation step, first running any timeline events that
+    /// are due. Parameter changes from the timeline are applied to `config`.
+    pub fn compute_step(&mut self, config: &mut ConfigurableParameters) {
+        self.compute.advance_timeline(config);
+        self.set_neighbourhood(Neighbourhood::from(&*config));
+        self.compute.step(SimulationParameters::from(&*config));
     }
 
     /// Get current time in milliseconds
@@ -611,11 +409,14 @@ impl GpuSimRenderer {
         Date::now()
     }
 
-    /// Execute compute steps based on elapsed time and render
+    /// Execute compute steps based on elapsed time and render. Timeline
+    /// events run before the step they are due on, and parameter changes from
+    /// the timeline are applied to `config`.
     pub fn step_and_render(
         &mut self,
-        parameters: SimulationParameters,
+        config: &mut ConfigurableParameters,
     ) -> Result<(), wgpu::SurfaceError> {
+        let parameters = SimulationParameters::from(&*config);
         // Calculate elapsed time and steps to run
         let now = Self::now();
         let elapsed_ms = if self.last_frame_time == 0.0 {
@@ -645,6 +446,10 @@ impl GpuSimRenderer {
         } else {
             0 // tick_rate of 0 means paused
         };
+        let steps_to_run = match self.step_limit {
+            Some(limit) => steps_to_run.min(limit.saturating_sub(self.compute.steps())),
+            None => steps_to_run,
+        };
 
         // Log when parameters change
         if self.last_logged_params.as_ref() != Some(&parameters) {
@@ -677,15 +482,6 @@ impl GpuSimRenderer {
             self.last_tick_log_time = now;
         }
 
-        // Update parameters if changed
-        if parameters != self.compute.old_params {
-            self.compute.old_params = parameters;
-            self.queue
-                .write_buffer(&self.compute.params_buf, 0, bytemuck::bytes_of(&parameters));
-        }
-
-        let num_dispatches = (self.width * self.height).div_ceil(64) as u32;
-
         // Get surface texture
         let output = self.render.surface.get_current_texture()?;
         let view = output
@@ -699,50 +495,10 @@ impl GpuSimRenderer {
             });
 
         // Run multiple compute passes if needed
-        // Each step must be submitted separately so the time_buf write takes effect
-        // before the compute pass reads it (otherwise all passes see the last written value)
         for _ in 0..steps_to_run {
-            // Update time buffer for this step
-            self.queue.write_buffer(
-                &self.compute.time_buf,
-                0,
-                bytemuck::bytes_of(&self.compute.steps),
-            );
-
-            let mut step_encoder = self
-                .device
-                .create_command_encoder(&CommandEncoderDescriptor {
-                    label: Some("compute step encoder"),
-                });
-
-            {
-                let mut pass = step_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                    label: Some("simulation step compute pass"),
-                    ..Default::default()
-                });
-
-                pass.set_pipeline(&self.compute.pipeline);
-                pass.set_bind_group(
-                    0,
-                    if self.compute.flipped_bufs {
-                        &self.compute.cells_bg_rev
-                    } else {
-                        &self.compute.cells_bg
-                    },
-                    &[],
-                );
-                pass.set_bind_group(1, &self.compute.params_bind_group, &[]);
-                pass.set_bind_group(2, &self.compute.size_bind_group, &[]);
-                pass.set_bind_group(3, &self.compute.time_bind_group, &[]);
-                pass.dispatch_workgroups(num_dispatches, 1, 1);
-            }
-
-            // Submit each step separately so time_buf is correct for each pass
-            self.queue.submit(std::iter::once(step_encoder.finish()));
-
-            self.compute.flipped_bufs = !self.compute.flipped_bufs;
-            self.compute.steps += 1;
+            self.compute_step(config);
         }
+        self.write_storm_tracks();
 
         // Render pass - reads from the most recent output buffer
         {
@@ -771,7 +527,7 @@ impl GpuSimRenderer {
 
             // Read from the current output buffer (after all compute passes)
             // flipped_bufs now reflects the final state after all steps
-            let cells_bind_group = if self.compute.flipped_bufs {
+            let cells_bind_group = if self.compute.is_flipped() {
                 &self.render.cells_bind_group_2 // buf_2 has latest
             } else {
                 &self.render.cells_bind_group_1 // buf_1 has latest
@@ -795,6 +551,7 @@ impl GpuSimRenderer {
             .texture
             .create_view(&TextureViewDescriptor::default());
 
+        self.write_storm_tracks();
         let mut encoder = self
             .device
             .create_command_encoder(&CommandEncoderDescriptor {
@@ -829,7 +586,7 @@ impl GpuSimRenderer {
             // After step_and_render flips the flag:
             // - If flipped_bufs == true: last compute was buf1→buf2, so buf2 has latest
             // - If flipped_bufs == false: last compute was buf2→buf1, so buf1 has latest
-            let cells_bind_group = if self.compute.flipped_bufs {
+            let cells_bind_group = if self.compute.is_flipped() {
                 &self.render.cells_bind_group_2 // buf_2 has latest
             } else {
                 &self.render.cells_bind_group_1 // buf_1 has latest
@@ -846,6 +603,17 @@ impl GpuSimRenderer {
         Ok(())
     }
 
+    /// Update the storm tracks the next frame draws
+    fn write_storm_tracks(&self) {
+        let tracks = if self.show_storms {
+            self.compute.storm_tracks()
+        } else {
+            GpuStormTracks::default()
+        };
+        self.queue
+            .write_buffer(&self.render.storm_buffer, 0, bytemuck::bytes_of(&tracks));
+    }
+
     /// Resize the render surface
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
@@ -864,7 +632,145 @@ impl GpuSimRenderer {
 
     /// Get current step count
     pub fn steps(&self) -> u32 {
-        self.compute.steps
+        self.compute.steps()
+    }
+
+    /// Get the catalogue of completed fires
+    pub fn fire_catalogue(&self) -> &Arc<Mutex<FireCatalogue>> {
+        self.compute.fire_catalogue()
+    }
+
+    /// Get the log of cell events
+    pub fn event_log(&self) -> &Arc<Mutex<EventLog>> {
+        self.compute.event_log()
+    }
+
+    /// Get the record of fuel treatments carried out
+    pub fn treatment_log(&self) -> &Arc<Mutex<TreatmentLog>> {
+        self.compute.treatment_log()
+    }
+
+    /// Change the neighbourhood that fire spreads through. Switching to or
+    /// from a hexagonal grid also changes how the grid is drawn.
+    pub fn set_neighbourhood(&mut self, neighbourhood: Neighbourhood) {
+        if self.compute.set_neighbourhood(neighbourhood) {
+            self.queue.write_buffer(
+                &self.render.size_buffer,
+                offset_of!(RenderSize, hexagonal) as u64,
+                bytemuck::bytes_of(&(neighbourhood.is_hexagonal() as u32)),
+            );
+        }
+    }
+
+    /// Change what the renderer shows for each cell
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        self.queue.write_buffer(
+            &self.render.size_buffer,
+            offset_of!(RenderSize, render_mode) as u64,
+            bytemuck::bytes_of(&(mode as u32)),
+        );
+    }
+
+    /// Show or hide the tracks of recent storms
+    pub fn set_storm_overlay(&mut self, show: bool) {
+        self.show_storms = show;
+    }
+
+    /// Change where the fuel moisture of each step comes from
+    pub fn set_weather(&mut self, weather: WeatherSeries) {
+        self.compute.set_weather(weather);
+    }
+
+    /// Change the scripted climate years
+    pub fn set_climate(&mut self, climate: Climate) {
+        self.compute.set_climate(climate);
+    }
+
+    /// Change the scheduled fuel treatments
+    pub fn set_management(&mut self, management: Management) {
+        self.compute.set_management(management);
+    }
+
+    /// Replace the scripted timeline
+    pub fn set_timeline(&mut self, timeline: Timeline) {
+        self.compute.set_timeline(timeline);
+    }
+
+    /// Add an event to the scripted timeline
+    pub fn schedule(&mut self, event: TimedEvent) {
+        self.compute.schedule(event);
+    }
+
+    /// Get the snapshots taken by the timeline
+    pub fn snapshots(&self) -> &Arc<Mutex<SnapshotLog>> {
+        self.compute.snapshots()
+    }
+
+    /// Read the fire ledger and event buffer back, see
+    /// `SimulationPipeline::flush`
+    pub async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.compute.flush().await
+    }
+
+    /// Read back the current cell state, waiting for all submitted steps to
+    /// finish
+    pub fn read_frame(
+        &self,
+    ) -> impl Future<Output = Result<SimulationFrame, anyhow::Error>> + 'static {
+        self.compute.read_frame()
+    }
+
+    /// Read back a whole-grid summary, see `SimulationPipeline::read_summary`
+    pub fn read_summary(
+        &self,
+    ) -> impl Future<Output = Result<FrameSummary, anyhow::Error>> + 'static {
+        self.compute.read_summary()
+    }
+
+    /// Stop advancing the simulation once it has run `limit` steps
+    pub fn set_step_limit(&mut self, limit: Option<u32>) {
+        self.step_limit = limit;
+    }
+
+    /// Whether the simulation has reached its step limit
+    pub fn reached_step_limit(&self) -> bool {
+        self.step_limit
+            .is_some_and(|limit| self.compute.steps() >= limit)
+    }
+
+    /// Replace the simulation state with `frame`, which must be the same
+    /// size as the simulation grid
+    pub fn load_frame(&mut self, frame: &SimulationFrame) -> Result<(), anyhow::Error> {
+        self.compute.load_frame(frame)?;
+        self.accumulated_time = 0.0;
+        Ok(())
+    }
+
+    /// Replace the cell state with `frame`, which may be a different size
+    /// from the simulation grid, carrying the run on (see
+    /// `SimulationPipeline::resize`). The cell buffers and the bind groups
+    /// that use them are rebuilt to fit.
+    pub fn resize_grid(&mut self, frame: &SimulationFrame) -> Result<(), anyhow::Error> {
+        self.compute.resize(frame)?;
+        let (buf_1, buf_2) = self.compute.buffers();
+        (
+            self.render.cells_bind_group_1,
+            self.render.cells_bind_group_2,
+        ) = Self::create_cells_bind_groups(
+            &self.device,
+            &self.render.cells_bind_group_layout,
+            buf_1,
+            buf_2,
+        );
+        self.queue.write_buffer(
+            &self.render.size_buffer,
+            0,
+            bytemuck::cast_slice(&[frame.width as u32, frame.height as u32]),
+        );
+        self.width = frame.width;
+        self.height = frame.height;
+        self.accumulated_time = 0.0;
+        Ok(())
     }
 
     /// Get reference to device
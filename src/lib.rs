@@ -1,16 +1,21 @@
 #![feature(if_let_guard)]
-use std::sync::{Arc, atomic::AtomicBool};
+use std::sync::{Arc, Mutex, atomic::AtomicBool};
 
 use crate::{
     gpu::GpuSimRenderer,
+    rendering::RenderMode,
+    scenario::Scenario,
     sim::{
-        ConfigurableParameters, SimulationFrame, SimulationHandle, SimulationParameters,
-        SimulationStatistics,
+        Climate, ConfigurableParameters, EventLog, FastForwardState, FireCatalogue, Management,
+        Neighbourhood, ParameterSpec, RunOutcome, RunUntil, SimulationFrame, SimulationHandle,
+        SimulationParameters, SimulationStatistics, SnapshotLog, StopReason, Timeline,
+        TimelineEvent, TreatmentLog, WeatherSeries, fast_forward::FastForward,
     },
 };
 use futures_intrusive::channel::shared::OneshotSender;
 use wasm_bindgen::prelude::*;
 use watch::{WatchReceiver, WatchSender};
+#[cfg(target_arch = "wasm32")]
 use web_sys::HtmlCanvasElement;
 use web_sys::{DedicatedWorkerGlobalScope, Worker, WorkerOptions};
 use winit::{
@@ -19,10 +24,12 @@ use winit::{
     window::WindowAttributes,
 };
 
+#[cfg(target_arch = "wasm32")]
 use winit::platform::web::WindowAttributesExtWebSys;
 
 pub mod gpu;
 pub mod rendering;
+pub mod scenario;
 pub mod sim;
 pub mod util;
 
@@ -34,6 +41,8 @@ pub enum GpuMessage {
     Stop,
     Resume,
     SetParameters(ConfigurableParameters),
+    /// Replace the grid with a frame of a different size
+    ResizeGrid(SimulationFrame),
 }
 
 #[allow(dead_code)]
@@ -42,24 +51,38 @@ struct Application {
     proxy: Option<EventLoopProxy<GpuMessage>>,
     gpu_renderer: Option<GpuSimRenderer>,
     config_params: ConfigurableParameters,
+    /// The first frame of the run, taken when the renderer is created
+    start_frame: Option<SimulationFrame>,
+    /// The number of steps after which the run stops, if it has a fixed length
+    step_limit: Option<u32>,
+    /// Where the fuel moisture of each step comes from
+    weather: WeatherSeries,
+    /// Scripted drought years
+    climate: Climate,
+    /// Scheduled fuel treatments
+    management: Management,
+    /// Scripted events
+    timeline: Timeline,
     paused: bool,
     stopped: bool,
 }
 
 impl Application {
-    fn new(event_loop: &EventLoop<GpuMessage>) -> Self {
-        const SIM_WIDTH: usize = 500;
-        const SIM_HEIGHT: usize = 500;
-
-        let sim_params = ConfigurableParameters::realistic(SIM_WIDTH, SIM_HEIGHT, 2.0, 36.0);
-        Self {
+    fn new(event_loop: &EventLoop<GpuMessage>, scenario: &Scenario) -> Result<Self, anyhow::Error> {
+        Ok(Self {
             simulation: None,
             proxy: Some(event_loop.create_proxy()),
             gpu_renderer: None,
-            config_params: sim_params,
+            config_params: scenario.parameters(),
+            start_frame: Some(scenario.initial_frame()?),
+            step_limit: scenario.total_steps(),
+            weather: scenario.weather.clone(),
+            climate: scenario.climate.clone(),
+            management: scenario.management.clone(),
+            timeline: scenario.timeline.clone(),
             paused: false,
             stopped: false,
-        }
+        })
     }
 
     /// Process any pending control messages from JavaScript
@@ -90,10 +113,81 @@ impl Application {
                         self.config_params = params;
                         log::debug!("Parameters updated");
                     }
+                    ControlMessage::SetRenderMode(mode) => {
+                        if let Some(ref mut renderer) = self.gpu_renderer {
+                            renderer.set_render_mode(mode);
+                        }
+                    }
+                    ControlMessage::SetStormOverlay(show) => {
+                        if let Some(ref mut renderer) = self.gpu_renderer {
+                            renderer.set_storm_overlay(show);
+                        }
+                    }
+                    ControlMessage::SetWeather(weather) => {
+                        if let Some(ref mut renderer) = self.gpu_renderer {
+                            renderer.set_weather(weather.clone());
+                        }
+                        self.weather = weather;
+                    }
+                    ControlMessage::SetTimeline(timeline) => {
+                        if let Some(ref mut renderer) = self.gpu_renderer {
+                            renderer.set_timeline(timeline.clone());
+                        }
+                        self.timeline = timeline;
+                    }
+                    ControlMessage::ResizeGrid {
+                        width,
+                        height,
+                        resample,
+                    } => self.resize_grid(width, height, resample),
+                }
+            }
+        });
+    }
+
+    /// Replace the grid with one of the given size, resampling the current
+    /// grid once it has been read back if asked to
+    fn resize_grid(&mut self, width: usize, height: usize, resample: bool) {
+        let Some(ref mut renderer) = self.gpu_renderer else {
+            return;
+        };
+        if !resample {
+            self.apply_grid(SimulationFrame::new(width, height));
+            return;
+        }
+        let Some(proxy) = self.proxy.clone() else {
+            return;
+        };
+        let frame = renderer.read_frame();
+        wasm_bindgen_futures::spawn_local(async move {
+            match frame.await {
+                Ok(frame) => {
+                    let frame = frame.resample(width, height);
+                    let _ = proxy.send_event(GpuMessage::ResizeGrid(frame));
                 }
+                Err(e) => log::error!("failed to read back the grid to resize it: {e}"),
             }
         });
     }
+
+    /// Rebuild the renderer around `frame`
+    fn apply_grid(&mut self, frame: SimulationFrame) {
+        let Some(ref mut renderer) = self.gpu_renderer else {
+            return;
+        };
+        match renderer.resize_grid(&frame) {
+            Ok(()) => {
+                self.config_params
+                    .set_forest_size(frame.width, frame.height);
+                log::info!("Grid resized to {}x{}", frame.width, frame.height);
+                if self.stopped {
+                    self.stopped = false;
+                    renderer.request_redraw();
+                }
+            }
+            Err(e) => log::error!("failed to resize the grid: {e}"),
+        }
+    }
 }
 
 impl winit::application::ApplicationHandler<GpuMessage> for Application {
@@ -101,28 +195,46 @@ impl winit::application::ApplicationHandler<GpuMessage> for Application {
         if self.gpu_renderer.is_some() {
             return;
         }
+        let Some(start_frame) = self.start_frame.take() else {
+            return;
+        };
 
-        let dom_window = web_sys::window().expect("could not get window");
-        let canvas: HtmlCanvasElement = dom_window
-            .document()
-            .expect("could not get document")
-            .get_element_by_id("sim-surface")
-            .expect("could not get element with id `sim-surface` as required")
-            .dyn_into()
-            .expect("`sim-surface` is not a canvas");
-        let window_attrs = WindowAttributes::default().with_canvas(Some(canvas));
+        #[cfg(target_arch = "wasm32")]
+        let window_attrs = {
+            let dom_window = web_sys::window().expect("could not get window");
+            let canvas: HtmlCanvasElement = dom_window
+                .document()
+                .expect("could not get document")
+                .get_element_by_id("sim-surface")
+                .expect("could not get element with id `sim-surface` as required")
+                .dyn_into()
+                .expect("`sim-surface` is not a canvas");
+            WindowAttributes::default().with_canvas(Some(canvas))
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let window_attrs = WindowAttributes::default();
         match event_loop.create_window(window_attrs) {
             Ok(window) => {
-                if let Some(proxy) = self.proxy.take() {
+                if let Some(proxy) = self.proxy.clone() {
                     let window = Arc::new(window);
-                    let config = self.config_params.clone();
-                    let sim_params = SimulationParameters::from(&config);
-                    let start_frame =
-                        SimulationFrame::new(config.forest_width, config.forest_height);
+                    let sim_params = SimulationParameters::from(&self.config_params);
+                    let neighbourhood = Neighbourhood::from(&self.config_params);
+                    let timeline = self.timeline.clone();
+                    let step_limit = self.step_limit;
+                    let weather = self.weather.clone();
+                    let climate = self.climate.clone();
+                    let management = self.management.clone();
 
                     wasm_bindgen_futures::spawn_local(async move {
-                        match GpuSimRenderer::new(window, start_frame, sim_params).await {
-                            Ok(renderer) => {
+                        match GpuSimRenderer::new(window, start_frame, sim_params, neighbourhood)
+                            .await
+                        {
+                            Ok(mut renderer) => {
+                                renderer.set_step_limit(step_limit);
+                                renderer.set_weather(weather);
+                                renderer.set_climate(climate);
+                                renderer.set_management(management);
+                                renderer.set_timeline(timeline);
                                 let _ = proxy.send_event(GpuMessage::Initialized(renderer));
                             }
                             Err(e) => {
@@ -169,9 +281,11 @@ impl winit::application::ApplicationHandler<GpuMessage> for Application {
                         r
                     } else {
                         // Running: step and render
-                        let sim_params = SimulationParameters::from(&self.config_params);
-                        let r = renderer.step_and_render(sim_params);
-                        if r.is_ok() {
+                        let r = renderer.step_and_render(&mut self.config_params);
+                        if renderer.reached_step_limit() {
+                            self.stopped = true;
+                            log::info!("Simulation finished after {} steps", renderer.steps());
+                        } else if r.is_ok() {
                             renderer.request_redraw();
                         }
                         r
@@ -208,6 +322,18 @@ impl winit::application::ApplicationHandler<GpuMessage> for Application {
         match event {
             GpuMessage::Initialized(renderer) => {
                 log::info!("GPU renderer initialized successfully");
+                FIRE_CATALOGUE.with(|fires| {
+                    *fires.borrow_mut() = Some(Arc::clone(renderer.fire_catalogue()));
+                });
+                EVENT_LOG.with(|events| {
+                    *events.borrow_mut() = Some(Arc::clone(renderer.event_log()));
+                });
+                TREATMENT_LOG.with(|log| {
+                    *log.borrow_mut() = Some(Arc::clone(renderer.treatment_log()));
+                });
+                SNAPSHOTS.with(|snapshots| {
+                    *snapshots.borrow_mut() = Some(Arc::clone(renderer.snapshots()));
+                });
                 // Request first redraw to kick off the animation loop
                 renderer.request_redraw();
                 self.gpu_renderer = Some(renderer);
@@ -241,6 +367,7 @@ impl winit::application::ApplicationHandler<GpuMessage> for Application {
                 self.config_params = params;
                 log::debug!("Parameters updated");
             }
+            GpuMessage::ResizeGrid(frame) => self.apply_grid(frame),
         }
     }
 }
@@ -270,15 +397,7 @@ pub fn worker_entry(ptr: u32) -> Result<(), JsValue> {
     let ptr = unsafe { Box::from_raw(ptr as *mut SimWorkerArgs) };
     let global = js_sys::global().unchecked_into::<DedicatedWorkerGlobalScope>();
     wasm_bindgen_futures::spawn_local(async move {
-        sim::sim_thread(
-            ptr.parameters_rx,
-            ptr.stop,
-            ptr.latest_frame_tx,
-            ptr.latest_frame_rx,
-            ptr.stats_tx,
-            ptr.wants_new_frame,
-        )
-        .await;
+        sim::sim_thread(*ptr).await;
     });
     global.post_message(&JsValue::undefined())?;
     Ok(())
@@ -304,6 +423,11 @@ struct SimWorkerArgs {
     latest_frame_rx: WatchReceiver<SimulationFrame>,
     stats_tx: OneshotSender<SimulationStatistics>,
     wants_new_frame: Arc<AtomicBool>,
+    fires: Arc<Mutex<FireCatalogue>>,
+    events: Arc<Mutex<EventLog>>,
+    timeline_rx: WatchReceiver<Timeline>,
+    snapshots: Arc<Mutex<SnapshotLog>>,
+    fast_forward: Arc<Mutex<FastForwardState>>,
 }
 
 #[wasm_bindgen(start)]
@@ -330,12 +454,25 @@ enum ControlMessage {
     Stop,
     Resume,
     SetParameters(ConfigurableParameters),
+    SetRenderMode(RenderMode),
+    SetStormOverlay(bool),
+    SetWeather(WeatherSeries),
+    SetTimeline(Timeline),
+    ResizeGrid {
+        width: usize,
+        height: usize,
+        resample: bool,
+    },
 }
 
 // Thread-local storage for control messages (WASM is single-threaded)
 thread_local! {
     static CONTROL_QUEUE: std::cell::RefCell<Vec<ControlMessage>> = std::cell::RefCell::new(Vec::new());
     static PARAMS_STORE: std::cell::RefCell<Option<ConfigurableParameters>> = const { std::cell::RefCell::new(None) };
+    static FIRE_CATALOGUE: std::cell::RefCell<Option<Arc<Mutex<FireCatalogue>>>> = const { std::cell::RefCell::new(None) };
+    static EVENT_LOG: std::cell::RefCell<Option<Arc<Mutex<EventLog>>>> = const { std::cell::RefCell::new(None) };
+    static TREATMENT_LOG: std::cell::RefCell<Option<Arc<Mutex<TreatmentLog>>>> = const { std::cell::RefCell::new(None) };
+    static SNAPSHOTS: std::cell::RefCell<Option<Arc<Mutex<SnapshotLog>>>> = const { std::cell::RefCell::new(None) };
 }
 
 /// Controller for the running simulation
@@ -362,82 +499,130 @@ impl SimulationController {
         CONTROL_QUEUE.with(|q| q.borrow_mut().push(ControlMessage::Resume));
     }
 
-    /// Set lightning frequency (strikes per year per acre)
+    /// Set a parameter by its `ConfigurableParameters` field name. See
+    /// `parameter_schema` for the available parameters. Values outside the
+    /// parameter's valid range are rejected with an error naming the range.
     #[wasm_bindgen]
-    pub fn set_lightning_frequency(value: f32) {
-        Self::update_param(|p| p.lightning_strikes_per_year_per_acre = value);
+    pub fn set_parameter(name: &str, value: f64) -> Result<(), JsValue> {
+        let spec = ParameterSpec::find(name).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        spec.check(value)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Self::update_param(|p| (spec.set)(p, value));
+        Ok(())
     }
 
-    /// Set fire spread rate (0-1)
+    /// Get the current value of a parameter by its field name, if the
+    /// simulation has started
     #[wasm_bindgen]
-    pub fn set_fire_spread_rate(value: f32) {
-        Self::update_param(|p| p.fire_spread_rate = value);
+    pub fn get_parameter(name: &str) -> Result<Option<f64>, JsValue> {
+        let spec = ParameterSpec::find(name).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(PARAMS_STORE.with(|store| store.borrow().as_ref().map(spec.get)))
     }
 
-    /// Set tree growth years
+    /// Change what the renderer shows for each cell
     #[wasm_bindgen]
-    pub fn set_tree_growth_years(value: f32) {
-        Self::update_param(|p| p.tree_growth_years = value);
+    pub fn set_render_mode(mode: RenderMode) {
+        CONTROL_QUEUE.with(|q| q.borrow_mut().push(ControlMessage::SetRenderMode(mode)));
     }
 
-    /// Set tree death years
+    /// Show or hide the tracks of recent lightning storms
     #[wasm_bindgen]
-    pub fn set_tree_death_years(value: f32) {
-        Self::update_param(|p| p.tree_death_years = value);
+    pub fn set_storm_overlay(show: bool) {
+        CONTROL_QUEUE.with(|q| q.borrow_mut().push(ControlMessage::SetStormOverlay(show)));
     }
 
-    /// Set months per second (simulation speed)
+    /// Drive fuel moisture from a daily CSV series instead of the scenario's
+    /// weather. See `WeatherSeries::from_csv` for the format.
     #[wasm_bindgen]
-    pub fn set_months_per_second(value: f32) {
-        Self::update_param(|p| p.months_per_second = value);
+    pub fn set_weather_csv(source: &str) -> Result<(), JsValue> {
+        let weather =
+            WeatherSeries::from_csv(source).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        CONTROL_QUEUE.with(|q| q.borrow_mut().push(ControlMessage::SetWeather(weather)));
+        Ok(())
     }
 
-    /// Set tree flammability
+    /// Get a snapshot of the catalogue of completed fires, if the simulation
+    /// has started
     #[wasm_bindgen]
-    pub fn set_tree_flammability(value: f32) {
-        Self::update_param(|p| p.tree_flammability = value);
-    }
-
-    /// Set underbrush flammability
-    #[wasm_bindgen]
-    pub fn set_underbrush_flammability(value: f32) {
-        Self::update_param(|p| p.underbrush_flammability = value);
-    }
-
-    /// Set underbrush tree growth hindrance
-    #[wasm_bindgen]
-    pub fn set_underbrush_tree_growth_hindrance(value: f32) {
-        Self::update_param(|p| p.underbrush_tree_growth_hindrance = value);
+    pub fn fire_catalogue() -> Option<FireCatalogue> {
+        FIRE_CATALOGUE.with(|fires| {
+            fires
+                .borrow()
+                .as_ref()
+                .map(|fires| fires.lock().expect("failed to lock fire catalogue").clone())
+        })
     }
 
-    /// Set tree underbrush generation
+    /// Get a copy of the log of cell events, if the simulation has started
     #[wasm_bindgen]
-    pub fn set_tree_underbrush_generation(value: f32) {
-        Self::update_param(|p| p.tree_underbrush_generation = value);
+    pub fn event_log() -> Option<EventLog> {
+        EVENT_LOG.with(|events| {
+            events
+                .borrow()
+                .as_ref()
+                .map(|events| events.lock().expect("failed to lock event log").clone())
+        })
     }
 
-    /// Set tree death underbrush
+    /// Get a snapshot of the record of fuel treatments, if the simulation has
+    /// started
     #[wasm_bindgen]
-    pub fn set_tree_death_underbrush(value: f32) {
-        Self::update_param(|p| p.tree_death_underbrush = value);
+    pub fn treatment_log() -> Option<TreatmentLog> {
+        TREATMENT_LOG.with(|log| {
+            log.borrow()
+                .as_ref()
+                .map(|log| log.lock().expect("failed to lock treatment log").clone())
+        })
     }
 
-    /// Set tree fire duration
+    /// Replace the scripted timeline with one parsed from TOML or JSON. See
+    /// `Timeline` for the format. Events whose time has already passed run
+    /// before the next step.
     #[wasm_bindgen]
-    pub fn set_tree_fire_duration(value: u32) {
-        Self::update_param(|p| p.tree_fire_duration = value);
+    pub fn load_timeline(source: &str) -> Result<(), JsValue> {
+        let timeline = Timeline::parse(source).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        PARAMS_STORE.with(|store| match *store.borrow() {
+            Some(ref params) => timeline
+                .validate(params.forest_width, params.forest_height)
+                .map_err(|e| JsValue::from_str(&e.to_string())),
+            None => Ok(()),
+        })?;
+        CONTROL_QUEUE.with(|q| q.borrow_mut().push(ControlMessage::SetTimeline(timeline)));
+        Ok(())
     }
 
-    /// Set underbrush fire duration
+    /// Carry the simulation on with a grid of a different size. With
+    /// `resample`, the current grid is stretched to fit the new size;
+    /// otherwise the new grid starts out as bare ground. Fires that were
+    /// burning are put out.
     #[wasm_bindgen]
-    pub fn set_underbrush_fire_duration(value: u32) {
-        Self::update_param(|p| p.underbrush_fire_duration = value);
+    pub fn resize_grid(width: usize, height: usize, resample: bool) -> Result<(), JsValue> {
+        if width == 0 || height == 0 {
+            return Err(JsValue::from_str(&format!(
+                "the grid must be at least 1x1, not {width}x{height}"
+            )));
+        }
+        Self::update_param(|p| p.set_forest_size(width, height));
+        CONTROL_QUEUE.with(|q| {
+            q.borrow_mut().push(ControlMessage::ResizeGrid {
+                width,
+                height,
+                resample,
+            })
+        });
+        Ok(())
     }
 
-    /// Set ticks per month (simulation resolution)
+    /// Get a copy of the snapshots taken by the timeline, if the simulation
+    /// has started
     #[wasm_bindgen]
-    pub fn set_ticks_per_month(value: f32) {
-        Self::update_param(|p| p.ticks_per_month = value);
+    pub fn snapshots() -> Option<SnapshotLog> {
+        SNAPSHOTS.with(|snapshots| {
+            snapshots
+                .borrow()
+                .as_ref()
+                .map(|log| log.lock().expect("failed to lock snapshot log").clone())
+        })
     }
 
     fn update_param<F: FnOnce(&mut ConfigurableParameters)>(f: F) {
@@ -460,16 +645,30 @@ impl SimulationController {
 /// with integrated GPU compute and rendering.
 #[wasm_bindgen]
 pub fn start() {
+    start_scenario(&Scenario::default()).expect("the default scenario is valid");
+}
+
+/// Start the fire simulation from a scenario file (TOML or JSON)
+#[wasm_bindgen]
+pub fn start_with_scenario(source: &str) -> Result<(), JsValue> {
+    let scenario = Scenario::parse(source).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    start_scenario(&scenario).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+fn start_scenario(scenario: &Scenario) -> Result<(), anyhow::Error> {
     use winit::event_loop::EventLoop;
 
     log::info!("Starting fire simulation with GPU rendering");
+    if !scenario.name.is_empty() {
+        log::info!("Scenario: {}", scenario.name);
+    }
 
     let event_loop = EventLoop::<GpuMessage>::with_user_event()
         .build()
         .expect("Failed to create event loop");
 
     #[allow(unused_mut)]
-    let mut app = Application::new(&event_loop);
+    let mut app = Application::new(&event_loop, scenario)?;
 
     // Store initial parameters for controller access
     PARAMS_STORE.with(|store| {
@@ -477,8 +676,14 @@ pub fn start() {
     });
 
     // On web, we need to spawn the event loop
-    use winit::platform::web::EventLoopExtWebSys;
-    event_loop.spawn_app(app);
+    #[cfg(target_arch = "wasm32")]
+    {
+        use winit::platform::web::EventLoopExtWebSys;
+        event_loop.spawn_app(app);
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    event_loop.run_app(&mut app)?;
+    Ok(())
 }
 
 /// Standalone GPU simulation and renderer for more control
@@ -492,6 +697,36 @@ pub struct GpuSimulation {
     stopped: bool,
 }
 
+impl GpuSimulation {
+    fn apply_scenario(&mut self, scenario: &Scenario) -> Result<(), anyhow::Error> {
+        // A scenario is a new run, so start it over after resizing
+        let frame = scenario.initial_frame()?;
+        self.renderer.resize_grid(&frame)?;
+        self.renderer.load_frame(&frame)?;
+        self.renderer.set_step_limit(scenario.total_steps());
+        self.renderer.set_weather(scenario.weather.clone());
+        self.renderer.set_climate(scenario.climate.clone());
+        self.renderer.set_management(scenario.management.clone());
+        self.renderer.set_timeline(scenario.timeline.clone());
+        self.config_params = scenario.parameters();
+        self.stopped = false;
+        Ok(())
+    }
+
+    /// Add a single event to the timeline, checking it first
+    fn schedule(&mut self, year: f32, event: TimelineEvent) -> Result<(), JsValue> {
+        let (width, height) = self.renderer.dimensions();
+        let mut timeline = Timeline::default();
+        timeline.push(year, event);
+        timeline
+            .validate(width, height)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.renderer
+            .schedule(timeline.events.pop().expect("the event was just pushed"));
+        Ok(())
+    }
+}
+
 #[wasm_bindgen]
 impl GpuSimulation {
     /// Run one simulation step and render the result
@@ -503,9 +738,8 @@ impl GpuSimulation {
         if self.paused {
             return self.render();
         }
-        let sim_params = SimulationParameters::from(&self.config_params);
         self.renderer
-            .step_and_render(sim_params)
+            .step_and_render(&mut self.config_params)
             .map_err(|e| JsValue::from_str(&format!("Render error: {e:?}")))
     }
 
@@ -515,8 +749,7 @@ impl GpuSimulation {
         if self.stopped || self.paused {
             return;
         }
-        let sim_params = SimulationParameters::from(&self.config_params);
-        self.renderer.compute_step(sim_params);
+        self.renderer.compute_step(&mut self.config_params);
     }
 
     /// Render the current state without advancing simulation
@@ -576,160 +809,215 @@ impl GpuSimulation {
         self.paused = false;
     }
 
-    /// Set lightning frequency
-    #[wasm_bindgen]
-    pub fn set_lightning_frequency(&mut self, strikes_per_year_per_acre: f32) {
-        self.config_params.lightning_strikes_per_year_per_acre = strikes_per_year_per_acre;
-    }
-
-    /// Get lightning frequency
-    #[wasm_bindgen]
-    pub fn get_lightning_frequency(&self) -> f32 {
-        self.config_params.lightning_strikes_per_year_per_acre
-    }
-
-    /// Set fire spread rate
-    #[wasm_bindgen]
-    pub fn set_fire_spread_rate(&mut self, rate: f32) {
-        self.config_params.fire_spread_rate = rate;
-    }
-
-    /// Get fire spread rate
-    #[wasm_bindgen]
-    pub fn get_fire_spread_rate(&self) -> f32 {
-        self.config_params.fire_spread_rate
-    }
-
-    /// Set tree growth rate in years
-    #[wasm_bindgen]
-    pub fn set_tree_growth_years(&mut self, years: f32) {
-        self.config_params.tree_growth_years = years;
-    }
-
-    /// Get tree growth years
-    #[wasm_bindgen]
-    pub fn get_tree_growth_years(&self) -> f32 {
-        self.config_params.tree_growth_years
-    }
-
-    /// Set tree death years
-    #[wasm_bindgen]
-    pub fn set_tree_death_years(&mut self, years: f32) {
-        self.config_params.tree_death_years = years;
-    }
-
-    /// Get tree death years
-    #[wasm_bindgen]
-    pub fn get_tree_death_years(&self) -> f32 {
-        self.config_params.tree_death_years
-    }
-
-    /// Set simulation speed (months per second)
+    /// Set a parameter by its `ConfigurableParameters` field name. See
+    /// `parameter_schema` for the available parameters. Values outside the
+    /// parameter's valid range are rejected with an error naming the range.
     #[wasm_bindgen]
-    pub fn set_months_per_second(&mut self, months: f32) {
-        self.config_params.months_per_second = months;
+    pub fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), JsValue> {
+        self.config_params
+            .set_parameter(name, value)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
-    /// Get months per second
+    /// Get a parameter by its field name
     #[wasm_bindgen]
-    pub fn get_months_per_second(&self) -> f32 {
-        self.config_params.months_per_second
+    pub fn get_parameter(&self, name: &str) -> Result<f64, JsValue> {
+        self.config_params
+            .get_parameter(name)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
-    /// Set ticks per month (simulation resolution)
+    /// Change what the renderer shows for each cell
     #[wasm_bindgen]
-    pub fn set_ticks_per_month(&mut self, value: f32) {
-        self.config_params.ticks_per_month = value;
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        self.renderer.set_render_mode(mode);
     }
 
-    /// Get ticks per month
+    /// Show or hide the tracks of recent lightning storms
     #[wasm_bindgen]
-    pub fn get_ticks_per_month(&self) -> f32 {
-        self.config_params.ticks_per_month
+    pub fn set_storm_overlay(&mut self, show: bool) {
+        self.renderer.set_storm_overlay(show);
     }
 
-    /// Set tree flammability
+    /// Drive fuel moisture from a daily CSV series instead of the scenario's
+    /// weather. See `WeatherSeries::from_csv` for the format.
     #[wasm_bindgen]
-    pub fn set_tree_flammability(&mut self, value: f32) {
-        self.config_params.tree_flammability = value;
+    pub fn set_weather_csv(&mut self, source: &str) -> Result<(), JsValue> {
+        let weather =
+            WeatherSeries::from_csv(source).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.renderer.set_weather(weather);
+        Ok(())
     }
 
-    /// Get tree flammability
+    /// Restart the simulation from a scenario file (TOML or JSON). The grid
+    /// is resized to the scenario's if they differ.
     #[wasm_bindgen]
-    pub fn get_tree_flammability(&self) -> f32 {
-        self.config_params.tree_flammability
+    pub fn load_scenario(&mut self, source: &str) -> Result<(), JsValue> {
+        Scenario::parse(source)
+            .and_then(|scenario| self.apply_scenario(&scenario))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
-    /// Set underbrush flammability
+    /// Carry the simulation on with a grid of a different size. With
+    /// `resample`, the current grid is stretched to fit the new size;
+    /// otherwise the new grid starts out as bare ground. Fires that were
+    /// burning are put out. The step count, timeline, weather, treatments,
+    /// fire catalogue and event log carry on where they were.
     #[wasm_bindgen]
-    pub fn set_underbrush_flammability(&mut self, value: f32) {
-        self.config_params.underbrush_flammability = value;
-    }
-
-    /// Get underbrush flammability
-    #[wasm_bindgen]
-    pub fn get_underbrush_flammability(&self) -> f32 {
-        self.config_params.underbrush_flammability
-    }
-
-    /// Set underbrush tree growth hindrance
-    #[wasm_bindgen]
-    pub fn set_underbrush_tree_growth_hindrance(&mut self, value: f32) {
-        self.config_params.underbrush_tree_growth_hindrance = value;
+    pub async fn resize_grid(
+        &mut self,
+        width: usize,
+        height: usize,
+        resample: bool,
+    ) -> Result<(), JsValue> {
+        let frame = if resample {
+            self.renderer
+                .read_frame()
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))?
+                .resample(width, height)
+        } else {
+            SimulationFrame::new(width, height)
+        };
+        self.renderer
+            .resize_grid(&frame)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.config_params.set_forest_size(width, height);
+        Ok(())
     }
 
-    /// Get underbrush tree growth hindrance
+    /// Replace the scripted timeline with one parsed from TOML or JSON. See
+    /// `Timeline` for the format. Events whose time has already passed run
+    /// before the next step.
     #[wasm_bindgen]
-    pub fn get_underbrush_tree_growth_hindrance(&self) -> f32 {
-        self.config_params.underbrush_tree_growth_hindrance
+    pub fn load_timeline(&mut self, source: &str) -> Result<(), JsValue> {
+        let (width, height) = self.renderer.dimensions();
+        let timeline = Timeline::parse(source)
+            .and_then(|timeline| {
+                timeline.validate(width, height)?;
+                Ok(timeline)
+            })
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.renderer.set_timeline(timeline);
+        Ok(())
     }
 
-    /// Set tree underbrush generation
+    /// Set a parameter by its field name at the given simulated year
     #[wasm_bindgen]
-    pub fn set_tree_underbrush_generation(&mut self, value: f32) {
-        self.config_params.tree_underbrush_generation = value;
+    pub fn schedule_parameter(&mut self, year: f32, name: &str, value: f64) -> Result<(), JsValue> {
+        self.schedule(
+            year,
+            TimelineEvent::SetParameter {
+                parameter: name.to_string(),
+                value,
+            },
+        )
     }
 
-    /// Get tree underbrush generation
+    /// Start a fire in the cell at `(x, y)` at the given simulated year
     #[wasm_bindgen]
-    pub fn get_tree_underbrush_generation(&self) -> f32 {
-        self.config_params.tree_underbrush_generation
+    pub fn schedule_ignition(&mut self, year: f32, x: u32, y: u32) -> Result<(), JsValue> {
+        self.schedule(year, TimelineEvent::Ignite { x, y })
     }
 
-    /// Set tree death underbrush
+    /// Take a snapshot of the grid at the given simulated year
     #[wasm_bindgen]
-    pub fn set_tree_death_underbrush(&mut self, value: f32) {
-        self.config_params.tree_death_underbrush = value;
+    pub fn schedule_snapshot(&mut self, year: f32, label: String) -> Result<(), JsValue> {
+        self.schedule(year, TimelineEvent::Snapshot { label })
     }
 
-    /// Get tree death underbrush
+    /// Run as fast as possible until `until` says to stop, rendering and
+    /// calling `progress` with a `RunProgress` after every check. Cancel the
+    /// run with the handle from `RunUntil::cancel_handle`. The simulation
+    /// can't be used from JS until the run stops.
     #[wasm_bindgen]
-    pub fn get_tree_death_underbrush(&self) -> f32 {
-        self.config_params.tree_death_underbrush
+    pub async fn run_until(
+        &mut self,
+        until: RunUntil,
+        progress: Option<js_sys::Function>,
+    ) -> Result<RunOutcome, JsValue> {
+        until
+            .validate()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let mut run = FastForward::new(until, self.renderer.steps());
+        loop {
+            let ticks_per_year = self.config_params.ticks_per_month * 12.0;
+            for _ in 0..run.batch_steps(self.renderer.steps(), ticks_per_year) {
+                if self.renderer.reached_step_limit() {
+                    break;
+                }
+                self.renderer.compute_step(&mut self.config_params);
+            }
+            // So that the fire catalogue and event log are up to date with
+            // the summary
+            self.renderer
+                .flush()
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            let summary = self
+                .renderer
+                .read_summary()
+                .await
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            let (report, reason) = run.check(self.renderer.steps(), summary, ticks_per_year);
+            let reason = reason.or(self
+                .renderer
+                .reached_step_limit()
+                .then_some(StopReason::StepLimit));
+            if let Err(e) = self.renderer.render() {
+                log::warn!("Surface error: {e:?}");
+            }
+            if let Some(callback) = &progress {
+                callback.call1(&JsValue::NULL, &report.into())?;
+            }
+            if let Some(reason) = reason {
+                return Ok(RunOutcome {
+                    reason,
+                    years: report.years,
+                    summary,
+                });
+            }
+        }
     }
 
-    /// Set tree fire duration in ticks
+    /// Get a copy of the snapshots taken by the timeline so far
     #[wasm_bindgen]
-    pub fn set_tree_fire_duration(&mut self, value: u32) {
-        self.config_params.tree_fire_duration = value;
+    pub fn snapshots(&self) -> SnapshotLog {
+        self.renderer
+            .snapshots()
+            .lock()
+            .expect("failed to lock snapshot log")
+            .clone()
     }
 
-    /// Get tree fire duration
+    /// Get a snapshot of the catalogue of completed fires
     #[wasm_bindgen]
-    pub fn get_tree_fire_duration(&self) -> u32 {
-        self.config_params.tree_fire_duration
+    pub fn fire_catalogue(&self) -> FireCatalogue {
+        self.renderer
+            .fire_catalogue()
+            .lock()
+            .expect("failed to lock fire catalogue")
+            .clone()
     }
 
-    /// Set underbrush fire duration in ticks
+    /// Get a copy of the log of cell events
     #[wasm_bindgen]
-    pub fn set_underbrush_fire_duration(&mut self, value: u32) {
-        self.config_params.underbrush_fire_duration = value;
+    pub fn event_log(&self) -> EventLog {
+        self.renderer
+            .event_log()
+            .lock()
+            .expect("failed to lock event log")
+            .clone()
     }
 
-    /// Get underbrush fire duration
+    /// Get a snapshot of the record of fuel treatments
     #[wasm_bindgen]
-    pub fn get_underbrush_fire_duration(&self) -> u32 {
-        self.config_params.underbrush_fire_duration
+    pub fn treatment_log(&self) -> TreatmentLog {
+        self.renderer
+            .treatment_log()
+            .lock()
+            .expect("failed to lock treatment log")
+            .clone()
     }
 
     /// Get forest width
@@ -747,6 +1035,12 @@ impl GpuSimulation {
     /// Get forest acres
     #[wasm_bindgen]
     pub fn get_forest_acres(&self) -> f32 {
-        self.config_params.forest_acres
+        self.config_params.forest_acres()
+    }
+
+    /// Get forest hectares
+    #[wasm_bindgen]
+    pub fn get_forest_hectares(&self) -> f32 {
+        self.config_params.forest_hectares()
     }
 }
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use wasm_bindgen::prelude::*;
 use winit::window::Window;
 
 // Keep the old types for compatibility, but mark them as deprecated
@@ -25,6 +26,24 @@ pub enum RenderSectioning {
     Multiple([Option<RenderMode>; 4]),
 }
 
+/// What the renderer shows for each cell
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u32)]
 pub enum RenderMode {
-    Standard,
+    /// Fires, trees and underbrush
+    #[default]
+    Standard = 0,
+    /// The age of each tree, from young (yellow) to old (purple)
+    Age = 1,
+    /// The health of each tree, from dying (red) to healthy (green)
+    Health = 2,
+    /// The alert signalled between trees, from calm (dark green) to fully
+    /// alert (cyan)
+    Alert = 3,
+    /// Surface fires (amber) and crown fires (crimson) over dimmed fuel
+    Fire = 4,
+    /// The severity of the last fire in each cell, in the classes of a dNBR
+    /// burn severity map
+    Severity = 5,
 }
@@ -6,15 +6,58 @@ struct Cell {
     tree: f32,
     underbrush: f32,
     fire: u32,
-    _padding: u32,
+    fire_id: u32,
+    age: f32,
+    health: f32,
+    alert: f32,
+    seeds: f32,
+    fire_kind: u32,
+    severity: f32,
 }
 
 // Grid size uniform
 struct GridSize {
     width: u32,
     height: u32,
+    // Nonzero if the cells lie on a hexagonal grid, where odd rows are
+    // shifted half a cell to the right
+    hexagonal: u32,
+    // What to show (one of the RENDER_ constants)
+    render_mode: u32,
 }
 
+// A recent storm track, matching GpuStormTrack
+struct StormTrack {
+    // Start (xy) and end (zw) of the track in cells
+    path: vec4<f32>,
+    radius: f32,
+    // How visible (0 - 1) the track still is
+    fade: f32,
+}
+
+// Storm tracks to draw, matching GpuStormTracks
+struct StormTracks {
+    count: u32,
+    tracks: array<StormTrack, 16>,
+}
+
+// Burning, trees and underbrush
+const RENDER_STANDARD: u32 = 0u;
+// Tree age
+const RENDER_AGE: u32 = 1u;
+// Tree health
+const RENDER_HEALTH: u32 = 2u;
+// Alert signalled between trees
+const RENDER_ALERT: u32 = 3u;
+// Surface and crown fires
+const RENDER_FIRE: u32 = 4u;
+// Severity of the last fire in each cell
+const RENDER_SEVERITY: u32 = 5u;
+
+// Fire kinds, matching the FIRE_ constants of the compute shader
+const FIRE_SURFACE: u32 = 1u;
+const FIRE_CROWN: u32 = 2u;
+
 // Bind group 0: Cell data (read-only for rendering)
 @group(0) @binding(0)
 var<storage, read> cells: array<Cell>;
@@ -23,6 +66,10 @@ var<storage, read> cells: array<Cell>;
 @group(1) @binding(0)
 var<uniform> grid_size: GridSize;
 
+// Bind group 1: Storm tracks to overlay
+@group(1) @binding(1)
+var<uniform> storms: StormTracks;
+
 // Vertex output / Fragment input
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -55,15 +102,72 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
 // Color constants
 const BURN_COLOR: vec3<f32> = vec3<f32>(1.0, 0.2, 0.0);      // Bright orange-red for fire
 const TREE_COLOR: vec3<f32> = vec3<f32>(0.133, 0.545, 0.133); // Forest green
+const SAPLING_COLOR: vec3<f32> = vec3<f32>(0.565, 0.933, 0.565); // Light green
 const UNDERBRUSH_COLOR: vec3<f32> = vec3<f32>(0.545, 0.353, 0.169); // Saddle brown
 const BACKGROUND_COLOR: vec3<f32> = vec3<f32>(0.196, 0.196, 0.196);  // Dark gray
+const YOUNG_COLOR: vec3<f32> = vec3<f32>(0.992, 0.906, 0.145); // Yellow
+const OLD_COLOR: vec3<f32> = vec3<f32>(0.267, 0.004, 0.329); // Deep purple
+const HEALTHY_COLOR: vec3<f32> = vec3<f32>(0.0, 0.8, 0.2); // Green
+const DYING_COLOR: vec3<f32> = vec3<f32>(0.8, 0.0, 0.0); // Red
+const CALM_COLOR: vec3<f32> = vec3<f32>(0.1, 0.3, 0.15); // Dark green
+const ALERT_COLOR: vec3<f32> = vec3<f32>(0.0, 0.9, 1.0); // Cyan
+const SURFACE_FIRE_COLOR: vec3<f32> = vec3<f32>(1.0, 0.8, 0.0); // Amber
+const CROWN_FIRE_COLOR: vec3<f32> = vec3<f32>(0.9, 0.0, 0.3); // Crimson
+// Burn severity classes, coloured as in dNBR maps
+const UNBURNED_COLOR: vec3<f32> = vec3<f32>(0.0, 0.5, 0.0); // Green
+const LOW_SEVERITY_COLOR: vec3<f32> = vec3<f32>(1.0, 1.0, 0.0); // Yellow
+const MODERATE_LOW_SEVERITY_COLOR: vec3<f32> = vec3<f32>(1.0, 0.6, 0.0); // Orange
+const MODERATE_HIGH_SEVERITY_COLOR: vec3<f32> = vec3<f32>(1.0, 0.0, 0.0); // Red
+const HIGH_SEVERITY_COLOR: vec3<f32> = vec3<f32>(0.5, 0.0, 0.5); // Purple
+const STORM_COLOR: vec3<f32> = vec3<f32>(0.416, 0.353, 0.804); // Slate blue
+
+// Lower bounds of the burn severity classes, matching SeverityClass
+const LOW_SEVERITY: f32 = 0.1;
+const MODERATE_LOW_SEVERITY: f32 = 0.27;
+const MODERATE_HIGH_SEVERITY: f32 = 0.44;
+const HIGH_SEVERITY: f32 = 0.66;
+
+// Trees at least this old (in years) are all drawn as old
+const MAX_DISPLAY_AGE: f32 = 300.0;
+
+// Distance from a point to the track of a storm, in cells
+fn storm_distance(point: vec2<f32>, track: StormTrack) -> f32 {
+    let start = track.path.xy;
+    let along = track.path.zw - start;
+    let length_squared = dot(along, along);
+    var t = 0.0;
+    if (length_squared > 0.0) {
+        t = clamp(dot(point - start, along) / length_squared, 0.0, 1.0);
+    }
+    return distance(point, start + along * t);
+}
+
+// Shade the footprints of recent storms over a color, with a line along the
+// middle of each track
+fn overlay_storms(color: vec3<f32>, point: vec2<f32>) -> vec3<f32> {
+    var shaded = color;
+    for (var i = 0u; i < min(storms.count, 16u); i++) {
+        let track = storms.tracks[i];
+        let from_track = storm_distance(point, track);
+        if (from_track < max(track.radius * 0.05, 1.0)) {
+            shaded = mix(shaded, STORM_COLOR, 0.8 * track.fade);
+        } else if (from_track < track.radius) {
+            shaded = mix(shaded, STORM_COLOR, 0.25 * track.fade);
+        }
+    }
+    return shaded;
+}
 
 // Fragment shader - samples the cell buffer and outputs color
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Calculate which cell this pixel corresponds to
-    let cell_x = u32(in.uv.x * f32(grid_size.width));
     let cell_y = u32(in.uv.y * f32(grid_size.height));
+    var grid_x = in.uv.x * f32(grid_size.width);
+    if (grid_size.hexagonal != 0u && (cell_y & 1u) == 1u) {
+        grid_x -= 0.5;
+    }
+    let cell_x = u32(max(grid_x, 0.0));
     
     // Clamp to valid range
     let x = min(cell_x, grid_size.width - 1u);
@@ -78,20 +182,68 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     // Determine color based on cell state
     var color: vec3<f32>;
     
-    if (cell.fire > 0u) {
+    if (grid_size.render_mode == RENDER_AGE) {
+        if (cell.tree > 0.0) {
+            color = mix(YOUNG_COLOR, OLD_COLOR, clamp(cell.age / MAX_DISPLAY_AGE, 0.0, 1.0));
+        } else {
+            color = BACKGROUND_COLOR;
+        }
+    } else if (grid_size.render_mode == RENDER_HEALTH) {
+        if (cell.tree > 0.0) {
+            color = mix(DYING_COLOR, HEALTHY_COLOR, clamp(cell.health, 0.0, 1.0));
+        } else {
+            color = BACKGROUND_COLOR;
+        }
+    } else if (grid_size.render_mode == RENDER_ALERT) {
+        if (cell.fire > 0u) {
+            color = BURN_COLOR;
+        } else if (cell.tree > 0.0) {
+            color = mix(CALM_COLOR, ALERT_COLOR, clamp(cell.alert, 0.0, 1.0));
+        } else {
+            color = BACKGROUND_COLOR;
+        }
+    } else if (grid_size.render_mode == RENDER_FIRE) {
+        if (cell.fire > 0u && cell.fire_kind == FIRE_CROWN) {
+            color = CROWN_FIRE_COLOR;
+        } else if (cell.fire > 0u && cell.fire_kind == FIRE_SURFACE) {
+            color = SURFACE_FIRE_COLOR;
+        } else {
+            // Dimmed fuel, so that fires stand out
+            let fuel = clamp(cell.tree + cell.underbrush, 0.0, 1.0);
+            color = mix(BACKGROUND_COLOR, TREE_COLOR * 0.5, fuel);
+        }
+    } else if (grid_size.render_mode == RENDER_SEVERITY) {
+        if (cell.fire > 0u) {
+            color = BURN_COLOR;
+        } else if (cell.severity >= HIGH_SEVERITY) {
+            color = HIGH_SEVERITY_COLOR;
+        } else if (cell.severity >= MODERATE_HIGH_SEVERITY) {
+            color = MODERATE_HIGH_SEVERITY_COLOR;
+        } else if (cell.severity >= MODERATE_LOW_SEVERITY) {
+            color = MODERATE_LOW_SEVERITY_COLOR;
+        } else if (cell.severity >= LOW_SEVERITY) {
+            color = LOW_SEVERITY_COLOR;
+        } else {
+            color = UNBURNED_COLOR;
+        }
+    } else if (cell.fire > 0u) {
         // Cell is burning - interpolate between yellow and red based on intensity
         let intensity = min(f32(cell.fire) / 10.0, 1.0);
         let yellow = vec3<f32>(1.0, 0.9, 0.0);
         color = mix(BURN_COLOR, yellow, intensity * 0.5);
-    } else if (cell.tree > 0.5) {
-        // Cell has a tree - show tree color, slightly modulated by underbrush
+    } else if (cell.tree > 0.0) {
+        // Cell has a tree - shade from sapling to mature by size, slightly
+        // modulated by underbrush
+        let tree_color = mix(SAPLING_COLOR, TREE_COLOR, smoothstep(0.0, 1.0, cell.tree));
         let underbrush_factor = clamp(cell.underbrush, 0.0, 1.0);
-        color = mix(TREE_COLOR, TREE_COLOR * 0.7 + UNDERBRUSH_COLOR * 0.3, underbrush_factor * 0.3);
+        color = mix(tree_color, tree_color * 0.7 + UNDERBRUSH_COLOR * 0.3, underbrush_factor * 0.3);
     } else {
         // No tree - interpolate between background and underbrush color
         let underbrush_factor = clamp(cell.underbrush, 0.0, 1.0);
         color = mix(BACKGROUND_COLOR, UNDERBRUSH_COLOR, underbrush_factor);
     }
+
+    color = overlay_storms(color, vec2<f32>(grid_x, in.uv.y * f32(grid_size.height)));
     
     return vec4<f32>(color, 1.0);
 }
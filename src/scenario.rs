@@ -0,0 +1,351 @@
+//! Scenario files for reproducible experiments
+//!
+//! A scenario describes everything needed to reproduce a run: the grid size,
+//! every configurable parameter, the seed, the initial landscape and how long
+//! to run for. Scenarios are written in TOML or JSON, e.g.
+//!
+//! ```toml
+//! name = "dense start"
+//! seed = 7
+//! years = 200
+//!
+//! [grid]
+//! width = 256
+//! height = 256
+//!
+//! [landscape]
+//! type = "random"
+//! tree_density = 0.6
+//! underbrush = 0.1
+//!
+//! [weather]
+//! type = "monthly"
+//! moisture = [0.2, 0.2, 0.18, 0.15, 0.12, 0.08, 0.05, 0.04, 0.07, 0.12, 0.17, 0.2]
+//!
+//! # Year 10 is a severe drought whatever the seed
+//! [[climate.years]]
+//! year = 10
+//! moisture = 0.5
+//!
+//! # Suppression crews using the assets strategy defend this town first
+//! [management]
+//! assets = [[200, 180]]
+//!
+//! # Burn the north-west corner every 5 years once the fuel is damp enough
+//! [[management.treatments]]
+//! type = "prescribed_burn"
+//! every_years = 5
+//! polygon = [[0, 0], [64, 0], [64, 64], [0, 64]]
+//! min_fuel_moisture = 0.15
+//!
+//! # Clear the underbrush of one of 16 blocks every year
+//! [[management.treatments]]
+//! type = "thinning"
+//! every_years = 1
+//! blocks = [4, 4]
+//!
+//! # Start a fire at year 50, make lightning more common from year 80 and
+//! # keep a copy of the grid at year 100
+//! [[timeline.events]]
+//! year = 50
+//! type = "ignite"
+//! x = 120
+//! y = 200
+//!
+//! [[timeline.events]]
+//! year = 80
+//! type = "set_parameter"
+//! parameter = "lightning_strikes_per_year_per_acre"
+//! value = 0.05
+//!
+//! [[timeline.events]]
+//! year = 100
+//! type = "snapshot"
+//! label = "after the fire"
+//!
+//! [parameters]
+//! cell_size_m = 10
+//! tree_growth_years = 100
+//! rate_of_spread = 10.0
+//! ```
+//!
+//! Any parameter that is left out takes its value from
+//! `ConfigurableParameters::default`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::sim::{
+    BurnState, CellState, Climate, ConfigurableParameters, Management, SimulationFrame, Timeline,
+    WeatherSeries,
+};
+
+/// A complete, reproducible description of a simulation run
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    /// Human-readable name of the scenario
+    pub name: String,
+    /// Free-form notes about what the scenario is for
+    pub description: String,
+    pub grid: GridSize,
+    /// Seed for the random number generator and the random landscape
+    pub seed: u32,
+    /// Length of the run in simulated years. Runs indefinitely if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub years: Option<f32>,
+    pub landscape: Landscape,
+    /// Where the fuel moisture of each tick comes from
+    pub weather: WeatherSeries,
+    /// Scripted drought years
+    pub climate: Climate,
+    /// Scheduled fuel treatments
+    pub management: Management,
+    /// Scripted events
+    pub timeline: Timeline,
+    pub parameters: ConfigurableParameters,
+}
+
+/// Size of the simulation grid in cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GridSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for GridSize {
+    fn default() -> Self {
+        Self {
+            width: 500,
+            height: 500,
+        }
+    }
+}
+
+/// Where the initial state of the grid comes from
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Landscape {
+    /// Bare ground everywhere
+    #[default]
+    Empty,
+    /// Trees placed independently at random
+    Random {
+        /// The chance (0 - 1) that a cell starts with a fully grown tree
+        tree_density: f32,
+        /// The amount of underbrush every cell starts with
+        #[serde(default)]
+        underbrush: f32,
+    },
+    /// A hand-drawn map, one string per row. `T` is a fully grown tree, `,` is a cell
+    /// full of underbrush, `#` is a tree with a full layer of underbrush and
+    /// `.` is bare ground.
+    Map { rows: Vec<String> },
+}
+
+impl Landscape {
+    /// Build the initial frame for a grid of the given size. The seed only
+    /// matters for random landscapes.
+    pub fn generate(
+        &self,
+        width: usize,
+        height: usize,
+        seed: u32,
+    ) -> Result<SimulationFrame, anyhow::Error> {
+        let cell = |tree: f32, underbrush: f32| CellState {
+            burning: BurnState::NotBurning,
+            tree,
+            underbrush,
+            age: 0.0,
+            health: if tree > 0.0 { 1.0 } else { 0.0 },
+            alert: 0.0,
+            seeds: 0.0,
+            severity: 0.0,
+        };
+        let grid: Vec<CellState> = match self {
+            Landscape::Empty => return Ok(SimulationFrame::new(width, height)),
+            Landscape::Random {
+                tree_density,
+                underbrush,
+            } => {
+                let mut state = seed as u64;
+                (0..width * height)
+                    .map(|_| {
+                        let tree = unit_random(&mut state) < *tree_density;
+                        cell(if tree { 1.0 } else { 0.0 }, *underbrush)
+                    })
+                    .collect()
+            }
+            Landscape::Map { rows } => {
+                if rows.len() != height {
+                    return Err(anyhow::anyhow!(
+                        "landscape map has {} rows but the grid is {height} cells high",
+                        rows.len()
+                    ));
+                }
+                let mut grid = Vec::with_capacity(width * height);
+                for (y, row) in rows.iter().enumerate() {
+                    if row.chars().count() != width {
+                        return Err(anyhow::anyhow!(
+                            "row {y} of the landscape map is {} cells wide but the grid is {width}",
+                            row.chars().count()
+                        ));
+                    }
+                    for c in row.chars() {
+                        grid.push(match c {
+                            '.' => cell(0.0, 0.0),
+                            'T' => cell(1.0, 0.0),
+                            ',' => cell(0.0, 1.0),
+                            '#' => cell(1.0, 1.0),
+                            _ => {
+                                return Err(anyhow::anyhow!(
+                                    "unknown landscape map cell `{c}` in row {y}"
+                                ));
+                            }
+                        });
+                    }
+                }
+                grid
+            }
+        };
+        Ok(SimulationFrame {
+            width,
+            height,
+            grid: grid.into(),
+        })
+    }
+}
+
+/// SplitMix64, mapped to a float in `[0, 1)`
+fn unit_random(state: &mut u64) -> f32 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+impl Scenario {
+    /// Parse a scenario from JSON (if the text starts with `{`) or TOML and
+    /// check that its parameters are valid
+    pub fn parse(source: &str) -> Result<Self, anyhow::Error> {
+        let scenario: Self = if source.trim_start().starts_with('{') {
+            serde_json::from_str(source)?
+        } else {
+            toml::from_str(source)?
+        };
+        scenario.parameters().validate()?;
+        scenario.weather.validate()?;
+        scenario.climate.validate()?;
+        scenario.management.validate()?;
+        scenario
+            .timeline
+            .validate(scenario.grid.width, scenario.grid.height)?;
+        Ok(scenario)
+    }
+
+    /// Serialize the scenario as TOML
+    pub fn to_toml(&self) -> Result<String, anyhow::Error> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Serialize the scenario as JSON
+    pub fn to_json(&self) -> Result<String, anyhow::Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// The full parameter set, including the grid size and seed
+    pub fn parameters(&self) -> ConfigurableParameters {
+        let mut params = self.parameters.clone();
+        params.set_forest_size(self.grid.width, self.grid.height);
+        params.seed = self.seed;
+        params
+    }
+
+    /// Build the first frame of the run
+    pub fn initial_frame(&self) -> Result<SimulationFrame, anyhow::Error> {
+        self.landscape
+            .generate(self.grid.width, self.grid.height, self.seed)
+    }
+
+    /// The number of steps in the run, if it has a fixed length
+    pub fn total_steps(&self) -> Option<u32> {
+        self.years
+            .map(|years| (years * self.parameters.ticks_per_month * 12.0).round() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::TimelineEvent;
+
+    const DEFAULT: &str = include_str!("../front/scenarios/default.toml");
+
+    fn map(rows: &[&str]) -> Landscape {
+        Landscape::Map {
+            rows: rows.iter().map(|row| row.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn toml_and_json_round_trip() {
+        let mut scenario = Scenario::parse(DEFAULT).unwrap();
+        scenario.years = Some(20.0);
+        scenario.landscape = Landscape::Random {
+            tree_density: 0.5,
+            underbrush: 0.25,
+        };
+        scenario
+            .timeline
+            .push(1.0, TimelineEvent::Ignite { x: 3, y: 4 });
+        let toml = scenario.to_toml().unwrap();
+
+        let from_toml = Scenario::parse(&toml).unwrap();
+        let from_json = Scenario::parse(&scenario.to_json().unwrap()).unwrap();
+        assert_eq!(from_toml.to_toml().unwrap(), toml);
+        assert_eq!(from_json.to_toml().unwrap(), toml);
+        assert_eq!(from_json.landscape, scenario.landscape);
+        assert_eq!(from_json.timeline, scenario.timeline);
+    }
+
+    #[test]
+    fn default_scenario_uses_the_default_parameters() {
+        let scenario = Scenario::parse(DEFAULT).unwrap();
+        assert_eq!(scenario.name, "default");
+        assert_eq!(scenario.grid, GridSize::default());
+        assert_eq!(scenario.landscape, Landscape::Empty);
+        assert_eq!(scenario.total_steps(), None);
+        assert_eq!(
+            toml::to_string(&scenario.parameters).unwrap(),
+            toml::to_string(&ConfigurableParameters::default()).unwrap()
+        );
+        let params = scenario.parameters();
+        assert_eq!((params.forest_width, params.forest_height), (500, 500));
+    }
+
+    #[test]
+    fn maps_must_match_the_grid_size() {
+        let frame = map(&[".T", ",#", ".."]).generate(2, 3, 0).unwrap();
+        assert_eq!(frame.grid[1].tree, 1.0);
+        assert_eq!(frame.grid[2].underbrush, 1.0);
+        assert_eq!((frame.grid[3].tree, frame.grid[3].underbrush), (1.0, 1.0));
+
+        assert!(map(&[".T", ",#"]).generate(2, 3, 0).is_err());
+        assert!(map(&[".T", ",#", "..."]).generate(2, 3, 0).is_err());
+        assert!(map(&[".T", ",#", ".x"]).generate(2, 3, 0).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_bad_scenarios() {
+        assert!(Scenario::parse("nmae = \"typo\"").is_err());
+        assert!(Scenario::parse("[parameters]\nticks_per_month = -1.0").is_err());
+        assert!(
+            Scenario::parse(
+                "[grid]\nwidth = 10\nheight = 10\n\n[[timeline.events]]\nyear = 1\ntype = \"ignite\"\nx = 10\ny = 0"
+            )
+            .is_err()
+        );
+    }
+}
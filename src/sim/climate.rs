@@ -0,0 +1,81 @@
+//! Inter-annual climate variability
+//!
+//! On top of the seasonal weather cycle, every simulated year gets a climate
+//! moisture multiplier that scales the fuel moisture for the whole year. The
+//! multiplier combines a long-term trend (`moisture_trend`) with random drought
+//! years (`drought_probability` and `drought_severity`). Drought years are
+//! drawn from the simulation seed, so a run is reproducible, and individual
+//! years can be scripted to replay a known sequence of droughts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::sim::SimulationParameters;
+
+/// Scripted overrides of the climate of particular years
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Climate {
+    /// Years whose drought multiplier is given explicitly instead of drawn
+    /// at random
+    pub years: Vec<ClimateYear>,
+}
+
+/// The climate of a single scripted year
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClimateYear {
+    /// Years since the start of the run, starting at 0
+    pub year: u32,
+    /// Multiplier applied to the fuel moisture of that year, e.g. 0.5 for a
+    /// severe drought. The moisture trend still applies on top.
+    pub moisture: f32,
+}
+
+impl Climate {
+    /// Check that every scripted year is usable
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        match self
+            .years
+            .iter()
+            .find(|y| !y.moisture.is_finite() || y.moisture < 0.0)
+        {
+            Some(y) => Err(anyhow::anyhow!(
+                "climate moisture {} of year {} must be a non-negative number",
+                y.moisture,
+                y.year
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the given year is a drought year, either because it is
+    /// scripted to be drier than normal or because it was drawn as one
+    pub fn is_drought(&self, year: u32, parameters: &SimulationParameters) -> bool {
+        match self.years.iter().find(|y| y.year == year) {
+            Some(scripted) => scripted.moisture < 1.0,
+            None => year_random(parameters.seed, year) < parameters.drought_probability,
+        }
+    }
+
+    /// The multiplier applied to the fuel moisture of every tick in the given
+    /// year
+    pub fn moisture_multiplier(&self, year: u32, parameters: &SimulationParameters) -> f32 {
+        let variability = match self.years.iter().find(|y| y.year == year) {
+            Some(scripted) => scripted.moisture,
+            None if self.is_drought(year, parameters) => 1.0 - parameters.drought_severity,
+            None => 1.0,
+        };
+        let trend = 1.0 + parameters.moisture_trend * year as f32;
+        (variability * trend).max(0.0)
+    }
+}
+
+/// A uniform random number in `[0, 1)` for a year of a seeded run
+fn year_random(seed: u32, year: u32) -> f32 {
+    // SplitMix64 finaliser over the seed and year
+    let mut z = ((seed as u64) << 32 | year as u64).wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
@@ -0,0 +1,179 @@
+//! Step-exact log of what happens to individual cells
+//!
+//! The GPU appends a compact record (see `GpuEvent`) to an event buffer
+//! whenever lightning strikes, fire spreads, a cell burns out or a tree dies,
+//! using an atomic counter to claim a slot. The buffer is periodically read
+//! back and emptied into an [`EventLog`], which explains how the picture on
+//! screen came about.
+//!
+//! The log is reproducible as long as nothing is dropped. Once the buffer
+//! overflows, which events claimed a slot depends on the order the GPU ran
+//! the cells in, so the log can differ between runs with the same seed.
+
+use std::collections::VecDeque;
+
+use bytemuck::{Pod, Zeroable};
+use wasm_bindgen::prelude::*;
+
+use crate::sim::GridHistory;
+
+/// Number of event slots in the GPU event buffer. Events beyond this many
+/// between two readbacks are dropped, and counted in `EventLog::dropped`.
+pub const EVENT_BUFFER_CAPACITY: usize = 1 << 16;
+
+/// Number of events the log keeps. Older events are forgotten first.
+pub const EVENT_LOG_RETAINED: usize = 1 << 18;
+
+/// An event as laid out in the GPU buffer
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct GpuEvent {
+    pub step: u32,
+    /// Index of the cell the event happened to
+    pub cell: u32,
+    /// One of the `EventKind` values
+    pub kind: u32,
+    pub fire_id: u32,
+}
+
+/// What happened to a cell
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum EventKind {
+    /// Lightning struck the cell, whether or not it started a fire
+    Lightning = 0,
+    /// The cell caught fire from a burning neighbour
+    Spread = 1,
+    /// The cell caught fire from an ember
+    SpotFire = 2,
+    /// A new fire started in the cell other than by lightning, e.g. from the
+    /// timeline or a fire source boundary
+    Ignition = 3,
+    /// The fire in the cell burnt out
+    BurntOut = 4,
+    /// The tree in the cell died naturally
+    TreeDied = 5,
+}
+
+impl EventKind {
+    fn from_gpu(kind: u32) -> Option<Self> {
+        Some(match kind {
+            0 => EventKind::Lightning,
+            1 => EventKind::Spread,
+            2 => EventKind::SpotFire,
+            3 => EventKind::Ignition,
+            4 => EventKind::BurntOut,
+            5 => EventKind::TreeDied,
+            _ => return None,
+        })
+    }
+}
+
+/// A single event
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimEvent {
+    /// The simulation step on which the event happened
+    pub step: u32,
+    /// Column of the cell
+    pub x: u32,
+    /// Row of the cell
+    pub y: u32,
+    pub kind: EventKind,
+    /// The fire the cell is burning (or burnt) in, or 0 if none
+    pub fire_id: u32,
+}
+
+/// Events read back from the GPU since the start of the run, in step order
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct EventLog {
+    events: VecDeque<SimEvent>,
+    /// Events lost because the GPU buffer filled up between readbacks
+    dropped: u32,
+}
+
+impl EventLog {
+    /// Fold a snapshot of the GPU event buffer into the log.
+    ///
+    /// `count` is the number of events the GPU tried to append since the
+    /// last readback and `records` is the buffer itself.
+    pub(crate) fn ingest(&mut self, count: u32, records: &[GpuEvent], grid: &GridHistory) {
+        let stored = (count as usize).min(records.len());
+        self.dropped += count - stored as u32;
+
+        // Events within a step are appended in whatever order the GPU ran the
+        // cells, so sort them to make the log reproducible
+        let mut batch: Vec<GpuEvent> = records[..stored].to_vec();
+        batch.sort_unstable_by_key(|e| (e.step, e.cell, e.kind));
+        self.events.extend(batch.into_iter().filter_map(|event| {
+            let (x, y) = grid.position(event.step, event.cell);
+            Some(SimEvent {
+                step: event.step,
+                x,
+                y,
+                kind: EventKind::from_gpu(event.kind)?,
+                fire_id: event.fire_id,
+            })
+        }));
+        let excess = self.events.len().saturating_sub(EVENT_LOG_RETAINED);
+        self.events.drain(..excess);
+    }
+
+    /// Iterate over the events in step order
+    pub fn iter(&self) -> impl Iterator<Item = &SimEvent> {
+        self.events.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a EventLog {
+    type Item = &'a SimEvent;
+    type IntoIter = std::collections::vec_deque::Iter<'a, SimEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.iter()
+    }
+}
+
+#[wasm_bindgen]
+impl EventLog {
+    /// Number of events in the log
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether the log is empty
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Get an event by index
+    #[wasm_bindgen]
+    pub fn event(&self, index: usize) -> Option<SimEvent> {
+        self.events.get(index).copied()
+    }
+
+    /// All events in the log, in step order
+    #[wasm_bindgen]
+    pub fn events(&self) -> Vec<SimEvent> {
+        self.events.iter().copied().collect()
+    }
+
+    /// Events from the given step on, in step order
+    #[wasm_bindgen]
+    pub fn events_since(&self, step: u32) -> Vec<SimEvent> {
+        let start = self.events.partition_point(|e| e.step < step);
+        self.events.range(start..).copied().collect()
+    }
+
+    /// Number of events lost because too many happened between two
+    /// readbacks. Once this is above zero, which events were kept depends on
+    /// GPU scheduling and can change from run to run.
+    #[wasm_bindgen]
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
@@ -0,0 +1,278 @@
+//! Running as fast as possible until a condition is met
+//!
+//! Normal playback is throttled to `tick_rate`. A fast-forward instead submits
+//! steps back to back, reading the grid back every `check_every_years` to see
+//! whether the run can stop:
+//!
+//! - after a set number of simulated years
+//! - once the tree cover has stayed within a tolerance for a window of years,
+//!   e.g. to spin a forest up to equilibrium
+//! - once no cells are burning
+//!
+//! Every run also stops after `max_years`, or when it is cancelled through a
+//! [`CancelHandle`].
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use wasm_bindgen::prelude::*;
+
+use crate::sim::stats::FrameSummary;
+
+/// The condition a fast-forward stops on
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Condition {
+    /// Stop after `max_years`
+    Years,
+    /// Stop once the tree cover has varied by no more than `tolerance` over
+    /// the last `window_years`
+    StableCover { tolerance: f32, window_years: f32 },
+    /// Stop once nothing is burning
+    FiresOut,
+}
+
+/// When a fast-forward stops
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct RunUntil {
+    condition: Condition,
+    max_years: f32,
+    check_every_years: f32,
+    cancel: CancelHandle,
+}
+
+#[wasm_bindgen]
+impl RunUntil {
+    /// Run for the given number of simulated years
+    #[wasm_bindgen]
+    pub fn years(years: f32) -> RunUntil {
+        Self::new(Condition::Years, years, 1.0)
+    }
+
+    /// Run until the tree cover (0 - 1) varies by no more than `tolerance`
+    /// over `window_years`, or for `max_years` at most
+    #[wasm_bindgen]
+    pub fn stable_cover(tolerance: f32, window_years: f32, max_years: f32) -> RunUntil {
+        Self::new(
+            Condition::StableCover {
+                tolerance,
+                window_years,
+            },
+            max_years,
+            1.0,
+        )
+    }
+
+    /// Run until no cells are burning, or for `max_years` at most. Stops
+    /// straight away if nothing is burning to begin with.
+    #[wasm_bindgen]
+    pub fn fires_out(max_years: f32) -> RunUntil {
+        Self::new(Condition::FiresOut, max_years, 1.0 / 12.0)
+    }
+
+    /// Change how often, in simulated years, the grid is read back to check
+    /// the condition and report progress
+    #[wasm_bindgen]
+    pub fn set_check_every_years(&mut self, years: f32) {
+        self.check_every_years = years;
+    }
+
+    /// Get a handle that cancels the run. Take it before starting the run,
+    /// since the simulation is busy until the run stops.
+    #[wasm_bindgen]
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+}
+
+impl RunUntil {
+    fn new(condition: Condition, max_years: f32, check_every_years: f32) -> Self {
+        Self {
+            condition,
+            max_years,
+            check_every_years,
+            cancel: CancelHandle::default(),
+        }
+    }
+
+    /// Check that the run can stop
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if !(self.max_years.is_finite() && self.max_years >= 0.0) {
+            return Err(anyhow::anyhow!(
+                "a run must last 0 or more years, not {}",
+                self.max_years
+            ));
+        }
+        if !(self.check_every_years.is_finite() && self.check_every_years > 0.0) {
+            return Err(anyhow::anyhow!(
+                "the condition must be checked after a positive number of years, not {}",
+                self.check_every_years
+            ));
+        }
+        match self.condition {
+            Condition::StableCover {
+                tolerance,
+                window_years,
+            } if !(tolerance >= 0.0 && window_years > 0.0) => Err(anyhow::anyhow!(
+                "tree cover must be stable to a tolerance of 0 or more over a positive number of years"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Cancels a fast-forward from outside it
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+#[wasm_bindgen]
+impl CancelHandle {
+    /// Stop the run after the steps already submitted
+    #[wasm_bindgen]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the run has been cancelled
+    #[wasm_bindgen]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Why a fast-forward stopped
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum StopReason {
+    /// The condition was met
+    ConditionMet = 0,
+    /// The run lasted `max_years` without meeting the condition
+    MaxYears = 1,
+    /// The run was cancelled
+    Cancelled = 2,
+    /// The simulation reached the end of its scenario
+    StepLimit = 3,
+}
+
+/// How far a fast-forward has got
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunProgress {
+    /// Simulated years run so far
+    pub years: f32,
+    /// The fraction (0 - 1) of `max_years` run so far
+    pub fraction: f32,
+    /// Summary of the grid at the last check
+    pub summary: FrameSummary,
+}
+
+/// The result of a fast-forward
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct RunOutcome {
+    pub reason: StopReason,
+    /// Simulated years run
+    pub years: f32,
+    /// Summary of the grid when the run stopped
+    pub summary: FrameSummary,
+}
+
+/// Decides how far a fast-forward runs between checks and when it stops
+pub struct FastForward {
+    until: RunUntil,
+    start_step: u32,
+    /// Tree cover at each check within the stability window, as
+    /// `(years, cover)`
+    covers: VecDeque<(f32, f32)>,
+}
+
+impl FastForward {
+    /// Start a run from the given step
+    pub fn new(until: RunUntil, start_step: u32) -> Self {
+        Self {
+            until,
+            start_step,
+            covers: VecDeque::new(),
+        }
+    }
+
+    /// The number of steps to submit before the next check, given the steps
+    /// run so far
+    pub fn batch_steps(&self, step: u32, ticks_per_year: f32) -> u32 {
+        let max_steps = (self.until.max_years * ticks_per_year).round() as u32;
+        let remaining = max_steps.saturating_sub(step - self.start_step);
+        ((self.until.check_every_years * ticks_per_year).round() as u32)
+            .max(1)
+            .min(remaining)
+    }
+
+    /// Check the run after a batch of steps, given a summary of the grid.
+    /// Returns the progress so far and why the run stopped, if it did.
+    pub fn check(
+        &mut self,
+        step: u32,
+        summary: FrameSummary,
+        ticks_per_year: f32,
+    ) -> (RunProgress, Option<StopReason>) {
+        let years = (step - self.start_step) as f32 / ticks_per_year;
+        let progress = RunProgress {
+            years,
+            fraction: if self.until.max_years > 0.0 {
+                (years / self.until.max_years).min(1.0)
+            } else {
+                1.0
+            },
+            summary,
+        };
+        let met = match self.until.condition {
+            Condition::Years => false,
+            Condition::StableCover {
+                tolerance,
+                window_years,
+            } => {
+                self.covers.push_back((years, summary.tree_cover));
+                while self
+                    .covers
+                    .get(1)
+                    .is_some_and(|&(year, _)| year <= years - window_years)
+                {
+                    self.covers.pop_front();
+                }
+                let (min, max) = self
+                    .covers
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &(_, c)| {
+                        (min.min(c), max.max(c))
+                    });
+                self.covers[0].0 <= years - window_years && max - min <= tolerance
+            }
+            Condition::FiresOut => summary.burning_fraction == 0.0,
+        };
+        let reason = if self.until.cancel.is_cancelled() {
+            Some(StopReason::Cancelled)
+        } else if met {
+            Some(StopReason::ConditionMet)
+        } else if self.batch_steps(step, ticks_per_year) == 0 {
+            Some(if self.until.condition == Condition::Years {
+                StopReason::ConditionMet
+            } else {
+                StopReason::MaxYears
+            })
+        } else {
+            None
+        };
+        (progress, reason)
+    }
+
+    /// Whether the run has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.until.cancel.is_cancelled()
+    }
+}
@@ -0,0 +1,560 @@
+//! Fire tracking and fire size statistics
+//!
+//! Every fire started by lightning gets an ID on the GPU, and each cell it
+//! spreads to inherits that ID, which it keeps after burning out so that the
+//! fire can't burn it twice. The GPU keeps a ledger of per-fire counters
+//! (see `GpuFireRecord`) which is periodically read back and folded into a
+//! [`FireCatalogue`] once a fire has burnt out.
+
+use std::collections::{HashMap, HashSet};
+
+use bytemuck::{Pod, Zeroable};
+use wasm_bindgen::prelude::*;
+
+use crate::sim::GridHistory;
+use crate::sim::events::{EventKind, EventLog};
+
+/// Number of fire slots in the GPU fire ledger. Fire IDs wrap around this many
+/// slots, so a fire is lost from the catalogue if this many newer fires are
+/// started before it burns out and the ledger is read back.
+pub const FIRE_LEDGER_CAPACITY: usize = 1 << 14;
+
+/// A fire ledger entry as laid out in the GPU buffer
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct GpuFireRecord {
+    pub ignition_tick: u32,
+    /// Index of the cell where the fire started
+    pub origin: u32,
+    /// Total number of cells that have caught fire
+    pub area: u32,
+    /// Number of cells that are currently burning
+    pub burning: u32,
+    /// The last tick on which a cell of this fire burnt out
+    pub last_active_tick: u32,
+    /// Index + 1 of the prescribed burn in the treatment log that lit the
+    /// fire, or 0 for a wildfire
+    pub treatment: u32,
+    /// Number of cells the prescribed burn lit itself
+    pub lit: u32,
+}
+
+/// A single completed fire
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct FireRecord {
+    pub id: u32,
+    /// The simulation step on which the fire was ignited
+    pub ignition_tick: u32,
+    /// Column of the ignition cell
+    pub x: u32,
+    /// Row of the ignition cell
+    pub y: u32,
+    /// The number of cells burnt by the fire
+    pub area: u32,
+    /// The area burnt by the fire in hectares
+    pub hectares: f32,
+    /// The number of ticks from ignition to extinction
+    pub duration: u32,
+}
+
+/// A prescribed burn that has burnt out. Burns are kept apart from the
+/// wildfires, so they don't count towards the fire size statistics.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct BurnRecord {
+    pub id: u32,
+    /// Index of the burn in the treatment log
+    pub treatment_event: u32,
+    /// The number of cells the burn lit
+    pub lit: u32,
+    /// The number of cells the fire spread to beyond those it was lit in
+    pub escaped_area: u32,
+    /// The escaped area in hectares
+    pub escaped_hectares: f32,
+}
+
+/// Histogram of fire sizes
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, Default)]
+pub struct FireSizeHistogram {
+    /// Bin edges in cells. There is one more edge than there are bins.
+    pub bin_edges: Vec<f64>,
+    pub counts: Vec<u32>,
+}
+
+/// Complementary cumulative distribution of fire sizes, `P(area >= size)`
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, Default)]
+pub struct FireSizeCcdf {
+    pub sizes: Vec<u32>,
+    pub probabilities: Vec<f64>,
+}
+
+/// Maximum-likelihood fit of a discrete power law `P(area) ~ area^-alpha`
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct PowerLawFit {
+    pub alpha: f64,
+    /// Standard error of `alpha`
+    pub std_error: f64,
+    /// The smallest fire size included in the fit
+    pub x_min: u32,
+    /// The number of fires included in the fit
+    pub sample_size: u32,
+}
+
+/// Catalogue of all fires that have burnt out since the simulation started
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct FireCatalogue {
+    fires: Vec<FireRecord>,
+    burns: Vec<BurnRecord>,
+    /// Fire IDs that have been seen in the ledger but are still burning
+    active: Vec<u32>,
+    /// The first fire ID that has not yet been seen in the ledger
+    next_unseen: u32,
+    /// Fires that were overwritten in the ledger before they burnt out
+    lost: u32,
+}
+
+impl Default for FireCatalogue {
+    fn default() -> Self {
+        Self {
+            fires: Vec::new(),
+            burns: Vec::new(),
+            active: Vec::new(),
+            next_unseen: 1,
+            lost: 0,
+        }
+    }
+}
+
+impl FireCatalogue {
+    /// Fold a snapshot of the GPU fire ledger into the catalogue.
+    ///
+    /// `allocated` is the number of fire IDs that have been handed out so far
+    /// (IDs start at 1) and `records` is the ledger itself.
+    pub(crate) fn ingest(
+        &mut self,
+        allocated: u32,
+        records: &[GpuFireRecord],
+        grid: &GridHistory,
+        cell_hectares: f32,
+    ) {
+        let capacity = records.len() as u32;
+        self.active.extend(self.next_unseen..=allocated);
+        self.next_unseen = self.next_unseen.max(allocated + 1);
+
+        let mut lost = 0;
+        let fires = &mut self.fires;
+        let burns = &mut self.burns;
+        self.active.retain(|&id| {
+            // A snapshot from before the fire started, such as one that was
+            // still in flight when a newer one was folded in
+            if id > allocated {
+                return true;
+            }
+            if allocated - id >= capacity {
+                lost += 1;
+                return false;
+            }
+            let record = &records[((id - 1) % capacity) as usize];
+            if record.treatment != 0 {
+                // A burn may not have lit any cells at all
+                if record.burning > 0 {
+                    return true;
+                }
+                let escaped_area = record.area.saturating_sub(record.lit);
+                burns.push(BurnRecord {
+                    id,
+                    treatment_event: record.treatment - 1,
+                    lit: record.lit,
+                    escaped_area,
+                    escaped_hectares: escaped_area as f32 * cell_hectares,
+                });
+                return false;
+            }
+            if record.burning > 0 || record.area == 0 {
+                return true;
+            }
+            let (x, y) = grid.position(record.ignition_tick, record.origin);
+            fires.push(FireRecord {
+                id,
+                ignition_tick: record.ignition_tick,
+                x,
+                y,
+                area: record.area,
+                hectares: record.area as f32 * cell_hectares,
+                duration: record.last_active_tick.saturating_sub(record.ignition_tick) + 1,
+            });
+            false
+        });
+        self.lost += lost;
+    }
+
+    /// All completed fires, in the order they burnt out
+    pub fn fires(&self) -> &[FireRecord] {
+        &self.fires
+    }
+
+    /// All completed prescribed burns, in the order they burnt out
+    pub fn burns(&self) -> &[BurnRecord] {
+        &self.burns
+    }
+
+    fn sorted_areas(&self) -> Vec<u32> {
+        let mut areas: Vec<u32> = self.fires.iter().map(|f| f.area).collect();
+        areas.sort_unstable();
+        areas
+    }
+
+    /// Check that no completed fire is larger than the number of distinct
+    /// cells the event log saw catch fire in it, since a fire can't burn a
+    /// cell twice. Only fires whose events all lie within the log are
+    /// checked, and nothing is checked once events have been dropped.
+    pub fn check_areas(&self, events: &EventLog) -> Result<(), anyhow::Error> {
+        if events.dropped() > 0 {
+            return Ok(());
+        }
+        let mut logged_steps = None;
+        let mut burnt: HashMap<u32, HashSet<(u32, u32)>> = HashMap::new();
+        for event in events {
+            let (first, _) = *logged_steps.get_or_insert((event.step, event.step));
+            logged_steps = Some((first, event.step));
+            let caught_fire = matches!(
+                event.kind,
+                EventKind::Lightning
+                    | EventKind::Spread
+                    | EventKind::SpotFire
+                    | EventKind::Ignition
+            );
+            if caught_fire && event.fire_id != 0 {
+                burnt
+                    .entry(event.fire_id)
+                    .or_default()
+                    .insert((event.x, event.y));
+            }
+        }
+        let Some((first, last)) = logged_steps else {
+            return Ok(());
+        };
+        // The log may have forgotten part of its first step
+        for fire in self
+            .fires
+            .iter()
+            .filter(|f| f.ignition_tick > first && f.ignition_tick + f.duration <= last)
+        {
+            let cells = burnt.get(&fire.id).map_or(0, HashSet::len);
+            if fire.area as usize > cells {
+                return Err(anyhow::anyhow!(
+                    "fire {} has an area of {} cells but only burnt {cells} distinct cells",
+                    fire.id,
+                    fire.area
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl FireCatalogue {
+    /// Number of completed fires
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.fires.len()
+    }
+
+    /// Whether no fires have completed yet
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.fires.is_empty()
+    }
+
+    /// Get a completed fire by index
+    #[wasm_bindgen]
+    pub fn fire(&self, index: usize) -> Option<FireRecord> {
+        self.fires.get(index).copied()
+    }
+
+    /// Number of completed prescribed burns
+    #[wasm_bindgen]
+    pub fn burn_count(&self) -> usize {
+        self.burns.len()
+    }
+
+    /// Get a completed prescribed burn by index
+    #[wasm_bindgen]
+    pub fn burn(&self, index: usize) -> Option<BurnRecord> {
+        self.burns.get(index).copied()
+    }
+
+    /// Number of fires that are still burning
+    #[wasm_bindgen]
+    pub fn active_fires(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Number of fires that were lost because the ledger wrapped around
+    #[wasm_bindgen]
+    pub fn lost_fires(&self) -> u32 {
+        self.lost
+    }
+
+    /// Final areas (in cells) of all completed fires
+    #[wasm_bindgen]
+    pub fn areas(&self) -> Vec<u32> {
+        self.fires.iter().map(|f| f.area).collect()
+    }
+
+    /// Final areas (in hectares) of all completed fires
+    #[wasm_bindgen]
+    pub fn hectares(&self) -> Vec<f32> {
+        self.fires.iter().map(|f| f.hectares).collect()
+    }
+
+    /// Durations (in ticks) of all completed fires
+    #[wasm_bindgen]
+    pub fn durations(&self) -> Vec<u32> {
+        self.fires.iter().map(|f| f.duration).collect()
+    }
+
+    /// Histogram of fire areas with `bins` bins. With `logarithmic` the bins
+    /// are evenly spaced in log space, which is what you want for scale-free
+    /// distributions.
+    #[wasm_bindgen]
+    pub fn histogram(&self, bins: usize, logarithmic: bool) -> FireSizeHistogram {
+        let areas = self.sorted_areas();
+        let (Some(&min), Some(&max)) = (areas.first(), areas.last()) else {
+            return FireSizeHistogram::default();
+        };
+        let bins = bins.max(1);
+        let (lo, hi) = (min as f64, max as f64 + 1.0);
+        let bin_edges: Vec<f64> = (0..=bins)
+            .map(|i| {
+                let t = i as f64 / bins as f64;
+                if logarithmic {
+                    lo * (hi / lo).powf(t)
+                } else {
+                    lo + (hi - lo) * t
+                }
+            })
+            .collect();
+        let mut counts = vec![0; bins];
+        for area in areas {
+            let area = area as f64;
+            let bin = bin_edges[1..]
+                .iter()
+                .position(|&edge| area < edge)
+                .unwrap_or(bins - 1);
+            counts[bin] += 1;
+        }
+        FireSizeHistogram { bin_edges, counts }
+    }
+
+    /// Empirical complementary cumulative distribution of fire areas
+    #[wasm_bindgen]
+    pub fn ccdf(&self) -> FireSizeCcdf {
+        let areas = self.sorted_areas();
+        let n = areas.len() as f64;
+        let mut ccdf = FireSizeCcdf::default();
+        for (i, &area) in areas.iter().enumerate() {
+            if ccdf.sizes.last() != Some(&area) {
+                ccdf.sizes.push(area);
+                ccdf.probabilities.push((areas.len() - i) as f64 / n);
+            }
+        }
+        ccdf
+    }
+
+    /// Maximum-likelihood power-law exponent for fires of at least `x_min`
+    /// cells, using the discrete approximation from Clauset, Shalizi & Newman
+    /// (2009): `alpha = 1 + n / sum(ln(x / (x_min - 0.5)))`.
+    ///
+    /// Returns `None` if fewer than two fires are large enough.
+    #[wasm_bindgen]
+    pub fn power_law_fit(&self, x_min: u32) -> Option<PowerLawFit> {
+        let x_min = x_min.max(1);
+        let shifted_min = x_min as f64 - 0.5;
+        let (n, log_sum) = self
+            .fires
+            .iter()
+            .filter(|f| f.area >= x_min)
+            .fold((0u32, 0.0), |(n, sum), f| {
+                (n + 1, sum + (f.area as f64 / shifted_min).ln())
+            });
+        if n < 2 || log_sum <= 0.0 {
+            return None;
+        }
+        let alpha = 1.0 + n as f64 / log_sum;
+        Some(PowerLawFit {
+            alpha,
+            std_error: (alpha - 1.0) / (n as f64).sqrt(),
+            x_min,
+            sample_size: n,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::events::GpuEvent;
+
+    fn record(ignition_tick: u32, area: u32, last_active_tick: u32) -> GpuFireRecord {
+        GpuFireRecord {
+            ignition_tick,
+            origin: 0,
+            area,
+            burning: 0,
+            last_active_tick,
+            treatment: 0,
+            lit: 0,
+        }
+    }
+
+    /// A catalogue of completed fires with the given areas
+    fn catalogue(areas: &[u32]) -> FireCatalogue {
+        let records: Vec<_> = areas.iter().map(|&area| record(0, area, 1)).collect();
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(records.len() as u32, &records, &GridHistory::new(10), 1.0);
+        catalogue
+    }
+
+    fn event(step: u32, cell: u32, kind: EventKind, fire_id: u32) -> GpuEvent {
+        GpuEvent {
+            step,
+            cell,
+            kind: kind as u32,
+            fire_id,
+        }
+    }
+
+    #[test]
+    fn check_areas_catches_cells_counted_twice() {
+        let mut events = EventLog::default();
+        let records = [
+            event(0, 9, EventKind::TreeDied, 0),
+            event(1, 0, EventKind::Ignition, 1),
+            event(2, 1, EventKind::Spread, 1),
+            event(3, 0, EventKind::BurntOut, 1),
+            // Cell 0 catching fire again from the same fire
+            event(4, 0, EventKind::Spread, 1),
+            event(6, 0, EventKind::BurntOut, 1),
+            event(6, 1, EventKind::BurntOut, 1),
+            event(8, 9, EventKind::TreeDied, 0),
+        ];
+        events.ingest(records.len() as u32, &records, &GridHistory::new(10));
+
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(1, &[record(1, 2, 6)], &GridHistory::new(10), 1.0);
+        assert!(catalogue.check_areas(&events).is_ok());
+
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(1, &[record(1, 3, 6)], &GridHistory::new(10), 1.0);
+        assert!(catalogue.check_areas(&events).is_err());
+    }
+
+    #[test]
+    fn check_areas_skips_fires_outside_the_log() {
+        let mut events = EventLog::default();
+        let records = [event(1, 0, EventKind::Ignition, 1)];
+        events.ingest(records.len() as u32, &records, &GridHistory::new(10));
+
+        // Still burning when the log was last read back
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(1, &[record(1, 5, 4)], &GridHistory::new(10), 1.0);
+        assert!(catalogue.check_areas(&events).is_ok());
+    }
+
+    #[test]
+    fn ingest_waits_for_fires_to_burn_out() {
+        let mut burning = record(3, 4, 5);
+        burning.burning = 2;
+        burning.origin = 23;
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(2, &[record(1, 2, 6), burning], &GridHistory::new(10), 0.5);
+        assert_eq!(catalogue.len(), 1);
+        assert_eq!(catalogue.active_fires(), 1);
+
+        burning.burning = 0;
+        catalogue.ingest(2, &[record(1, 2, 6), burning], &GridHistory::new(10), 0.5);
+        assert_eq!(catalogue.active_fires(), 0);
+        let fire = catalogue.fire(1).unwrap();
+        assert_eq!((fire.id, fire.x, fire.y), (2, 3, 2));
+        assert_eq!(fire.hectares, 2.0);
+        assert_eq!(fire.duration, 3);
+    }
+
+    #[test]
+    fn ingest_keeps_burns_apart() {
+        let mut burn = record(1, 7, 4);
+        burn.treatment = 3;
+        burn.lit = 5;
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(1, &[burn], &GridHistory::new(10), 1.0);
+        assert!(catalogue.is_empty());
+        let burn = catalogue.burn(0).unwrap();
+        assert_eq!((burn.treatment_event, burn.escaped_area), (2, 2));
+    }
+
+    #[test]
+    fn ingest_counts_overwritten_fires_as_lost() {
+        let mut burning = record(1, 1, 1);
+        burning.burning = 1;
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(1, &[burning, burning], &GridHistory::new(10), 1.0);
+        // Fire 3 has taken fire 1's slot
+        catalogue.ingest(3, &[burning, burning], &GridHistory::new(10), 1.0);
+        assert_eq!(catalogue.lost_fires(), 1);
+        assert_eq!(catalogue.active_fires(), 2);
+    }
+
+    #[test]
+    fn ingest_tolerates_older_snapshots() {
+        let mut burning = record(1, 1, 1);
+        burning.burning = 1;
+        let mut catalogue = FireCatalogue::default();
+        catalogue.ingest(2, &[burning, burning], &GridHistory::new(10), 1.0);
+        catalogue.ingest(1, &[burning, burning], &GridHistory::new(10), 1.0);
+        assert_eq!(catalogue.lost_fires(), 0);
+        assert_eq!(catalogue.active_fires(), 2);
+    }
+
+    #[test]
+    fn histogram_bins_areas() {
+        let linear = catalogue(&[1, 2, 3, 4]).histogram(2, false);
+        assert_eq!(linear.bin_edges, [1.0, 3.0, 5.0]);
+        assert_eq!(linear.counts, [2, 2]);
+
+        let logarithmic = catalogue(&[1, 10, 100]).histogram(2, true);
+        assert_eq!(logarithmic.counts, [2, 1]);
+        assert!(
+            FireCatalogue::default()
+                .histogram(2, true)
+                .counts
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn ccdf_counts_fires_at_least_each_size() {
+        let ccdf = catalogue(&[5, 1, 2, 1]).ccdf();
+        assert_eq!(ccdf.sizes, [1, 2, 5]);
+        assert_eq!(ccdf.probabilities, [1.0, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn power_law_fit_matches_the_estimator() {
+        let fires = catalogue(&[1, 2, 4]);
+        let fit = fires.power_law_fit(1).unwrap();
+        // ln(2) + ln(4) + ln(8) = 6 ln(2)
+        let alpha = 1.0 + 3.0 / (6.0 * 2f64.ln());
+        assert!((fit.alpha - alpha).abs() < 1e-12);
+        assert!((fit.std_error - (alpha - 1.0) / 3f64.sqrt()).abs() < 1e-12);
+        assert_eq!(fit.sample_size, 3);
+
+        assert_eq!(fires.power_law_fit(3).map(|f| f.sample_size), None);
+    }
+}
@@ -1,5 +1,5 @@
 use std::sync::{
-    Arc,
+    Arc, Mutex,
     atomic::{AtomicBool, AtomicU32, Ordering},
 };
 
@@ -14,7 +14,19 @@ use wgpu::{
     wgt::CommandEncoderDescriptor,
 };
 
-use crate::sim::{BurnState, CellState, SimulationFrame, SimulationParameters};
+use crate::sim::{
+    BurnState, CellState, ConfigurableParameters, FireKind, GridHistory, Neighbourhood,
+    SimulationFrame, SimulationParameters,
+    climate::Climate,
+    events::{EVENT_BUFFER_CAPACITY, EventLog, GpuEvent},
+    fires::{FIRE_LEDGER_CAPACITY, FireCatalogue, GpuFireRecord},
+    management::{Management, TreatmentAction, TreatmentLog, TreatmentScheduler},
+    stats::FrameSummary,
+    storms::{GpuStormTracks, StormProcess},
+    suppression::{GpuSuppression, SuppressionEffort},
+    timeline::{Snapshot, SnapshotLog, TimedEvent, Timeline, TimelineEvent, TimelinePlayer},
+    weather::WeatherSeries,
+};
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -22,7 +34,82 @@ pub struct GpuCell {
     pub tree: f32,
     pub underbrush: f32,
     pub burning: u32,
-    pub padding: u32,
+    /// ID of the fire this cell is burning in, or the last fire it burnt in
+    /// once the fire has gone out. 0 if it hasn't burnt.
+    pub fire_id: u32,
+    /// Age of the tree in years
+    pub age: f32,
+    /// Health (0 - 1) of the tree
+    pub health: f32,
+    /// Alert level (0 - 1) of the tree
+    pub alert: f32,
+    /// Viable seeds in the cell's seed bank
+    pub seeds: f32,
+    /// Kind of fire burning in the cell, 0 if it is not burning
+    pub fire_kind: u32,
+    /// Severity (0 - 1) of the last fire to burn the cell
+    pub severity: f32,
+}
+
+impl From<&CellState> for GpuCell {
+    fn from(cell: &CellState) -> Self {
+        let (burning, fire_id, fire_kind) = match cell.burning {
+            BurnState::NotBurning => (0, 0, 0),
+            BurnState::Burning {
+                ticks_remaining,
+                fire_id,
+                kind,
+            } => (ticks_remaining, fire_id, kind as u32),
+        };
+        GpuCell {
+            tree: cell.tree,
+            underbrush: cell.underbrush,
+            burning,
+            fire_id,
+            age: cell.age,
+            health: cell.health,
+            alert: cell.alert,
+            seeds: cell.seeds,
+            fire_kind,
+            severity: cell.severity,
+        }
+    }
+}
+
+impl From<&GpuCell> for CellState {
+    fn from(cell: &GpuCell) -> Self {
+        CellState {
+            burning: if cell.burning > 0 {
+                BurnState::Burning {
+                    ticks_remaining: cell.burning,
+                    fire_id: cell.fire_id,
+                    kind: if cell.fire_kind == FireKind::Crown as u32 {
+                        FireKind::Crown
+                    } else {
+                        FireKind::Surface
+                    },
+                }
+            } else {
+                BurnState::NotBurning
+            },
+            underbrush: cell.underbrush,
+            tree: cell.tree,
+            age: cell.age,
+            health: cell.health,
+            alert: cell.alert,
+            seeds: cell.seeds,
+            severity: cell.severity,
+        }
+    }
+}
+
+/// The device limits the simulation needs: the downlevel defaults, plus the
+/// storage buffers that WebGPU guarantees, which the compute shader uses up
+pub fn required_limits() -> wgpu::Limits {
+    wgpu::Limits {
+        max_storage_buffers_per_shader_stage: 8,
+        ..wgpu::Limits::downlevel_defaults()
+    }
 }
 
 /// Shared GPU resources (device, queue, instance)
@@ -56,7 +143,7 @@ impl GpuResources {
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("firesim device"),
                 required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::downlevel_defaults(),
+                required_limits: required_limits(),
                 experimental_features: wgpu::ExperimentalFeatures::disabled(),
                 memory_hints: wgpu::MemoryHints::MemoryUsage,
                 trace: wgpu::Trace::Off,
@@ -72,9 +159,7 @@ impl GpuResources {
     }
 
     /// Create GPU resources with a compatible surface for rendering
-    pub async fn new_with_surface(
-        surface: &wgpu::Surface<'_>,
-    ) -> Result<Self, anyhow::Error> {
+    pub async fn new_with_surface(surface: &wgpu::Surface<'_>) -> Result<Self, anyhow::Error> {
         let instance = Instance::new(&wgpu::InstanceDescriptor::default());
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
@@ -96,7 +181,7 @@ impl GpuResources {
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("firesim device"),
                 required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::downlevel_defaults(),
+                required_limits: required_limits(),
                 experimental_features: wgpu::ExperimentalFeatures::disabled(),
                 memory_hints: wgpu::MemoryHints::MemoryUsage,
                 trace: wgpu::Trace::Off,
@@ -112,26 +197,12 @@ impl GpuResources {
     }
 }
 
+/// Compute context for running the simulation headlessly, e.g. on a worker.
+/// Completed frames are read back and sent through `frame_tx` on request.
 pub struct ComputeContext {
-    buf_1: Buffer,
-    buf_2: Buffer,
-    cells_bg: BindGroup,
-    cells_bg_rev: BindGroup,
-    params_bind_group: BindGroup,
-    params_buf: Buffer,
-    size_bind_group: BindGroup,
-    flipped_bufs: bool,
-    time_bind_group: BindGroup,
-    time_buf: Buffer,
-    old_params: SimulationParameters,
-    queue: Arc<Queue>,
-    pipeline: ComputePipeline,
-    device: Arc<Device>,
-    width: usize,
-    height: usize,
+    pipeline: SimulationPipeline,
     staging_buf: Buffer,
     staging_mapped: Arc<AtomicBool>,
-    steps: Arc<AtomicU32>,
     frame_tx: WatchSender<SimulationFrame>,
 }
 
@@ -160,7 +231,7 @@ pub async fn create_device() -> Result<(Device, Queue), anyhow::Error> {
         .request_device(&wgpu::DeviceDescriptor {
             label: Some("firesim compute device"),
             required_features: wgpu::Features::empty(),
-            required_limits: wgpu::Limits::downlevel_defaults(),
+            required_limits: required_limits(),
             experimental_features: wgpu::ExperimentalFeatures::disabled(),
             memory_hints: wgpu::MemoryHints::MemoryUsage,
             trace: wgpu::Trace::Off,
@@ -173,101 +244,154 @@ impl ComputeContext {
     /// Get the current output buffer (the one that was last written to)
     /// This is the buffer that should be used for rendering
     pub fn current_output_buffer(&self) -> &Buffer {
-        if self.flipped_bufs {
-            &self.buf_1
-        } else {
-            &self.buf_2
-        }
+        self.pipeline.current_output_buffer()
     }
 
     /// Get the current input buffer
     pub fn current_input_buffer(&self) -> &Buffer {
-        if self.flipped_bufs {
-            &self.buf_2
-        } else {
-            &self.buf_1
-        }
+        self.pipeline.current_input_buffer()
     }
 
     /// Get both buffers for creating render bind groups
     pub fn get_buffers(&self) -> (&Buffer, &Buffer) {
-        (&self.buf_1, &self.buf_2)
+        self.pipeline.buffers()
     }
 
     /// Returns true if buffers are flipped (buf_2 is input, buf_1 is output)
     pub fn is_flipped(&self) -> bool {
-        self.flipped_bufs
+        self.pipeline.is_flipped()
     }
 
     /// Get grid dimensions
     pub fn dimensions(&self) -> (usize, usize) {
-        (self.width, self.height)
+        self.pipeline.dimensions()
     }
 
     /// Get shared device reference
     pub fn device(&self) -> &Arc<Device> {
-        &self.device
+        &self.pipeline.device
     }
 
     /// Get shared queue reference
     pub fn queue(&self) -> &Arc<Queue> {
-        &self.queue
+        &self.pipeline.queue
     }
 
-    pub fn compute_step(&mut self, parameters: SimulationParameters) {
-        if parameters != self.old_params {
-            self.update_params(parameters);
-        }
-        let num_dispatches = self.buf_1.size().div_ceil(64);
-        let mut encoder = self
-            .device
-            .create_command_encoder(&CommandEncoderDescriptor {
-                label: Some("simulation step encoder"),
-            });
-        self.queue.write_buffer(
-            &self.time_buf,
-            0,
-            bytemuck::bytes_of(&self.steps.load(Ordering::Relaxed)),
-        );
-        {
-            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some("simulation step compute pass"),
-                ..Default::default()
-            });
-            pass.set_pipeline(&self.pipeline);
-            pass.set_bind_group(
-                0,
-                if self.flipped_bufs {
-                    &self.cells_bg_rev
-                } else {
-                    &self.cells_bg
-                },
-                &[],
-            );
-            pass.set_bind_group(1, &self.params_bind_group, &[]);
-            pass.set_bind_group(2, &self.size_bind_group, &[]);
-            pass.set_bind_group(3, &self.time_bind_group, &[]);
-            pass.dispatch_workgroups(num_dispatches as u32, 1, 1);
-        }
-        if !self.staging_mapped.load(Ordering::SeqCst) {
-            let src_buf = if self.flipped_bufs {
-                &self.buf_1
-            } else {
-                &self.buf_2
-            };
-            encoder.copy_buffer_to_buffer(src_buf, 0, &self.staging_buf, 0, src_buf.size());
-        }
+    /// Get the catalogue of completed fires
+    pub fn fire_catalogue(&self) -> &Arc<Mutex<FireCatalogue>> {
+        self.pipeline.fire_catalogue()
+    }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
+    /// Share a fire catalogue with the owner of this context, so that it can
+    /// be read from another thread
+    pub fn set_fire_catalogue(&mut self, catalogue: Arc<Mutex<FireCatalogue>>) {
+        self.pipeline.set_fire_catalogue(catalogue);
+    }
 
-        self.flipped_bufs = !self.flipped_bufs;
-        self.steps.fetch_add(1, Ordering::Relaxed);
+    /// Get the log of cell events
+    pub fn event_log(&self) -> &Arc<Mutex<EventLog>> {
+        self.pipeline.event_log()
     }
 
-    fn update_params(&mut self, new: SimulationParameters) {
-        self.old_params = new;
-        self.queue
-            .write_buffer(&self.params_buf, 0, bytemuck::bytes_of(&new));
+    /// Share an event log with the owner of this context, so that it can be
+    /// read from another thread
+    pub fn set_event_log(&mut self, log: Arc<Mutex<EventLog>>) {
+        self.pipeline.set_event_log(log);
+    }
+
+    /// Change the neighbourhood that fire spreads through
+    pub fn set_neighbourhood(&mut self, neighbourhood: Neighbourhood) {
+        self.pipeline.set_neighbourhood(neighbourhood);
+    }
+
+    /// Change the scheduled fuel treatments
+    pub fn set_management(&mut self, management: Management) {
+        self.pipeline.set_management(management);
+    }
+
+    /// Get the record of fuel treatments carried out
+    pub fn treatment_log(&self) -> &Arc<Mutex<TreatmentLog>> {
+        self.pipeline.treatment_log()
+    }
+
+    /// Read back the suppression effort spent since the run started
+    pub async fn suppression_effort(&self) -> Result<SuppressionEffort, anyhow::Error> {
+        self.pipeline.suppression_effort().await
+    }
+
+    /// Replace the scripted timeline
+    pub fn set_timeline(&mut self, timeline: Timeline) {
+        self.pipeline.set_timeline(timeline);
+    }
+
+    /// Get the snapshots taken by the timeline
+    pub fn snapshots(&self) -> &Arc<Mutex<SnapshotLog>> {
+        self.pipeline.snapshots()
+    }
+
+    /// Replace the snapshot log that the timeline's snapshots are read back
+    /// into
+    pub fn set_snapshot_log(&mut self, snapshots: Arc<Mutex<SnapshotLog>>) {
+        self.pipeline.set_snapshot_log(snapshots);
+    }
+
+    /// Run the timeline events that are due before the next step
+    pub fn advance_timeline(&mut self, config: &mut ConfigurableParameters) {
+        self.pipeline.advance_timeline(config);
+    }
+
+    /// Get the number of steps run so far
+    pub fn steps(&self) -> u32 {
+        self.pipeline.steps()
+    }
+
+    /// Read back the current cell state, waiting for all submitted steps to
+    /// finish
+    pub async fn read_frame(&self) -> Result<SimulationFrame, anyhow::Error> {
+        self.pipeline.read_frame().await
+    }
+
+    /// Read the fire ledger and event buffer back, see
+    /// `SimulationPipeline::flush`
+    pub async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.pipeline.flush().await
+    }
+
+    /// Read back a whole-grid summary, see `SimulationPipeline::read_summary`
+    pub async fn read_summary(&self) -> Result<FrameSummary, anyhow::Error> {
+        self.pipeline.read_summary().await
+    }
+
+    /// Replace the cell state with `frame`, which may be a different size
+    /// from the grid, rebuilding the staging buffer to fit
+    pub fn resize(&mut self, frame: &SimulationFrame) -> Result<(), anyhow::Error> {
+        self.pipeline.resize(frame)?;
+        self.staging_buf = self.pipeline.device.create_buffer(&BufferDescriptor {
+            label: Some("staging buffer"),
+            size: self.pipeline.buf_1.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // A readback of the old staging buffer may still be in flight
+        self.staging_mapped = Arc::new(AtomicBool::new(false));
+        Ok(())
+    }
+
+    pub fn compute_step(&mut self, parameters: SimulationParameters) {
+        self.pipeline.step(parameters);
+        if !self.staging_mapped.load(Ordering::SeqCst) {
+            let mut encoder =
+                self.pipeline
+                    .device
+                    .create_command_encoder(&CommandEncoderDescriptor {
+                        label: Some("frame staging copy encoder"),
+                    });
+            let src_buf = self.pipeline.current_output_buffer();
+            encoder.copy_buffer_to_buffer(src_buf, 0, &self.staging_buf, 0, src_buf.size());
+            self.pipeline
+                .queue
+                .submit(std::iter::once(encoder.finish()));
+        }
     }
 
     /// Create a compute context using shared GPU resources
@@ -275,6 +399,7 @@ impl ComputeContext {
         resources: &GpuResources,
         start: SimulationFrame,
         parameters: SimulationParameters,
+        neighbourhood: Neighbourhood,
         frame_tx: WatchSender<SimulationFrame>,
     ) -> Result<Self, anyhow::Error> {
         Self::create_internal(
@@ -282,6 +407,7 @@ impl ComputeContext {
             Arc::clone(&resources.queue),
             start,
             parameters,
+            neighbourhood,
             frame_tx,
         )
     }
@@ -291,9 +417,17 @@ impl ComputeContext {
         queue: Queue,
         start: SimulationFrame,
         parameters: SimulationParameters,
+        neighbourhood: Neighbourhood,
         frame_tx: WatchSender<SimulationFrame>,
     ) -> Result<Self, anyhow::Error> {
-        Self::create_internal(Arc::new(device), Arc::new(queue), start, parameters, frame_tx)
+        Self::create_internal(
+            Arc::new(device),
+            Arc::new(queue),
+            start,
+            parameters,
+            neighbourhood,
+            frame_tx,
+        )
     }
 
     fn create_internal(
@@ -301,86 +435,538 @@ impl ComputeContext {
         queue: Arc<Queue>,
         start: SimulationFrame,
         parameters: SimulationParameters,
+        neighbourhood: Neighbourhood,
         frame_tx: WatchSender<SimulationFrame>,
     ) -> Result<Self, anyhow::Error> {
-        let start_data: Vec<_> = start
-            .grid
-            .clone()
-            .into_iter()
-            .map(|i| GpuCell {
-                burning: match i.burning {
-                    BurnState::NotBurning => 0,
-                    BurnState::Burning { ticks_remaining } => ticks_remaining,
-                },
-                tree: if i.tree { 1.0 } else { 0.0 },
-                underbrush: i.underbrush,
-                padding: 0,
-            })
-            .collect();
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("simulation compute shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("./shader.wgsl").into()),
-        });
+        let pipeline = SimulationPipeline::new(device, queue, &start, parameters, neighbourhood)?;
 
-        // Create buffers with STORAGE usage for compute and rendering
-        let buf_1 = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("cells buffer 1"),
-            contents: bytemuck::cast_slice(&start_data),
-            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        let staging_buf = pipeline.device.create_buffer(&BufferDescriptor {
+            label: Some("staging buffer"),
+            size: pipeline.buf_1.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
-        let buf_2 = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("cells buffer 2"),
-            size: buf_1.size(),
-            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+        Ok(Self {
+            pipeline,
+            staging_buf,
+            staging_mapped: Arc::new(AtomicBool::new(false)),
+            frame_tx,
+        })
+    }
+
+    pub fn send_latest(&self) {
+        if !self
+            .staging_mapped
+            .load(std::sync::atomic::Ordering::SeqCst)
+        {
+            let tx = self.frame_tx.clone();
+            let buf = self.staging_buf.clone();
+            let (width, height) = self.pipeline.dimensions();
+            self.staging_mapped.store(true, Ordering::SeqCst);
+            let staging_mapped = Arc::clone(&self.staging_mapped);
+            self.staging_buf.map_async(MapMode::Read, .., move |v| {
+                if v.is_err() {
+                    log::error!("map error");
+                    return;
+                }
+                let buf_view = buf.get_mapped_range(..);
+                let cells: &[GpuCell] = bytemuck::cast_slice(buf_view.as_ref());
+                let frame = SimulationFrame {
+                    grid: cells.iter().map(CellState::from).collect(),
+                    width,
+                    height,
+                };
+                drop(buf_view);
+                buf.unmap();
+                staging_mapped.store(false, Ordering::SeqCst);
+                tx.send(frame);
+            });
+        }
+    }
+}
+
+/// How often (in steps) the fire ledger is read back into the fire catalogue
+const FIRE_LEDGER_POLL_INTERVAL: u32 = 16;
+
+/// How often (in steps) the event buffer is read back into the event log
+const EVENT_POLL_INTERVAL: u32 = 4;
+
+/// Ember slot value that makes a cell catch fire as a new fire, matching
+/// `IGNITION` in the shader
+const IGNITION_EMBER: u32 = u32::MAX;
+
+/// Fields set by a cell edit, matching the `EDIT_` constants in the shader
+const EDIT_TREE: u32 = 1;
+const EDIT_UNDERBRUSH: u32 = 2;
+const EDIT_IGNITE: u32 = 4;
+
+/// A change to a list of cells, as laid out at the start of the cell edit
+/// buffer. The indices of the cells follow it.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+struct GpuCellEdit {
+    /// Which fields to set (`EDIT_` flags)
+    flags: u32,
+    tree: f32,
+    underbrush: f32,
+    /// Index + 1 of the prescribed burn in the treatment log
+    treatment: u32,
+    /// ID of the fire the edit lights, filled in on the GPU
+    fire_id: u32,
+    /// Number of cells to edit
+    count: u32,
+}
+
+/// Ember slot value of a storm's lightning strike, matching `STRIKE` in the
+/// shader
+const STRIKE_EMBER: u32 = u32::MAX - 1;
+
+/// The simulation compute pipeline and the double-buffered cell state it runs
+/// on. This is shared by the headless `ComputeContext` and the integrated
+/// `GpuSimRenderer`.
+pub struct SimulationPipeline {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    buf_1: Buffer,
+    buf_2: Buffer,
+    cells_bg: BindGroup,
+    cells_bg_rev: BindGroup,
+    params_bind_group: BindGroup,
+    params_buf: Buffer,
+    size_bind_group: BindGroup,
+    kernel_buf: Buffer,
+    neighbourhood: Neighbourhood,
+    dispersal_buf: Buffer,
+    weather: WeatherSeries,
+    climate: Climate,
+    flipped_bufs: bool,
+    time_bind_group: BindGroup,
+    time_buf: Buffer,
+    /// Which of the fire's substeps is running, see `step`
+    substep_buf: Buffer,
+    old_params: SimulationParameters,
+    pipeline: ComputePipeline,
+    /// Lets fire spread again within a step, for fires that cross more than
+    /// one cell a tick
+    spread_pipeline: ComputePipeline,
+    /// Puts out every fire in the ledger, see `resize`
+    end_fires_pipeline: ComputePipeline,
+    /// Pipelines that change cells between steps, see `edit_cells`
+    edit_pipeline: ComputePipeline,
+    edit_fire_pipeline: ComputePipeline,
+    /// A `GpuCellEdit` followed by room for the index of every cell
+    edit_buf: Buffer,
+    width: usize,
+    height: usize,
+    /// The grid's width on every step of the run, to place events and fires
+    grid: GridHistory,
+    steps: u32,
+    fire_ledger: FireLedger,
+    event_buffer: EventBuffer,
+    /// Embers in flight, two slots per cell. Burning cells write embers into
+    /// one half while every cell reads (and clears) its slot in the other
+    /// half, swapping each step.
+    ember_buf: Buffer,
+    treatments: TreatmentScheduler,
+    /// Assets of the management plan, which suppression protects first
+    assets_buf: Buffer,
+    /// Suppression counters, see `GpuSuppression`
+    suppression_buf: Buffer,
+    timeline: TimelinePlayer,
+    storms: StormProcess,
+}
+
+/// GPU-side fire ledger and the machinery to read it back
+struct FireLedger {
+    buf: Buffer,
+    staging_buf: Buffer,
+    staging_mapped: Arc<AtomicBool>,
+    catalogue: Arc<Mutex<FireCatalogue>>,
+    /// Bumped whenever the ledger is cleared, so that a readback which was
+    /// already in flight is not folded into the fresh catalogue
+    generation: Arc<AtomicU32>,
+}
+
+impl FireLedger {
+    fn new(device: &Device) -> Self {
+        // The ledger is a `next_id` counter followed by the fire records
+        let size = (std::mem::size_of::<u32>()
+            + FIRE_LEDGER_CAPACITY * std::mem::size_of::<GpuFireRecord>())
+            as wgpu::BufferAddress;
+        let buf = device.create_buffer(&BufferDescriptor {
+            label: Some("fire ledger buffer"),
+            size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_buf = device.create_buffer(&BufferDescriptor {
+            label: Some("fire ledger staging buffer"),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
+        Self {
+            buf,
+            staging_buf,
+            staging_mapped: Arc::new(AtomicBool::new(false)),
+            catalogue: Arc::new(Mutex::new(FireCatalogue::default())),
+            generation: Arc::new(AtomicU32::new(0)),
+        }
+    }
 
-        let params_buf = device.create_buffer_init(&BufferInitDescriptor {
-            label: Some("simulation parameters buffer"),
-            contents: bytemuck::bytes_of(&parameters),
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+    /// Forget every fire, both on the GPU and in the catalogue
+    fn clear(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.clear_buffer(&self.buf, 0, None);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self
+            .catalogue
+            .lock()
+            .expect("failed to lock fire catalogue") = FireCatalogue::default();
+    }
+
+    /// Copy the ledger to the staging buffer and fold it into the catalogue
+    /// once the copy is mapped
+    fn poll(&self, device: &Device, queue: &Queue, grid: &GridHistory, cell_hectares: f32) {
+        if self.staging_mapped.load(Ordering::SeqCst) {
+            return;
+        }
+        self.staging_mapped.store(true, Ordering::SeqCst);
+        let staging_mapped = Arc::clone(&self.staging_mapped);
+        self.read_back(
+            device,
+            queue,
+            self.staging_buf.clone(),
+            grid,
+            cell_hectares,
+            move || staging_mapped.store(false, Ordering::SeqCst),
+        );
+    }
+
+    /// Read the ledger back into the catalogue, waiting for all submitted
+    /// steps to finish
+    async fn flush(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        grid: &GridHistory,
+        cell_hectares: f32,
+    ) -> Result<(), anyhow::Error> {
+        let staging_buf = device.create_buffer(&BufferDescriptor {
+            label: Some("fire ledger flush buffer"),
+            size: self.buf.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        self.read_back(device, queue, staging_buf, grid, cell_hectares, move || {
+            let _ = tx.send(());
         });
+        rx.receive()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("fire ledger readback was dropped"))
+    }
 
-        let cells_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("cells bind group layout"),
-            entries: &[
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
+    /// Copy the ledger to `staging_buf` and fold it into the catalogue once
+    /// the copy is mapped, then call `done`
+    fn read_back(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        staging_buf: Buffer,
+        grid: &GridHistory,
+        cell_hectares: f32,
+        done: impl FnOnce() + Send + 'static,
+    ) {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("fire ledger copy encoder"),
         });
+        encoder.copy_buffer_to_buffer(&self.buf, 0, &staging_buf, 0, self.buf.size());
+        queue.submit(std::iter::once(encoder.finish()));
 
-        let cells_bg = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("cells bind group (buf1 -> buf2)"),
-            layout: &cells_bg_layout,
-            entries: &[
-                BindGroupEntry {
-                    binding: 0,
-                    resource: buf_1.as_entire_binding(),
-                },
-                BindGroupEntry {
-                    binding: 1,
-                    resource: buf_2.as_entire_binding(),
-                },
-            ],
+        let buf = staging_buf.clone();
+        let catalogue = Arc::clone(&self.catalogue);
+        let grid = grid.clone();
+        let generation = Arc::clone(&self.generation);
+        let polled_generation = generation.load(Ordering::SeqCst);
+        staging_buf.map_async(MapMode::Read, .., move |v| {
+            if v.is_err() {
+                log::error!("fire ledger map error");
+                done();
+                return;
+            }
+            let buf_view = buf.get_mapped_range(..);
+            if generation.load(Ordering::SeqCst) == polled_generation {
+                let (header, records) = buf_view.split_at(std::mem::size_of::<u32>());
+                let allocated: u32 = *bytemuck::from_bytes(header);
+                let records: &[GpuFireRecord] = bytemuck::cast_slice(records);
+                catalogue
+                    .lock()
+                    .expect("failed to lock fire catalogue")
+                    .ingest(allocated, records, &grid, cell_hectares);
+            }
+            drop(buf_view);
+            buf.unmap();
+            done();
+        });
+    }
+}
+
+/// GPU-side event buffer and the machinery to read it back and empty it
+struct EventBuffer {
+    buf: Buffer,
+    staging_buf: Buffer,
+    staging_mapped: Arc<AtomicBool>,
+    log: Arc<Mutex<EventLog>>,
+    /// Bumped whenever the buffer is cleared, so that a readback which was
+    /// already in flight is not folded into the fresh log
+    generation: Arc<AtomicU32>,
+}
+
+impl EventBuffer {
+    fn new(device: &Device) -> Self {
+        // The buffer is a `count` counter followed by the events
+        let size = (std::mem::size_of::<u32>()
+            + EVENT_BUFFER_CAPACITY * std::mem::size_of::<GpuEvent>())
+            as wgpu::BufferAddress;
+        let buf = device.create_buffer(&BufferDescriptor {
+            label: Some("event buffer"),
+            size,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let staging_buf = device.create_buffer(&BufferDescriptor {
+            label: Some("event staging buffer"),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buf,
+            staging_buf,
+            staging_mapped: Arc::new(AtomicBool::new(false)),
+            log: Arc::new(Mutex::new(EventLog::default())),
+            generation: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Forget every event, both on the GPU and in the log
+    fn clear(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.clear_buffer(&self.buf, 0, None);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.log.lock().expect("failed to lock event log") = EventLog::default();
+    }
+
+    /// Copy the buffer to the staging buffer and reset its counter, then
+    /// fold the copy into the log once it is mapped. While a readback is in
+    /// flight the GPU keeps appending, so nothing is lost unless the buffer
+    /// fills up.
+    fn poll(&self, device: &Device, queue: &Queue, grid: &GridHistory) {
+        if self.staging_mapped.load(Ordering::SeqCst) {
+            return;
+        }
+        self.staging_mapped.store(true, Ordering::SeqCst);
+        let staging_mapped = Arc::clone(&self.staging_mapped);
+        self.read_back(device, queue, self.staging_buf.clone(), grid, move || {
+            staging_mapped.store(false, Ordering::SeqCst)
+        });
+    }
+
+    /// Read the buffer back into the log and empty it, waiting for all
+    /// submitted steps to finish. Buffers are mapped in the order their
+    /// copies were submitted, so a readback already in flight is folded in
+    /// first and the log stays in step order.
+    async fn flush(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        grid: &GridHistory,
+    ) -> Result<(), anyhow::Error> {
+        let staging_buf = device.create_buffer(&BufferDescriptor {
+            label: Some("event flush buffer"),
+            size: self.buf.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        self.read_back(device, queue, staging_buf, grid, move || {
+            let _ = tx.send(());
+        });
+        rx.receive()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("event readback was dropped"))
+    }
+
+    /// Copy the buffer to `staging_buf` and reset its counter, then fold the
+    /// copy into the log once it is mapped and call `done`
+    fn read_back(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        staging_buf: Buffer,
+        grid: &GridHistory,
+        done: impl FnOnce() + Send + 'static,
+    ) {
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("event buffer copy encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&self.buf, 0, &staging_buf, 0, self.buf.size());
+        encoder.clear_buffer(&self.buf, 0, Some(std::mem::size_of::<u32>() as u64));
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let buf = staging_buf.clone();
+        let log = Arc::clone(&self.log);
+        let grid = grid.clone();
+        let generation = Arc::clone(&self.generation);
+        let polled_generation = generation.load(Ordering::SeqCst);
+        staging_buf.map_async(MapMode::Read, .., move |v| {
+            if v.is_err() {
+                log::error!("event buffer map error");
+                done();
+                return;
+            }
+            let buf_view = buf.get_mapped_range(..);
+            if generation.load(Ordering::SeqCst) == polled_generation {
+                let (header, records) = buf_view.split_at(std::mem::size_of::<u32>());
+                let count: u32 = *bytemuck::from_bytes(header);
+                let records: &[GpuEvent] = bytemuck::cast_slice(records);
+                log.lock()
+                    .expect("failed to lock event log")
+                    .ingest(count, records, &grid);
+            }
+            drop(buf_view);
+            buf.unmap();
+            done();
+        });
+    }
+}
+
+impl SimulationPipeline {
+    pub fn new(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        start: &SimulationFrame,
+        parameters: SimulationParameters,
+        neighbourhood: Neighbourhood,
+    ) -> Result<Self, anyhow::Error> {
+        let cells_size = (start.width * start.height * std::mem::size_of::<GpuCell>()) as u64;
+        let max_size = device.limits().max_storage_buffer_binding_size as u64;
+        if start.width == 0 || start.height == 0 || cells_size > max_size {
+            return Err(anyhow::anyhow!(
+                "a {}x{} grid doesn't fit in the device's {max_size} byte storage buffers",
+                start.width,
+                start.height
+            ));
+        }
+        let start_data: Vec<GpuCell> = start.grid.iter().map(GpuCell::from).collect();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("simulation compute shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("./shader.wgsl").into()),
+        });
+
+        // Create buffers with STORAGE usage for compute and rendering
+        let buf_1 = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("cells buffer 1"),
+            contents: bytemuck::cast_slice(&start_data),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+        });
+
+        let buf_2 = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cells buffer 2"),
+            size: buf_1.size(),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let fire_ledger = FireLedger::new(&device);
+        let event_buffer = EventBuffer::new(&device);
+
+        let ember_buf = device.create_buffer(&BufferDescriptor {
+            label: Some("ember buffer"),
+            size: 2
+                * (start.width * start.height * std::mem::size_of::<u32>()) as wgpu::BufferAddress,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let edit_buf = device.create_buffer(&BufferDescriptor {
+            label: Some("cell edit buffer"),
+            size: (std::mem::size_of::<GpuCellEdit>()
+                + start.width * start.height * std::mem::size_of::<u32>())
+                as wgpu::BufferAddress,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let suppression_buf = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("suppression buffer"),
+            contents: bytemuck::bytes_of(&GpuSuppression::default()),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+        });
+
+        let params_buf = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("simulation parameters buffer"),
+            contents: bytemuck::bytes_of(&parameters),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let storage_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let cells_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("cells bind group layout"),
+            entries: &[
+                storage_entry(0),
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+                storage_entry(4),
+                storage_entry(5),
+                storage_entry(6),
+            ],
+        });
+
+        let cells_bg = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("cells bind group (buf1 -> buf2)"),
+            layout: &cells_bg_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: buf_1.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: buf_2.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: fire_ledger.buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: ember_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: suppression_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: event_buffer.buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: edit_buf.as_entire_binding(),
+                },
+            ],
         });
 
         let cells_bg_rev = device.create_bind_group(&BindGroupDescriptor {
@@ -395,6 +981,26 @@ impl ComputeContext {
                     binding: 1,
                     resource: buf_1.as_entire_binding(),
                 },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: fire_ledger.buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: ember_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: suppression_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: event_buffer.buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: edit_buf.as_entire_binding(),
+                },
             ],
         });
 
@@ -427,41 +1033,53 @@ impl ComputeContext {
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
+        let substep_buf = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("fire substep buffer"),
+            contents: &[0, 0, 0, 0],
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let uniform_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
         let time_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("time bind group layout"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
+            entries: &[uniform_entry(0), uniform_entry(1)],
         });
 
         let time_bg = device.create_bind_group(&BindGroupDescriptor {
             label: Some("time bind group"),
             layout: &time_bg_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: time_buf.as_entire_binding(),
-            }],
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: time_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: substep_buf.as_entire_binding(),
+                },
+            ],
         });
 
+        // The grid size, the neighbourhood kernels and the assets together
+        // describe the grid's geometry
         let size_bg_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("grid size bind group layout"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
+            label: Some("grid geometry bind group layout"),
+            entries: &[
+                uniform_entry(0),
+                uniform_entry(1),
+                uniform_entry(2),
+                uniform_entry(3),
+            ],
         });
 
         let size_buf = device.create_buffer_init(&BufferInitDescriptor {
@@ -470,13 +1088,45 @@ impl ComputeContext {
             usage: BufferUsages::UNIFORM,
         });
 
+        let kernel_buf = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("neighbourhood kernel buffer"),
+            contents: bytemuck::bytes_of(&neighbourhood.kernel()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let dispersal_buf = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("seed dispersal kernel buffer"),
+            contents: bytemuck::bytes_of(&Neighbourhood::seed_dispersal(&parameters).kernel()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let assets_buf = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("assets buffer"),
+            contents: bytemuck::bytes_of(&Management::default().gpu_assets()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
         let size_bg = device.create_bind_group(&BindGroupDescriptor {
-            label: Some("grid size bind group"),
+            label: Some("grid geometry bind group"),
             layout: &size_bg_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: size_buf.as_entire_binding(),
-            }],
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: size_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: kernel_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: dispersal_buf.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: assets_buf.as_entire_binding(),
+                },
+            ],
         });
 
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
@@ -490,23 +1140,26 @@ impl ComputeContext {
             push_constant_ranges: &[],
         });
 
-        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
-            label: Some("simulation compute pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: None,
-            compilation_options: PipelineCompilationOptions::default(),
-            cache: None,
-        });
-
-        let staging_buf = device.create_buffer(&BufferDescriptor {
-            label: Some("staging buffer"),
-            size: buf_1.size(),
-            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let create_pipeline = |label, entry_point| {
+            device.create_compute_pipeline(&ComputePipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        };
+        let pipeline = create_pipeline("simulation compute pipeline", "main");
+        let spread_pipeline = create_pipeline("fire spread compute pipeline", "spread_substep");
+        let end_fires_pipeline = create_pipeline("end fires compute pipeline", "end_all_fires");
+        let edit_pipeline = create_pipeline("cell edit compute pipeline", "apply_edit");
+        let edit_fire_pipeline =
+            create_pipeline("cell edit fire compute pipeline", "start_edit_fire");
 
         Ok(Self {
+            device,
+            queue,
             buf_1,
             buf_2,
             cells_bg,
@@ -514,63 +1167,703 @@ impl ComputeContext {
             params_buf,
             params_bind_group: params_bg,
             size_bind_group: size_bg,
+            kernel_buf,
+            neighbourhood,
+            dispersal_buf,
+            weather: WeatherSeries::default(),
+            climate: Climate::default(),
             flipped_bufs: false,
+            time_bind_group: time_bg,
+            time_buf,
+            substep_buf,
             old_params: parameters,
-            queue,
-            device,
             pipeline,
+            spread_pipeline,
+            end_fires_pipeline,
+            edit_pipeline,
+            edit_fire_pipeline,
+            edit_buf,
             width: start.width,
             height: start.height,
-            staging_buf,
-            staging_mapped: Arc::new(AtomicBool::new(false)),
-            time_bind_group: time_bg,
-            time_buf,
-            steps: Arc::new(AtomicU32::new(0)),
-            frame_tx,
+            grid: GridHistory::new(start.width),
+            steps: 0,
+            fire_ledger,
+            event_buffer,
+            ember_buf,
+            treatments: TreatmentScheduler::new(
+                Management::default(),
+                start.width,
+                start.height,
+                Arc::new(Mutex::new(TreatmentLog::default())),
+            ),
+            assets_buf,
+            suppression_buf,
+            timeline: TimelinePlayer::new(
+                Timeline::default(),
+                Arc::new(Mutex::new(SnapshotLog::default())),
+            ),
+            storms: StormProcess::default(),
         })
     }
 
-    pub fn send_latest(&self) {
-        if !self
-            .staging_mapped
-            .load(std::sync::atomic::Ordering::SeqCst)
+    /// Run one simulation step.
+    ///
+    /// Each step is submitted separately so that the time buffer write takes
+    /// effect before the compute pass reads it, and so is each of the fire's
+    /// extra substeps. The fuel moisture of the parameters is replaced with
+    /// the weather and climate of this step.
+    pub fn step(&mut self, mut parameters: SimulationParameters) {
+        parameters.climate_moisture = self
+            .climate
+            .moisture_multiplier(self.year(&parameters), &parameters);
+        parameters.fuel_moisture =
+            self.weather.fuel_moisture(self.steps, &parameters) * parameters.climate_moisture;
+        let treatments = self.treatments.due(self.steps, &parameters);
+        for cell in self
+            .storms
+            .strikes(self.steps, &parameters, self.width, self.height)
         {
-            let tx = self.frame_tx.clone();
-            let buf = self.staging_buf.clone();
-            let width = self.width;
-            let height = self.height;
-            self.staging_mapped.store(true, Ordering::SeqCst);
-            let staging_mapped = Arc::clone(&self.staging_mapped);
-            self.staging_buf.map_async(MapMode::Read, .., move |v| {
-                if v.is_err() {
-                    log::error!("map error");
-                    return;
-                }
-                let buf_view = buf.get_mapped_range(..);
-                let cells: &[GpuCell] = bytemuck::cast_slice(buf_view.as_ref());
-                let frame = SimulationFrame {
-                    grid: cells
-                        .iter()
-                        .map(|i| CellState {
-                            burning: if i.burning > 0 {
-                                BurnState::Burning {
-                                    ticks_remaining: i.burning,
-                                }
-                            } else {
-                                BurnState::NotBurning
-                            },
-                            underbrush: i.underbrush,
-                            tree: i.tree > 0.0,
-                        })
-                        .collect(),
+            self.strike(cell);
+        }
+        if parameters != self.old_params {
+            let dispersal = Neighbourhood::seed_dispersal(&parameters);
+            if dispersal != Neighbourhood::seed_dispersal(&self.old_params) {
+                self.queue.write_buffer(
+                    &self.dispersal_buf,
+                    0,
+                    bytemuck::bytes_of(&dispersal.kernel()),
+                );
+            }
+            self.old_params = parameters;
+            self.queue
+                .write_buffer(&self.params_buf, 0, bytemuck::bytes_of(&parameters));
+        }
+        self.queue
+            .write_buffer(&self.time_buf, 0, bytemuck::bytes_of(&self.steps));
+        // After the time buffer, so burns start on this step
+        for action in treatments {
+            self.apply_treatment(action);
+        }
+        // Start this step's suppression tallies from zero, keeping the last
+        // step's demand for the shader to share capacity out by
+        for offset in [
+            GpuSuppression::demand_offset(self.steps),
+            GpuSuppression::used_offset(),
+        ] {
+            self.queue
+                .write_buffer(&self.suppression_buf, offset, bytemuck::bytes_of(&0u32));
+        }
+
+        let cells = self.width * self.height;
+        self.run_pass(&self.pipeline, "simulation step", cells);
+        self.flipped_bufs = !self.flipped_bufs;
+        // The main pass is the first substep
+        for substep in 1..parameters.fire_substeps {
+            self.queue
+                .write_buffer(&self.substep_buf, 0, bytemuck::bytes_of(&substep));
+            self.run_pass(&self.spread_pipeline, "fire substep", cells);
+            self.flipped_bufs = !self.flipped_bufs;
+        }
+        self.steps += 1;
+
+        if self.steps.is_multiple_of(EVENT_POLL_INTERVAL) {
+            self.event_buffer
+                .poll(&self.device, &self.queue, &self.grid);
+        }
+        if self.steps.is_multiple_of(FIRE_LEDGER_POLL_INTERVAL) {
+            self.fire_ledger.poll(
+                &self.device,
+                &self.queue,
+                &self.grid,
+                parameters.cell_hectares,
+            );
+        }
+    }
+
+    /// Run a pipeline with the given number of invocations, reading the
+    /// current input buffer and writing the output buffer
+    fn run_pass(&self, pipeline: &ComputePipeline, label: &str, invocations: usize) {
+        let num_dispatches = invocations.div_ceil(64) as u32;
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some(&format!("{label} encoder")),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some(&format!("{label} compute pass")),
+                ..Default::default()
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(
+                0,
+                if self.flipped_bufs {
+                    &self.cells_bg_rev
+                } else {
+                    &self.cells_bg
+                },
+                &[],
+            );
+            pass.set_bind_group(1, &self.params_bind_group, &[]);
+            pass.set_bind_group(2, &self.size_bind_group, &[]);
+            pass.set_bind_group(3, &self.time_bind_group, &[]);
+            pass.dispatch_workgroups(num_dispatches, 1, 1);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Write a treatment into the cells that the next step reads from
+    fn apply_treatment(&self, action: TreatmentAction) {
+        match &action {
+            TreatmentAction::Ignite { cells, event } => self.edit_cells(
+                cells,
+                GpuCellEdit {
+                    flags: EDIT_IGNITE,
+                    treatment: event + 1,
+                    ..Default::default()
+                },
+            ),
+            TreatmentAction::Thin(cells) => self.edit_cells(
+                cells,
+                GpuCellEdit {
+                    flags: EDIT_UNDERBRUSH,
+                    underbrush: 0.0,
+                    ..Default::default()
+                },
+            ),
+        }
+    }
+
+    /// Change the given cells of the buffer that the next step reads from.
+    /// The change is made on the GPU, so only the fields in `edit` are
+    /// written and cells that are already burning aren't lit again.
+    fn edit_cells(&self, cells: &[u32], edit: GpuCellEdit) {
+        if cells.is_empty() {
+            return;
+        }
+        let edit = GpuCellEdit {
+            count: cells.len() as u32,
+            ..edit
+        };
+        self.queue
+            .write_buffer(&self.edit_buf, 0, bytemuck::bytes_of(&edit));
+        self.queue.write_buffer(
+            &self.edit_buf,
+            std::mem::size_of::<GpuCellEdit>() as wgpu::BufferAddress,
+            bytemuck::cast_slice(cells),
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("cell edit encoder"),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("cell edit compute pass"),
+                ..Default::default()
+            });
+            // The same bind groups as the next step, whose input is edited
+            pass.set_bind_group(
+                0,
+                if self.flipped_bufs {
+                    &self.cells_bg_rev
+                } else {
+                    &self.cells_bg
+                },
+                &[],
+            );
+            pass.set_bind_group(1, &self.params_bind_group, &[]);
+            pass.set_bind_group(2, &self.size_bind_group, &[]);
+            pass.set_bind_group(3, &self.time_bind_group, &[]);
+            if edit.flags & EDIT_IGNITE != 0 {
+                pass.set_pipeline(&self.edit_fire_pipeline);
+                pass.dispatch_workgroups(1, 1, 1);
+            }
+            pass.set_pipeline(&self.edit_pipeline);
+            pass.dispatch_workgroups(edit.count.div_ceil(64), 1, 1);
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Run the timeline events that are due before the next step. Parameter
+    /// changes are applied to `config`, which the caller should build the
+    /// step's parameters from.
+    pub fn advance_timeline(&mut self, config: &mut ConfigurableParameters) {
+        for event in self.timeline.due(config) {
+            match event {
+                TimelineEvent::Ignite { x, y } => self.ignite(y as usize * self.width + x as usize),
+                TimelineEvent::Edit {
+                    x,
+                    y,
                     width,
                     height,
-                };
-                drop(buf_view);
-                buf.unmap();
-                staging_mapped.store(false, Ordering::SeqCst);
-                tx.send(frame);
+                    tree,
+                    underbrush,
+                } => {
+                    let grid_width = self.width as u32;
+                    let cells: Vec<u32> = (y..y + height)
+                        .flat_map(|row| (x..x + width).map(move |col| row * grid_width + col))
+                        .collect();
+                    let mut edit = GpuCellEdit::default();
+                    if let Some(tree) = tree {
+                        edit.flags |= EDIT_TREE;
+                        edit.tree = tree;
+                    }
+                    if let Some(underbrush) = underbrush {
+                        edit.flags |= EDIT_UNDERBRUSH;
+                        edit.underbrush = underbrush;
+                    }
+                    self.edit_cells(&cells, edit);
+                }
+                TimelineEvent::Snapshot { label } => self.snapshot(label),
+                // Already applied to `config` by the player
+                TimelineEvent::SetParameter { .. } => {}
+            }
+        }
+    }
+
+    /// Make a cell catch fire on the next step, as a new fire. The ignition
+    /// is passed in the cell's ember slot, so it goes through the same
+    /// checks as any other way of catching fire.
+    fn ignite(&self, cell: usize) {
+        self.write_ember_slot(cell, IGNITION_EMBER);
+    }
+
+    /// Strike a cell with lightning on the next step, which starts a fire if
+    /// the fuel is dry enough
+    fn strike(&self, cell: usize) {
+        self.write_ember_slot(cell, STRIKE_EMBER);
+    }
+
+    /// Overwrite a cell's slot in the half of the ember buffer that the next
+    /// step reads
+    fn write_ember_slot(&self, cell: usize, value: u32) {
+        let read_half = 1 - (self.steps as usize & 1);
+        let slot = read_half * self.width * self.height + cell;
+        self.queue.write_buffer(
+            &self.ember_buf,
+            (slot * std::mem::size_of::<u32>()) as wgpu::BufferAddress,
+            bytemuck::bytes_of(&value),
+        );
+    }
+
+    /// Copy the current cells and read them back into the snapshot log in
+    /// the background
+    fn snapshot(&self, label: String) {
+        let src_buf = self.current_output_buffer();
+        let readback_buf = self.device.create_buffer(&BufferDescriptor {
+            label: Some("snapshot readback buffer"),
+            size: src_buf.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("snapshot encoder"),
+            });
+        encoder.copy_buffer_to_buffer(src_buf, 0, &readback_buf, 0, src_buf.size());
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let buf = readback_buf.clone();
+        let log = Arc::clone(self.timeline.snapshots());
+        let (width, height, step) = (self.width, self.height, self.steps);
+        readback_buf.map_async(MapMode::Read, .., move |result| {
+            if let Err(e) = result {
+                log::error!("failed to read back snapshot `{label}`: {e}");
+                return;
+            }
+            let view = buf.get_mapped_range(..);
+            let cells: &[GpuCell] = bytemuck::cast_slice(view.as_ref());
+            let frame = SimulationFrame {
+                grid: cells.iter().map(CellState::from).collect(),
+                width,
+                height,
+            };
+            drop(view);
+            buf.unmap();
+            log.lock()
+                .expect("failed to lock snapshot log")
+                .push(Snapshot { label, step, frame });
+        });
+    }
+
+    /// Get the current output buffer (the one that was last written to)
+    pub fn current_output_buffer(&self) -> &Buffer {
+        if self.flipped_bufs {
+            &self.buf_2
+        } else {
+            &self.buf_1
+        }
+    }
+
+    /// Get the current input buffer (the one the next step will read from)
+    pub fn current_input_buffer(&self) -> &Buffer {
+        self.current_output_buffer()
+    }
+
+    /// Get both cell buffers
+    pub fn buffers(&self) -> (&Buffer, &Buffer) {
+        (&self.buf_1, &self.buf_2)
+    }
+
+    /// Returns true if the last step wrote to buf_2
+    pub fn is_flipped(&self) -> bool {
+        self.flipped_bufs
+    }
+
+    /// Get grid dimensions
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Get the number of steps run so far
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Get the neighbourhood that fire spreads through
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    /// Change the neighbourhood that fire spreads through, regenerating the
+    /// kernel if it differs from the current one. Returns whether it changed.
+    pub fn set_neighbourhood(&mut self, neighbourhood: Neighbourhood) -> bool {
+        if neighbourhood == self.neighbourhood {
+            return false;
+        }
+        self.neighbourhood = neighbourhood;
+        self.queue.write_buffer(
+            &self.kernel_buf,
+            0,
+            bytemuck::bytes_of(&neighbourhood.kernel()),
+        );
+        true
+    }
+
+    /// Change where the fuel moisture of each step comes from
+    pub fn set_weather(&mut self, weather: WeatherSeries) {
+        self.weather = weather;
+    }
+
+    /// Change the scripted climate years
+    pub fn set_climate(&mut self, climate: Climate) {
+        self.climate = climate;
+    }
+
+    /// Change the scheduled fuel treatments, starting their schedule and log
+    /// over
+    pub fn set_management(&mut self, management: Management) {
+        self.queue.write_buffer(
+            &self.assets_buf,
+            0,
+            bytemuck::bytes_of(&management.gpu_assets()),
+        );
+        self.treatments = TreatmentScheduler::new(
+            management,
+            self.width,
+            self.height,
+            Arc::clone(self.treatments.log()),
+        );
+    }
+
+    /// Get the record of treatments carried out
+    pub fn treatment_log(&self) -> &Arc<Mutex<TreatmentLog>> {
+        self.treatments.log()
+    }
+
+    /// Replace the scripted timeline, starting it and the snapshot log over.
+    /// Events whose time has already passed run before the next step.
+    pub fn set_timeline(&mut self, timeline: Timeline) {
+        self.timeline = TimelinePlayer::new(timeline, Arc::clone(self.timeline.snapshots()));
+    }
+
+    /// Add an event to the timeline. An event whose time has already passed
+    /// runs before the next step.
+    pub fn schedule(&mut self, event: TimedEvent) {
+        self.timeline.schedule(event);
+    }
+
+    /// Get the snapshots taken by the timeline
+    pub fn snapshots(&self) -> &Arc<Mutex<SnapshotLog>> {
+        self.timeline.snapshots()
+    }
+
+    /// Replace the snapshot log that the timeline's snapshots are read back
+    /// into
+    pub fn set_snapshot_log(&mut self, snapshots: Arc<Mutex<SnapshotLog>>) {
+        self.timeline.set_snapshots(snapshots);
+    }
+
+    /// The fuel moisture used by the last step
+    pub fn fuel_moisture(&self) -> f32 {
+        self.old_params.fuel_moisture
+    }
+
+    /// The climate moisture multiplier used by the last step
+    pub fn climate_moisture(&self) -> f32 {
+        self.old_params.climate_moisture
+    }
+
+    /// Whether the current year of the run is a drought year
+    pub fn is_drought(&self) -> bool {
+        self.climate
+            .is_drought(self.year(&self.old_params), &self.old_params)
+    }
+
+    /// Whole simulated years since the start of the run
+    fn year(&self, parameters: &SimulationParameters) -> u32 {
+        (self.steps as f32 * parameters.years_per_tick) as u32
+    }
+
+    /// Get the catalogue of completed fires
+    pub fn fire_catalogue(&self) -> &Arc<Mutex<FireCatalogue>> {
+        &self.fire_ledger.catalogue
+    }
+
+    /// Get the log of cell events
+    pub fn event_log(&self) -> &Arc<Mutex<EventLog>> {
+        &self.event_buffer.log
+    }
+
+    /// The recent storm tracks, laid out for the renderer
+    pub fn storm_tracks(&self) -> GpuStormTracks {
+        self.storms.tracks(self.steps)
+    }
+
+    /// Read back a whole-grid summary of the current cell state, along with
+    /// the step count and the weather of the last step. Like `read_frame`,
+    /// the returned future doesn't borrow the pipeline.
+    pub fn read_summary(
+        &self,
+    ) -> impl Future<Output = Result<FrameSummary, anyhow::Error>> + 'static {
+        let frame = self.read_frame();
+        let (step, fuel_moisture, climate_moisture, drought) = (
+            self.steps,
+            self.fuel_moisture(),
+            self.climate_moisture(),
+            self.is_drought(),
+        );
+        async move {
+            Ok(FrameSummary {
+                step,
+                fuel_moisture,
+                climate_moisture,
+                drought,
+                ..FrameSummary::from(&frame.await?)
+            })
+        }
+    }
+
+    /// Read back the current cell state, waiting for all submitted steps to
+    /// finish. The copy is submitted straight away, so the returned future
+    /// doesn't borrow the pipeline.
+    pub fn read_frame(
+        &self,
+    ) -> impl Future<Output = Result<SimulationFrame, anyhow::Error>> + 'static {
+        let src_buf = self.current_output_buffer();
+        let readback_buf = self.device.create_buffer(&BufferDescriptor {
+            label: Some("frame readback buffer"),
+            size: src_buf.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("frame readback encoder"),
+            });
+        encoder.copy_buffer_to_buffer(src_buf, 0, &readback_buf, 0, src_buf.size());
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        readback_buf.map_async(MapMode::Read, .., move |v| {
+            let _ = tx.send(v);
+        });
+        let (width, height) = (self.width, self.height);
+        async move {
+            rx.receive()
+                .await
+                .ok_or_else(|| anyhow::anyhow!("frame readback was dropped"))??;
+
+            let buf_view = readback_buf.get_mapped_range(..);
+            let cells: &[GpuCell] = bytemuck::cast_slice(buf_view.as_ref());
+            let frame = SimulationFrame {
+                grid: cells.iter().map(CellState::from).collect(),
+                width,
+                height,
+            };
+            drop(buf_view);
+            readback_buf.unmap();
+            Ok(frame)
+        }
+    }
+
+    /// Read back the suppression effort spent since the run started, waiting
+    /// for all submitted steps to finish
+    pub async fn suppression_effort(&self) -> Result<SuppressionEffort, anyhow::Error> {
+        let readback_buf = self.device.create_buffer(&BufferDescriptor {
+            label: Some("suppression readback buffer"),
+            size: self.suppression_buf.size(),
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("suppression readback encoder"),
             });
+        encoder.copy_buffer_to_buffer(
+            &self.suppression_buf,
+            0,
+            &readback_buf,
+            0,
+            self.suppression_buf.size(),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
+        readback_buf.map_async(MapMode::Read, .., move |v| {
+            let _ = tx.send(v);
+        });
+        rx.receive()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("suppression readback was dropped"))??;
+
+        let buf_view = readback_buf.get_mapped_range(..);
+        let effort =
+            SuppressionEffort::from(bytemuck::from_bytes::<GpuSuppression>(buf_view.as_ref()));
+        drop(buf_view);
+        readback_buf.unmap();
+        Ok(effort)
+    }
+
+    /// Replace the cell state with `frame` and start counting steps from zero
+    /// again. Fires that were burning are forgotten, along with the fire
+    /// catalogue.
+    pub fn load_frame(&mut self, frame: &SimulationFrame) -> Result<(), anyhow::Error> {
+        if (frame.width, frame.height) != (self.width, self.height) {
+            return Err(anyhow::anyhow!(
+                "frame is {}x{} but the simulation grid is {}x{}",
+                frame.width,
+                frame.height,
+                self.width,
+                self.height
+            ));
         }
+        let data: Vec<GpuCell> = frame.grid.iter().map(GpuCell::from).collect();
+        self.queue
+            .write_buffer(self.current_output_buffer(), 0, bytemuck::cast_slice(&data));
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("load frame encoder"),
+            });
+        self.fire_ledger.clear(&mut encoder);
+        self.event_buffer.clear(&mut encoder);
+        encoder.clear_buffer(&self.ember_buf, 0, None);
+        encoder.clear_buffer(&self.suppression_buf, 0, None);
+        self.queue.submit(std::iter::once(encoder.finish()));
+        self.steps = 0;
+        self.grid = GridHistory::new(self.width);
+        self.treatments.reset();
+        self.timeline.reset();
+        self.storms.reset();
+        Ok(())
+    }
+
+    /// Replace the cell state with `frame`, which may be a different size
+    /// from the grid, rebuilding the buffers and bind groups that depend on
+    /// the grid size. Unlike `load_frame`, the run carries on: the step
+    /// count, fire catalogue, event log, treatments and timeline all carry
+    /// over, though timeline events that no longer fit the grid are dropped.
+    /// Fires that were burning are put out, as their cells are gone.
+    /// Embers in flight are dropped.
+    pub fn resize(&mut self, frame: &SimulationFrame) -> Result<(), anyhow::Error> {
+        if frame.width == 0 || frame.height == 0 {
+            return Err(anyhow::anyhow!(
+                "the grid must be at least 1x1, not {}x{}",
+                frame.width,
+                frame.height
+            ));
+        }
+        let resized = Self::new(
+            Arc::clone(&self.device),
+            Arc::clone(&self.queue),
+            frame,
+            self.old_params,
+            self.neighbourhood,
+        )?;
+        let old = std::mem::replace(self, resized);
+        // The ledger and event buffer don't depend on the grid size, so copy
+        // them across, keeping the staging buffers so that a readback in
+        // flight is still folded in before the next one
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor {
+                label: Some("resize encoder"),
+            });
+        for (from, to) in [
+            (&old.fire_ledger.buf, &self.fire_ledger.buf),
+            (&old.event_buffer.buf, &self.event_buffer.buf),
+        ] {
+            encoder.copy_buffer_to_buffer(from, 0, to, 0, from.size());
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+        // The bind groups use the new buffers
+        let FireLedger { buf, .. } = std::mem::replace(&mut self.fire_ledger, old.fire_ledger);
+        self.fire_ledger.buf = buf;
+        let EventBuffer { buf, .. } = std::mem::replace(&mut self.event_buffer, old.event_buffer);
+        self.event_buffer.buf = buf;
+
+        self.steps = old.steps;
+        self.grid = old.grid;
+        self.grid.resize(self.steps, self.width);
+        self.queue
+            .write_buffer(&self.time_buf, 0, bytemuck::bytes_of(&self.steps));
+        self.run_pass(&self.end_fires_pipeline, "end fires", FIRE_LEDGER_CAPACITY);
+
+        self.weather = old.weather;
+        self.climate = old.climate;
+        self.queue.write_buffer(
+            &self.assets_buf,
+            0,
+            bytemuck::bytes_of(&old.treatments.management().gpu_assets()),
+        );
+        self.treatments = old.treatments;
+        self.treatments.resize(self.width, self.height);
+        self.timeline = old.timeline;
+        self.timeline.fit_to(self.width, self.height);
+        Ok(())
+    }
+
+    /// Read the fire ledger and event buffer back into the catalogue and the
+    /// event log, waiting for all submitted steps to finish. Otherwise they
+    /// are only read back every few steps, so call this before using them
+    /// at the end of a run.
+    pub async fn flush(&self) -> Result<(), anyhow::Error> {
+        self.event_buffer
+            .flush(&self.device, &self.queue, &self.grid)
+            .await?;
+        self.fire_ledger
+            .flush(
+                &self.device,
+                &self.queue,
+                &self.grid,
+                self.old_params.cell_hectares,
+            )
+            .await
+    }
+
+    /// Replace the fire catalogue that the ledger is read back into
+    pub fn set_fire_catalogue(&mut self, catalogue: Arc<Mutex<FireCatalogue>>) {
+        self.fire_ledger.catalogue = catalogue;
+    }
+
+    /// Replace the log that the event buffer is read back into
+    pub fn set_event_log(&mut self, log: Arc<Mutex<EventLog>>) {
+        self.event_buffer.log = log;
     }
 }
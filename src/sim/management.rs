@@ -0,0 +1,402 @@
+//! Fuel treatments: prescribed burns and thinning
+//!
+//! A management plan is a list of treatments that are repeated on a schedule.
+//! Between steps the pipeline asks its [`TreatmentScheduler`] which treatments
+//! are due and writes them straight into the cell buffer:
+//!
+//! - a prescribed burn lights every cell inside a polygon with fuel that isn't
+//!   already burning, all as one fire. It waits until the fuel moisture is at
+//!   least `min_fuel_moisture`, so that the fire spreads slowly. The lit cells
+//!   burn like any other surface fire, and a burn whose fire spreads to any
+//!   other cell counts as an escape.
+//! - thinning clears the underbrush of one block of the grid, moving on to the
+//!   next block each time
+//!
+//! Every treatment that is carried out is recorded in a [`TreatmentLog`].
+//!
+//! The plan also lists the assets (e.g. towns) that the `assets` suppression
+//! strategy protects first.
+
+use std::sync::{Arc, Mutex};
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::sim::{FireCatalogue, SimulationParameters};
+
+/// The most assets a management plan can have
+pub const MAX_ASSETS: usize = 32;
+
+/// Scheduled fuel treatments and the assets to protect from fire
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Management {
+    pub treatments: Vec<Treatment>,
+    /// Positions of assets as `[x, y]` in cells
+    pub assets: Vec<[f32; 2]>,
+}
+
+/// The assets as laid out in the GPU uniform
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct GpuAssets {
+    pub count: u32,
+    pub _padding: [u32; 3],
+    /// Asset positions in the first two components
+    pub positions: [[f32; 4]; MAX_ASSETS],
+}
+
+/// A fuel treatment that is repeated every `every_years` years, starting in
+/// year `start_year`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Treatment {
+    /// Light every cell inside a polygon
+    PrescribedBurn {
+        every_years: f32,
+        #[serde(default)]
+        start_year: f32,
+        /// Corners of the burn unit as `[x, y]` in cells, where `[0, 0]` is
+        /// the top left corner of the grid
+        polygon: Vec<[f32; 2]>,
+        /// The burn waits until the fuel moisture is at least this high
+        #[serde(default)]
+        min_fuel_moisture: f32,
+    },
+    /// Clear the underbrush of one block of the grid, which is split into
+    /// `blocks` columns and rows of blocks treated in turn
+    Thinning {
+        every_years: f32,
+        #[serde(default)]
+        start_year: f32,
+        blocks: [u32; 2],
+    },
+}
+
+impl Treatment {
+    fn every_years(&self) -> f32 {
+        match self {
+            Treatment::PrescribedBurn { every_years, .. }
+            | Treatment::Thinning { every_years, .. } => *every_years,
+        }
+    }
+
+    fn start_year(&self) -> f32 {
+        match self {
+            Treatment::PrescribedBurn { start_year, .. }
+            | Treatment::Thinning { start_year, .. } => *start_year,
+        }
+    }
+
+    /// Check that the treatment can be carried out
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        if !(self.every_years().is_finite() && self.every_years() > 0.0) {
+            return Err(anyhow::anyhow!(
+                "treatments must repeat after a positive number of years, not {}",
+                self.every_years()
+            ));
+        }
+        match self {
+            Treatment::PrescribedBurn { polygon, .. } if polygon.len() < 3 => Err(anyhow::anyhow!(
+                "a prescribed burn polygon needs at least 3 corners but has {}",
+                polygon.len()
+            )),
+            Treatment::Thinning { blocks, .. } if blocks.contains(&0) => Err(anyhow::anyhow!(
+                "thinning needs at least one block in each direction, not {}x{}",
+                blocks[0],
+                blocks[1]
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Management {
+    /// Check that every treatment can be carried out and that the assets fit
+    /// on the GPU
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        if self.assets.len() > MAX_ASSETS {
+            return Err(anyhow::anyhow!(
+                "a management plan can have at most {MAX_ASSETS} assets but has {}",
+                self.assets.len()
+            ));
+        }
+        self.treatments.iter().try_for_each(Treatment::validate)
+    }
+
+    /// The assets as laid out in the GPU uniform. Assets beyond
+    /// [`MAX_ASSETS`] are left out.
+    pub fn gpu_assets(&self) -> GpuAssets {
+        let mut assets = GpuAssets::zeroed();
+        for (slot, &[x, y]) in assets.positions.iter_mut().zip(&self.assets) {
+            *slot = [x, y, 0.0, 0.0];
+            assets.count += 1;
+        }
+        assets
+    }
+}
+
+/// A change to the cells of the grid made by a treatment
+pub enum TreatmentAction {
+    /// Set these cells alight as one fire, for the prescribed burn at index
+    /// `event` in the treatment log
+    Ignite { cells: Vec<u32>, event: u32 },
+    /// Clear the underbrush of these cells
+    Thin(Vec<u32>),
+}
+
+/// A treatment that was carried out
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct TreatmentEvent {
+    /// Index of the treatment in the management plan
+    pub treatment: u32,
+    /// The simulation step on which the treatment was carried out
+    pub step: u32,
+    /// The number of cells treated
+    pub area: u32,
+    /// The area treated in hectares
+    pub hectares: f32,
+}
+
+/// Totals of the events of one treatment
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TreatmentSummary {
+    /// The number of times the treatment was carried out
+    pub events: u32,
+    /// Total cells treated, counting cells treated more than once each time
+    pub area: u32,
+    /// Total area treated in hectares, counted the same way
+    pub hectares: f32,
+    /// Completed prescribed burns whose fire spread beyond the cells lit
+    pub escapes: u32,
+    /// Total cells the escaped burns spread to
+    pub escaped_area: u32,
+    /// Total area in hectares the escaped burns spread to
+    pub escaped_hectares: f32,
+}
+
+/// Record of the treatments carried out since the start of the run
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct TreatmentLog {
+    events: Vec<TreatmentEvent>,
+}
+
+impl TreatmentLog {
+    /// All treatments carried out so far, in order
+    pub fn events(&self) -> &[TreatmentEvent] {
+        &self.events
+    }
+
+    /// Totals over all treatments in the management plan
+    pub fn total(&self, fires: &FireCatalogue) -> TreatmentSummary {
+        self.summarise(fires, |_| true)
+    }
+
+    fn summarise(
+        &self,
+        fires: &FireCatalogue,
+        include: impl Fn(&TreatmentEvent) -> bool,
+    ) -> TreatmentSummary {
+        let mut summary = TreatmentSummary::default();
+        for event in self.events.iter().filter(|e| include(e)) {
+            summary.events += 1;
+            summary.area += event.area;
+            summary.hectares += event.hectares;
+        }
+        let escapes = fires.burns().iter().filter(|burn| {
+            burn.escaped_area > 0
+                && self
+                    .events
+                    .get(burn.treatment_event as usize)
+                    .is_some_and(&include)
+        });
+        for burn in escapes {
+            summary.escapes += 1;
+            summary.escaped_area += burn.escaped_area;
+            summary.escaped_hectares += burn.escaped_hectares;
+        }
+        summary
+    }
+}
+
+#[wasm_bindgen]
+impl TreatmentLog {
+    /// Number of treatments carried out
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether no treatments have been carried out yet
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Get a treatment event by index
+    #[wasm_bindgen]
+    pub fn event(&self, index: usize) -> Option<TreatmentEvent> {
+        self.events.get(index).copied()
+    }
+
+    /// Totals for the treatment at `index` in the management plan. Escapes
+    /// are found among the completed prescribed burns of `fires`, so a burn
+    /// that is still burning isn't counted yet.
+    #[wasm_bindgen]
+    pub fn summary(&self, index: u32, fires: &FireCatalogue) -> TreatmentSummary {
+        self.summarise(fires, |e| e.treatment == index)
+    }
+}
+
+/// Works out which treatments are due on each step
+pub struct TreatmentScheduler {
+    management: Management,
+    width: usize,
+    height: usize,
+    /// The year each treatment is next due in
+    next_due: Vec<f32>,
+    /// The number of times each treatment has been carried out, which picks
+    /// the next block to thin
+    rounds: Vec<u32>,
+    log: Arc<Mutex<TreatmentLog>>,
+}
+
+impl TreatmentScheduler {
+    /// Create a scheduler for the given plan, which starts `log` over
+    pub fn new(
+        management: Management,
+        width: usize,
+        height: usize,
+        log: Arc<Mutex<TreatmentLog>>,
+    ) -> Self {
+        let next_due = management
+            .treatments
+            .iter()
+            .map(Treatment::start_year)
+            .collect();
+        let rounds = vec![0; management.treatments.len()];
+        *log.lock().expect("failed to lock treatment log") = TreatmentLog::default();
+        Self {
+            management,
+            width,
+            height,
+            next_due,
+            rounds,
+            log,
+        }
+    }
+
+    /// Start the schedule over, e.g. when a new frame is loaded
+    pub fn reset(&mut self) {
+        *self = Self::new(
+            self.management.clone(),
+            self.width,
+            self.height,
+            Arc::clone(&self.log),
+        );
+    }
+
+    /// Carry the schedule over to a grid of a different size
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// The plan the treatments come from
+    pub fn management(&self) -> &Management {
+        &self.management
+    }
+
+    /// The record of treatments carried out so far
+    pub fn log(&self) -> &Arc<Mutex<TreatmentLog>> {
+        &self.log
+    }
+
+    /// The treatments to carry out before the given step, whose fuel moisture
+    /// has already been filled in
+    pub fn due(&mut self, step: u32, parameters: &SimulationParameters) -> Vec<TreatmentAction> {
+        let year = step as f32 * parameters.years_per_tick;
+        let mut actions = Vec::new();
+        for (index, treatment) in self.management.treatments.iter().enumerate() {
+            if year < self.next_due[index] {
+                continue;
+            }
+            let mut log = self.log.lock().expect("failed to lock treatment log");
+            let (action, area) = match treatment {
+                Treatment::PrescribedBurn {
+                    polygon,
+                    min_fuel_moisture,
+                    ..
+                } => {
+                    // Wait for damp enough weather
+                    if parameters.fuel_moisture < *min_fuel_moisture {
+                        continue;
+                    }
+                    let cells = self.cells_in_polygon(polygon);
+                    let area = cells.len() as u32;
+                    let event = log.events.len() as u32;
+                    (TreatmentAction::Ignite { cells, event }, area)
+                }
+                Treatment::Thinning { blocks, .. } => {
+                    let cells = self.cells_in_block(*blocks, self.rounds[index]);
+                    let area = cells.len() as u32;
+                    (TreatmentAction::Thin(cells), area)
+                }
+            };
+            // Skip any rounds that were missed while waiting
+            while self.next_due[index] <= year {
+                self.next_due[index] += treatment.every_years();
+            }
+            self.rounds[index] += 1;
+            log.events.push(TreatmentEvent {
+                treatment: index as u32,
+                step,
+                area,
+                hectares: area as f32 * parameters.cell_hectares,
+            });
+            actions.push(action);
+        }
+        actions
+    }
+
+    /// Indices of the cells whose centres lie inside the polygon
+    fn cells_in_polygon(&self, polygon: &[[f32; 2]]) -> Vec<u32> {
+        let mut cells = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if contains(polygon, [x as f32 + 0.5, y as f32 + 0.5]) {
+                    cells.push((y * self.width + x) as u32);
+                }
+            }
+        }
+        cells
+    }
+
+    /// Indices of the cells in the block treated on the given round. Blocks
+    /// are visited row by row.
+    fn cells_in_block(&self, [columns, rows]: [u32; 2], round: u32) -> Vec<u32> {
+        let block = round % (columns * rows);
+        let (column, row) = ((block % columns) as usize, (block / columns) as usize);
+        let (columns, rows) = (columns as usize, rows as usize);
+        let xs = column * self.width / columns..(column + 1) * self.width / columns;
+        let ys = row * self.height / rows..(row + 1) * self.height / rows;
+        ys.flat_map(|y| xs.clone().map(move |x| (y * self.width + x) as u32))
+            .collect()
+    }
+}
+
+/// Whether a point lies inside a polygon, by the even-odd rule
+fn contains(polygon: &[[f32; 2]], [px, py]: [f32; 2]) -> bool {
+    let mut inside = false;
+    for (i, &[x1, y1]) in polygon.iter().enumerate() {
+        let [x2, y2] = polygon[(i + 1) % polygon.len()];
+        if (y1 > py) != (y2 > py) && px < x1 + (py - y1) / (y2 - y1) * (x2 - x1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
@@ -1,5 +1,5 @@
 use bytemuck::{Pod, Zeroable};
-use futures_intrusive::channel::shared::{OneshotReceiver, OneshotSender};
+use futures_intrusive::channel::shared::OneshotReceiver;
 use std::sync::{
     Arc, Mutex,
     atomic::{AtomicBool, Ordering},
@@ -7,13 +7,45 @@ use std::sync::{
 use wasm_bindgen::prelude::*;
 use watch::{WatchReceiver, WatchSender};
 
+pub mod climate;
+pub mod events;
+pub mod fast_forward;
+pub mod fires;
 pub mod gpucompute;
+pub mod management;
+pub mod neighbourhood;
+pub mod parameters;
+pub mod stats;
+pub mod storms;
+pub mod suppression;
+pub mod sweep;
+pub mod timeline;
+pub mod weather;
 
+pub use climate::Climate;
+pub use events::{EventKind, EventLog, SimEvent};
+pub use fast_forward::{CancelHandle, RunOutcome, RunProgress, RunUntil, StopReason};
+pub use fires::FireCatalogue;
 pub use gpucompute::GpuCell;
+pub use management::{Management, TreatmentLog};
+pub use neighbourhood::{Neighbourhood, NeighbourhoodKind};
+pub use parameters::{
+    BoundaryMode, ConfigurableParameters, GrowthCurve, MortalityCurve, PARAMETERS, ParameterSpec,
+    SuppressionStrategy,
+};
+pub use suppression::SuppressionEffort;
+pub use timeline::{SnapshotLog, Timeline, TimelineEvent};
+pub use weather::WeatherSeries;
 
 use js_sys::Date;
 
-use crate::spawn_sim_worker;
+use fast_forward::FastForward;
+
+use crate::{SimWorkerArgs, spawn_sim_worker};
+
+/// The most times fire gets to spread in a tick. Fires faster than this many
+/// cells a tick spread at this speed.
+pub const MAX_FIRE_SUBSTEPS: u32 = 16;
 
 #[derive(Clone)]
 pub struct SimulationFrame {
@@ -30,14 +62,40 @@ impl SimulationFrame {
             grid: vec![
                 CellState {
                     burning: BurnState::NotBurning,
-                    tree: false,
-                    underbrush: 0.0
+                    tree: 0.0,
+                    underbrush: 0.0,
+                    age: 0.0,
+                    health: 0.0,
+                    alert: 0.0,
+                    seeds: 0.0,
+                    severity: 0.0,
                 };
                 width * height
             ]
             .into(),
         }
     }
+
+    /// Resample the grid to a new size, taking each cell from the nearest
+    /// cell of this grid. Fires are put out, since a burning cell may be
+    /// duplicated or dropped and its fire's cell count would no longer add up.
+    pub fn resample(&self, width: usize, height: usize) -> SimulationFrame {
+        let grid = (0..width * height)
+            .map(|index| {
+                let (x, y) = (index % width, index / width);
+                CellState {
+                    burning: BurnState::NotBurning,
+                    ..self.grid[(y * self.height / height) * self.width + x * self.width / width]
+                        .clone()
+                }
+            })
+            .collect();
+        SimulationFrame {
+            width,
+            height,
+            grid,
+        }
+    }
 }
 
 impl Default for SimulationFrame {
@@ -46,101 +104,78 @@ impl Default for SimulationFrame {
     }
 }
 
+/// The width of the grid over the course of a run. The GPU identifies cells
+/// by their index, so events and fires are placed using the width the grid
+/// had on the step they happened, which changes when the grid is resized.
+#[derive(Clone, Debug)]
+pub(crate) struct GridHistory {
+    /// The first step of each width, in order
+    widths: Vec<(u32, usize)>,
+}
+
+impl GridHistory {
+    pub(crate) fn new(width: usize) -> Self {
+        Self {
+            widths: vec![(0, width)],
+        }
+    }
+
+    /// Record that the grid has the given width from `step` on
+    pub(crate) fn resize(&mut self, step: u32, width: usize) {
+        self.widths.retain(|&(start, _)| start < step);
+        self.widths.push((step, width));
+    }
+
+    /// The column and row of a cell index on the given step
+    pub(crate) fn position(&self, step: u32, cell: u32) -> (u32, u32) {
+        let width = self
+            .widths
+            .iter()
+            .rev()
+            .find(|&&(start, _)| start <= step)
+            .map_or(self.widths[0].1, |&(_, width)| width) as u32;
+        (cell % width, cell / width)
+    }
+}
+
 #[derive(Clone)]
 pub struct CellState {
     pub burning: BurnState,
     pub underbrush: f32,
-    pub tree: bool,
+    /// Size of the tree in the cell, from 0 (no tree) to 1 (fully grown)
+    pub tree: f32,
+    /// Age of the tree in years
+    pub age: f32,
+    /// Health of the tree, from 0 (dead) to 1 (healthy)
+    pub health: f32,
+    /// Alert level of the tree, from 0 (calm) to 1 (fully alert), which
+    /// reduces its flammability
+    pub alert: f32,
+    /// Viable seeds in the cell's seed bank, which germinate quickly
+    pub seeds: f32,
+    /// Severity of the last fire to burn the cell, from 0 (nothing consumed)
+    /// to 1 (all fuel consumed)
+    pub severity: f32,
 }
 
 #[derive(Clone)]
 pub enum BurnState {
     NotBurning,
-    Burning { ticks_remaining: u32 },
-}
-
-/// Configuration parameters with realistic units and static forest properties.
-/// These parameters are grounded in reality and are used to compute the internal
-/// SimulationParameters struct.
-#[derive(Clone)]
-#[wasm_bindgen]
-pub struct ConfigurableParameters {
-    // Static parameters - forest size
-    /// Width of the forest in cells
-    pub forest_width: usize,
-    /// Height of the forest in cells
-    pub forest_height: usize,
-    /// Size of the forest in acres (computed from width and height)
-    pub forest_acres: f32,
-
-    // Time scale parameters
-    /// Number of simulation ticks per month
-    pub ticks_per_month: f32,
-    /// Number of months that pass per second of real time
-    pub months_per_second: f32,
-
-    // Realistic configurable parameters
-    /// Lightning strike frequency in strikes per year per acre
-    pub lightning_strikes_per_year_per_acre: f32,
-    /// Tree growth rate: average years for a tree to grow (e.g., 150.0 means 1/150 per year)
-    pub tree_growth_years: f32,
-    /// Tree death rate: average years for a tree to die naturally (e.g., 200.0 means 1/200 per year)
-    pub tree_death_years: f32,
-    /// The factor by which the tree growth rate is reduced with underbrush.
-    /// The final growth rate is calculated as
-    /// ```
-    /// let final_growth_rate = (1.0 - underbrush_tree_growth_hindrance * underbrush) *
-    /// tree_growth_rate;
-    /// ```
-    pub underbrush_tree_growth_hindrance: f32,
-    /// The base rate of underbrush accumulation per tick
-    pub tree_underbrush_generation: f32,
-    /// The amount of underbrush created when a tree dies naturally
-    pub tree_death_underbrush: f32,
-    /// The length a single tree can support a fire for in ticks
-    pub tree_fire_duration: u32,
-    /// The length that underbrush can support a fire for in ticks. This is
-    /// multiplied by the amount of underbrush
-    pub underbrush_fire_duration: u32,
-    /// The base chance (0 - 1) that fire spreads from a particular cell to a
-    /// particular neighbor cell
-    pub fire_spread_rate: f32,
-    /// The multiplier for fire spread rate for trees
-    pub tree_flammability: f32,
-    /// The multiplier for fire spread rate for underbrush (multiplied by the
-    /// amount of underbrush). This is added with the value from tree_flammability
-    /// to calculate the final chance
-    pub underbrush_flammability: f32,
+    Burning {
+        ticks_remaining: u32,
+        fire_id: u32,
+        kind: FireKind,
+    },
 }
 
-impl ConfigurableParameters {
-    /// Create realistic default parameters for a forest of the given size
-    pub fn realistic(
-        width: usize,
-        height: usize,
-        ticks_per_month: f32,
-        months_per_second: f32,
-    ) -> ConfigurableParameters {
-        let forest_acres = (width as f32 * height as f32) / 4047.0;
-        Self {
-            forest_width: width,
-            forest_height: height,
-            forest_acres,
-            ticks_per_month,
-            months_per_second,
-            lightning_strikes_per_year_per_acre: 1.0 / 45.0, // ~1 strike per 45 acres per year
-            tree_growth_years: 150.0,
-            tree_death_years: 200.0,
-            underbrush_tree_growth_hindrance: 0.0,
-            tree_underbrush_generation: 0.0001,
-            tree_death_underbrush: 0.01,
-            tree_fire_duration: 1,
-            underbrush_fire_duration: 1,
-            fire_spread_rate: 1.0,
-            tree_flammability: 0.5,
-            underbrush_flammability: 1.0,
-        }
-    }
+/// How a cell is burning
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum FireKind {
+    /// Burning the underbrush beneath the canopy, which only kills small trees
+    Surface = 1,
+    /// Burning through the canopy, which spreads faster and kills the tree
+    Crown = 2,
 }
 
 /// Internal computed parameters derived from ConfigurableParameters.
@@ -149,7 +184,8 @@ impl ConfigurableParameters {
 #[derive(Clone, Copy, Pod, Zeroable, PartialEq)]
 #[repr(C)]
 pub struct SimulationParameters {
-    /// The base chance (0 - 1) that a tree will grow in a given cell each tick
+    /// The base chance (0 - 1) that a sapling will germinate in a given cell
+    /// each tick
     pub tree_growth_rate: f32,
     /// The factor by which the tree growth rate is reduced with underbrush.
     pub underbrush_tree_growth_hindrance: f32,
@@ -158,16 +194,23 @@ pub struct SimulationParameters {
     /// The amount of underbrush created when a tree dies naturally
     pub tree_death_underbrush: f32,
     /// The chance (0 - 1) that a particular tree dies naturally each tick
+    /// with a constant mortality curve
     pub tree_death_rate: f32,
-    /// The length a single tree can support a fire for in ticks
+    /// The length a fully grown tree can support a fire for in ticks. Smaller
+    /// trees burn for proportionally less, but at least one tick
     pub tree_fire_duration: u32,
     /// The length that underbrush can support a fire for in ticks. This is
     /// multiplied by the amount of underbrush
     pub underbrush_fire_duration: u32,
     /// The base chance (0 - 1) that fire spreads from a particular cell to a
-    /// particular neighbor cell
+    /// particular neighbor cell in each substep, derived from the rate of
+    /// spread
     pub fire_spread_rate: f32,
-    /// The multiplier for fire spread rate for trees
+    /// The number of times fire gets to spread each tick, so that fast fires
+    /// can cross more than one cell a tick
+    pub fire_substeps: u32,
+    /// The multiplier for fire spread rate for fully grown trees (multiplied by
+    /// the size of the tree)
     pub tree_flammability: f32,
     /// The multiplier for fire spread rate for underbrush (multiplied by the
     /// amount of underbrush). This is added with the value from tree_flammability
@@ -177,6 +220,122 @@ pub struct SimulationParameters {
     pub lightning_frequency: f32,
     /// The tick rate in ticks per second
     pub tick_rate: u32,
+    /// Seed for the random number generator
+    pub seed: u32,
+    /// How the grid edges behave, as a `BoundaryMode`
+    pub boundary_mode: u32,
+    /// The size (0 - 1) of a newly germinated sapling
+    pub sapling_size: f32,
+    /// The growth rate per tick, whose meaning depends on the growth curve
+    pub tree_maturation_rate: f32,
+    /// How trees grow towards full size, as a `GrowthCurve`
+    pub growth_curve: u32,
+    /// The length of a tick in years
+    pub years_per_tick: f32,
+    /// The characteristic lifespan of a tree in years
+    pub tree_lifespan: f32,
+    /// How the chance of natural death changes with age, as a `MortalityCurve`
+    pub mortality_curve: u32,
+    /// The shape of the mortality curve
+    pub mortality_shape: f32,
+    /// The health lost each tick when the whole neighbourhood is burning
+    pub scorch_damage: f32,
+    /// The health lost each tick when the whole neighbourhood is full of
+    /// mature trees
+    pub crowding_stress: f32,
+    /// The health regained each tick
+    pub health_recovery: f32,
+    /// How strongly neighbouring trees suppress germination
+    pub germination_competition: f32,
+    /// How strongly neighbouring trees slow growth
+    pub growth_competition: f32,
+    /// How strongly neighbouring trees raise the chance of natural death
+    pub mortality_competition: f32,
+    /// The fraction (0 - 1) by which full alert reduces flammability
+    pub alert_strength: f32,
+    /// The fraction (0 - 1) of a neighbour's alert passed on each tick
+    pub alert_range: f32,
+    /// The fraction (0 - 1) of alert lost each tick
+    pub alert_decay: f32,
+    /// The alert raised each tick by a neighbourhood full of trees
+    pub alert_stand_signal: f32,
+    /// Radius of the seed dispersal kernel in cells
+    pub seed_dispersal_radius: u32,
+    /// Distance falloff of the seed dispersal kernel
+    pub seed_dispersal_falloff: f32,
+    /// The smallest tree size (0 - 1) that produces seeds
+    pub seed_bearing_size: f32,
+    /// The seed supply that reaches every cell regardless of nearby trees
+    pub background_seed_rain: f32,
+    /// The fraction of a burnt tree's size released as seeds when its fire
+    /// goes out
+    pub serotiny: f32,
+    /// The chance (0 - 1) each tick that a cell with a full seed bank
+    /// germinates
+    pub seed_bank_germination: f32,
+    /// The fraction (0 - 1) of a seed bank that stops being viable each tick
+    pub seed_bank_decay: f32,
+    /// Mean fuel moisture of the sinusoidal weather profile
+    pub mean_fuel_moisture: f32,
+    /// Amplitude of the sinusoidal weather profile
+    pub fuel_moisture_amplitude: f32,
+    /// Month (1 - 12) in which the sinusoidal weather profile is driest
+    pub driest_month: f32,
+    /// The fuel moisture above which fuel doesn't burn
+    pub moisture_of_extinction: f32,
+    /// The chance (0 - 1) that a year is a drought year
+    pub drought_probability: f32,
+    /// The fraction (0 - 1) by which drought years reduce fuel moisture
+    pub drought_severity: f32,
+    /// The fractional change in fuel moisture per year
+    pub moisture_trend: f32,
+    /// The climate multiplier of the current year's fuel moisture, filled in
+    /// by the pipeline
+    pub climate_moisture: f32,
+    /// Fuel moisture of the current tick, filled in from the weather and
+    /// climate by the pipeline
+    pub fuel_moisture: f32,
+    /// The chance (0 - 1) each tick that a burning cell lofts an ember, per
+    /// unit of fuel load above the threshold
+    pub ember_rate: f32,
+    /// The fuel load (tree + underbrush) a burning cell needs to loft embers
+    pub ember_intensity_threshold: f32,
+    /// Mean distance in cells that embers travel
+    pub ember_distance: f32,
+    /// Furthest distance in cells that embers travel
+    pub ember_max_distance: f32,
+    /// The chance (0 - 1) that an ember ignites a fully flammable cell
+    pub ember_ignition: f32,
+    /// Direction the wind blows towards, in radians clockwise from north
+    pub wind_direction: f32,
+    /// How strongly (0 - 1) embers are carried downwind
+    pub wind_strength: f32,
+    /// The underbrush load beneath a tree at which a surface fire climbs
+    /// into the canopy
+    pub ladder_fuel_threshold: f32,
+    /// The multiplier for fire spread from crown fires
+    pub crown_fire_spread: f32,
+    /// Trees smaller than this size (0 - 1) are killed by surface fires
+    pub surface_fire_kill_size: f32,
+    /// The dry underbrush load that a surface fire consumes completely
+    pub complete_consumption_load: f32,
+    /// Which cells suppression crews work on first (one of the SUPPRESSION_
+    /// constants)
+    pub suppression_strategy: u32,
+    /// The number of ticks a fire burns for before suppression starts
+    pub detection_delay: u32,
+    /// The number of cells suppression crews can work on each tick
+    pub suppression_capacity: u32,
+    /// The area of a cell in hectares
+    pub cell_hectares: f32,
+    /// The expected number of storms each tick
+    pub storm_frequency: f32,
+    /// The mean number of strikes per storm
+    pub strikes_per_storm: f32,
+    /// Radius of a storm's footprint in cells
+    pub storm_radius: f32,
+    /// Length of a storm's track in cells
+    pub storm_track_length: f32,
 }
 
 impl From<&ConfigurableParameters> for SimulationParameters {
@@ -186,12 +345,32 @@ impl From<&ConfigurableParameters> for SimulationParameters {
 
         // Convert lightning strikes per year per acre to per-tick probability
         let lightning_frequency =
-            config.lightning_strikes_per_year_per_acre * config.forest_acres / ticks_per_year;
+            config.lightning_strikes_per_year_per_acre * config.forest_acres() / ticks_per_year;
+
+        // Convert the rate of spread to the number of cells fire crosses in a
+        // tick, which is split into substeps that each cross at most one cell
+        let cells_per_tick =
+            config.rate_of_spread * config.spread_minutes_per_tick / config.cell_size_m;
+        let fire_substeps = (cells_per_tick.ceil() as u32).clamp(1, MAX_FIRE_SUBSTEPS);
+        let fire_spread_rate = (cells_per_tick / fire_substeps as f32).min(1.0);
 
         // Convert tree growth/death rates from years to per-tick probabilities
         let tree_growth_rate = 1.0 / (ticks_per_year * config.tree_growth_years);
         let tree_death_rate = 1.0 / (ticks_per_year * config.tree_death_years);
 
+        // Pick the growth rate so that a sapling reaches maturity (95% of full
+        // size for the logistic curve, which never quite gets there) in the
+        // configured time
+        let maturity_ticks = ticks_per_year * config.tree_maturity_years;
+        let tree_maturation_rate = match config.growth_curve {
+            GrowthCurve::Logistic => {
+                let start = config.sapling_size.min(0.9);
+                let end = 0.95;
+                ((end * (1.0 - start)) / (start * (1.0 - end))).ln() / maturity_ticks
+            }
+            GrowthCurve::Linear => (1.0 - config.sapling_size) / maturity_ticks,
+        };
+
         Self {
             tick_rate,
             lightning_frequency,
@@ -202,9 +381,65 @@ impl From<&ConfigurableParameters> for SimulationParameters {
             tree_death_underbrush: config.tree_death_underbrush,
             tree_fire_duration: config.tree_fire_duration,
             underbrush_fire_duration: config.underbrush_fire_duration,
-            fire_spread_rate: config.fire_spread_rate,
+            fire_spread_rate,
+            fire_substeps,
             tree_flammability: config.tree_flammability,
             underbrush_flammability: config.underbrush_flammability,
+            seed: config.seed,
+            boundary_mode: config.boundary_mode as u32,
+            sapling_size: config.sapling_size,
+            tree_maturation_rate,
+            growth_curve: config.growth_curve as u32,
+            years_per_tick: 1.0 / ticks_per_year,
+            tree_lifespan: config.tree_death_years,
+            mortality_curve: config.mortality_curve as u32,
+            mortality_shape: config.mortality_shape,
+            scorch_damage: config.scorch_damage,
+            crowding_stress: config.crowding_stress / ticks_per_year,
+            health_recovery: config.health_recovery / ticks_per_year,
+            germination_competition: config.germination_competition,
+            growth_competition: config.growth_competition,
+            mortality_competition: config.mortality_competition,
+            alert_strength: config.alert_strength,
+            alert_range: config.alert_range,
+            alert_decay: config.alert_decay,
+            alert_stand_signal: config.alert_stand_signal,
+            seed_dispersal_radius: config.radius_cells(config.seed_dispersal_radius_m),
+            seed_dispersal_falloff: config.seed_dispersal_falloff,
+            seed_bearing_size: config.seed_bearing_size,
+            background_seed_rain: config.background_seed_rain,
+            serotiny: config.serotiny,
+            seed_bank_germination: 1.0
+                / (config.ticks_per_month * config.seed_bank_germination_months),
+            seed_bank_decay: 1.0 / (ticks_per_year * config.seed_bank_years),
+            mean_fuel_moisture: config.mean_fuel_moisture,
+            fuel_moisture_amplitude: config.fuel_moisture_amplitude,
+            driest_month: config.driest_month,
+            moisture_of_extinction: config.moisture_of_extinction,
+            drought_probability: config.drought_probability,
+            drought_severity: config.drought_severity,
+            moisture_trend: config.moisture_trend_per_century / 100.0,
+            climate_moisture: 1.0,
+            fuel_moisture: config.mean_fuel_moisture,
+            ember_rate: config.ember_rate,
+            ember_intensity_threshold: config.ember_intensity_threshold,
+            ember_distance: config.ember_distance_m / config.cell_size_m,
+            ember_max_distance: config.ember_max_distance_m / config.cell_size_m,
+            ember_ignition: config.ember_ignition,
+            wind_direction: config.wind_direction.to_radians(),
+            wind_strength: config.wind_strength,
+            ladder_fuel_threshold: config.ladder_fuel_threshold,
+            crown_fire_spread: config.crown_fire_spread,
+            surface_fire_kill_size: config.surface_fire_kill_size,
+            complete_consumption_load: config.complete_consumption_load,
+            suppression_strategy: config.suppression_strategy as u32,
+            detection_delay: config.detection_delay,
+            suppression_capacity: config.suppression_capacity,
+            cell_hectares: config.cell_hectares(),
+            storm_frequency: config.storms_per_year / ticks_per_year,
+            strikes_per_storm: config.strikes_per_storm,
+            storm_radius: config.storm_radius_m / config.cell_size_m,
+            storm_track_length: config.storm_track_km * 1000.0 / config.cell_size_m,
         }
     }
 }
@@ -214,6 +449,8 @@ impl From<&ConfigurableParameters> for SimulationParameters {
 #[wasm_bindgen(getter_with_clone)]
 pub struct SimulationStatistics {
     pub average_step_exec_time: f64,
+    /// Suppression effort spent over the run
+    pub suppression: SuppressionEffort,
 }
 
 #[wasm_bindgen]
@@ -225,8 +462,25 @@ pub struct SimulationHandle {
     latest_frame_rx: WatchReceiver<SimulationFrame>,
     stats_rx: Arc<Mutex<OneshotReceiver<SimulationStatistics>>>,
     wants_new_frame: Arc<AtomicBool>,
+    fires: Arc<Mutex<FireCatalogue>>,
+    events: Arc<Mutex<EventLog>>,
+    timeline_tx: WatchSender<Timeline>,
+    snapshots: Arc<Mutex<SnapshotLog>>,
+    fast_forward: Arc<Mutex<FastForwardState>>,
 }
 
+/// A fast-forward requested of the worker, and how it is getting on
+#[derive(Default)]
+pub struct FastForwardState {
+    request: Option<RunUntil>,
+    /// Progress since the handle last looked
+    progress: Option<RunProgress>,
+    outcome: Option<Result<RunOutcome, String>>,
+}
+
+/// How often, in milliseconds, a handle waiting on a fast-forward checks on it
+const FAST_FORWARD_POLL_MS: u32 = 100;
+
 pub fn spawn_simulation(
     start_frame: SimulationFrame,
     parameters: ConfigurableParameters,
@@ -240,13 +494,23 @@ pub fn spawn_simulation(
     let p = parameters_rx.clone();
     let (stats_tx, stats_rx) = futures_intrusive::channel::shared::oneshot_channel();
     let lf_rx = latest_frame_rx.clone();
-    spawn_sim_worker(crate::SimWorkerArgs {
+    let fires = Arc::new(Mutex::new(FireCatalogue::default()));
+    let events = Arc::new(Mutex::new(EventLog::default()));
+    let (timeline_tx, timeline_rx) = watch::channel(Timeline::default());
+    let snapshots = Arc::new(Mutex::new(SnapshotLog::default()));
+    let fast_forward = Arc::new(Mutex::new(FastForwardState::default()));
+    spawn_sim_worker(SimWorkerArgs {
         parameters_rx: p,
         stop: s,
         latest_frame_tx,
         latest_frame_rx: lf_rx,
         stats_tx,
         wants_new_frame: wnf,
+        fires: Arc::clone(&fires),
+        events: Arc::clone(&events),
+        timeline_rx,
+        snapshots: Arc::clone(&snapshots),
+        fast_forward: Arc::clone(&fast_forward),
     })
     .unwrap();
     let stats_rx = Arc::new(Mutex::new(stats_rx));
@@ -257,6 +521,11 @@ pub fn spawn_simulation(
         latest_frame_rx,
         stats_rx,
         wants_new_frame,
+        fires,
+        events,
+        timeline_tx,
+        snapshots,
+        fast_forward,
     }
 }
 
@@ -288,16 +557,102 @@ impl SimulationHandle {
     pub fn get_parameters(&mut self) -> ConfigurableParameters {
         self.parameters_rx.get()
     }
+    /// Get a snapshot of the catalogue of completed fires
+    #[wasm_bindgen]
+    pub fn fire_catalogue(&self) -> FireCatalogue {
+        self.fires
+            .lock()
+            .expect("failed to lock fire catalogue")
+            .clone()
+    }
+    /// Get a copy of the log of cell events
+    #[wasm_bindgen]
+    pub fn event_log(&self) -> EventLog {
+        self.events
+            .lock()
+            .expect("failed to lock event log")
+            .clone()
+    }
+    /// Replace the scripted timeline with one parsed from TOML or JSON.
+    /// Events whose time has already passed run before the next step.
+    #[wasm_bindgen]
+    pub fn load_timeline(&mut self, source: &str) -> Result<(), JsValue> {
+        let params = self.parameters_rx.get();
+        let timeline = Timeline::parse(source)
+            .and_then(|timeline| {
+                timeline.validate(params.forest_width, params.forest_height)?;
+                Ok(timeline)
+            })
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.timeline_tx.send(timeline);
+        Ok(())
+    }
+    /// Get a copy of the snapshots taken by the timeline so far
+    #[wasm_bindgen]
+    pub fn snapshots(&self) -> SnapshotLog {
+        self.snapshots
+            .lock()
+            .expect("failed to lock snapshot log")
+            .clone()
+    }
+    /// Run as fast as possible until `until` says to stop, calling
+    /// `progress` with a `RunProgress` every so often. Cancel the run with
+    /// the handle from `RunUntil::cancel_handle`. This also works while the
+    /// simulation is paused, which is the usual way to spin up a forest.
+    #[wasm_bindgen]
+    pub async fn run_until(
+        &self,
+        until: RunUntil,
+        progress: Option<js_sys::Function>,
+    ) -> Result<RunOutcome, JsValue> {
+        until
+            .validate()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        *self
+            .fast_forward
+            .lock()
+            .expect("failed to lock fast-forward state") = FastForwardState {
+            request: Some(until),
+            ..Default::default()
+        };
+        // Wake the worker up in case it is paused, waiting for new parameters
+        self.parameters_tx.send(self.parameters_rx.clone().get());
+        loop {
+            gloo_timers::future::TimeoutFuture::new(FAST_FORWARD_POLL_MS).await;
+            if self.stop.load(Ordering::Relaxed) {
+                return Err(JsValue::from_str("the simulation was stopped"));
+            }
+            let (report, outcome) = {
+                let mut state = self
+                    .fast_forward
+                    .lock()
+                    .expect("failed to lock fast-forward state");
+                (state.progress.take(), state.outcome.take())
+            };
+            if let (Some(report), Some(callback)) = (report, &progress) {
+                callback.call1(&JsValue::NULL, &report.into())?;
+            }
+            if let Some(outcome) = outcome {
+                return outcome.map_err(|e| JsValue::from_str(&e));
+            }
+        }
+    }
 }
 
-pub async fn sim_thread(
-    mut parameters_rx: WatchReceiver<ConfigurableParameters>,
-    stop: Arc<AtomicBool>,
-    latest_frame_tx: WatchSender<SimulationFrame>,
-    mut latest_frame_rx: WatchReceiver<SimulationFrame>,
-    stats_tx: OneshotSender<SimulationStatistics>,
-    wants_new_frame: Arc<AtomicBool>,
-) {
+pub(crate) async fn sim_thread(args: SimWorkerArgs) {
+    let SimWorkerArgs {
+        mut parameters_rx,
+        stop,
+        latest_frame_tx,
+        mut latest_frame_rx,
+        stats_tx,
+        wants_new_frame,
+        fires,
+        events,
+        mut timeline_rx,
+        snapshots,
+        fast_forward,
+    } = args;
     let (device, queue) = gpucompute::create_device().await.unwrap();
     let mut end_of_last_step = Date::now();
     let mut total_iterations = 0;
@@ -307,9 +662,13 @@ pub async fn sim_thread(
         queue,
         latest_frame_rx.get(),
         SimulationParameters::from(&parameters_rx.get()),
+        Neighbourhood::from(&parameters_rx.get()),
         latest_frame_tx,
     )
     .unwrap();
+    context.set_fire_catalogue(fires);
+    context.set_event_log(events);
+    context.set_snapshot_log(snapshots);
 
     // Debug logging state
     let mut last_log_time = Date::now();
@@ -317,7 +676,26 @@ pub async fn sim_thread(
     let mut last_logged_params = Some(SimulationParameters::from(&parameters_rx.get()));
 
     while !stop.load(Ordering::Relaxed) {
-        let config_params = parameters_rx.get();
+        let request = fast_forward
+            .lock()
+            .expect("failed to lock fast-forward state")
+            .request
+            .take();
+        if let Some(until) = request {
+            let outcome =
+                run_fast_forward(&mut context, until, &mut parameters_rx, &fast_forward).await;
+            fast_forward
+                .lock()
+                .expect("failed to lock fast-forward state")
+                .outcome = Some(outcome.map_err(|e| e.to_string()));
+            context.send_latest();
+            end_of_last_step = Date::now();
+        }
+        if let Some(timeline) = timeline_rx.get_if_new() {
+            context.set_timeline(timeline);
+        }
+        let mut config_params = parameters_rx.get();
+        context.advance_timeline(&mut config_params);
         let parameters = SimulationParameters::from(&config_params);
 
         // Log when parameters change
@@ -331,6 +709,7 @@ pub async fn sim_thread(
             last_logged_params = Some(parameters);
         }
 
+        context.set_neighbourhood(Neighbourhood::from(&config_params));
         context.compute_step(parameters);
         total_time += Date::now() - end_of_last_step;
         if wants_new_frame.load(Ordering::Relaxed) {
@@ -357,7 +736,16 @@ pub async fn sim_thread(
         }
 
         if parameters.tick_rate == 0 {
-            parameters_rx.wait();
+            // A fast-forward asked for since the top of the loop may already
+            // have sent the parameters that would wake this up
+            let pending = fast_forward
+                .lock()
+                .expect("failed to lock fast-forward state")
+                .request
+                .is_some();
+            if !pending {
+                parameters_rx.wait();
+            }
             end_of_last_step = Date::now();
             continue;
         }
@@ -368,8 +756,52 @@ pub async fn sim_thread(
         }
         end_of_last_step = Date::now();
     }
+    let suppression = match context.suppression_effort().await {
+        Ok(effort) => effort,
+        Err(e) => {
+            log::error!("failed to read back suppression effort: {e}");
+            SuppressionEffort::default()
+        }
+    };
     let stats = SimulationStatistics {
         average_step_exec_time: total_time / total_iterations as f64,
+        suppression,
     };
     stats_tx.send(stats).unwrap();
 }
+
+/// Run a fast-forward in the worker, leaving its progress in `state` after
+/// every check
+async fn run_fast_forward(
+    context: &mut gpucompute::ComputeContext,
+    until: RunUntil,
+    parameters_rx: &mut WatchReceiver<ConfigurableParameters>,
+    state: &Mutex<FastForwardState>,
+) -> Result<RunOutcome, anyhow::Error> {
+    let mut run = FastForward::new(until, context.steps());
+    loop {
+        let mut config_params = parameters_rx.get();
+        let ticks_per_year = config_params.ticks_per_month * 12.0;
+        for _ in 0..run.batch_steps(context.steps(), ticks_per_year) {
+            context.advance_timeline(&mut config_params);
+            context.set_neighbourhood(Neighbourhood::from(&config_params));
+            context.compute_step(SimulationParameters::from(&config_params));
+        }
+        // So that the fire catalogue and event log are up to date with the
+        // summary
+        context.flush().await?;
+        let summary = context.read_summary().await?;
+        let (progress, reason) = run.check(context.steps(), summary, ticks_per_year);
+        state
+            .lock()
+            .expect("failed to lock fast-forward state")
+            .progress = Some(progress);
+        if let Some(reason) = reason {
+            return Ok(RunOutcome {
+                reason,
+                years: progress.years,
+                summary,
+            });
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Neighbourhood kernels for fire spread
+//!
+//! The compute shader doesn't hardcode which cells count as neighbours.
+//! Instead it loops over a kernel of weighted offsets that is generated here
+//! when the pipeline is created (and again if the neighbourhood changes). The
+//! chance of catching fire is proportional to the weighted fraction of the
+//! kernel that is burning.
+//!
+//! Hexagonal grids are stored as offset rows, where odd rows are shifted half
+//! a cell to the right. Their kernel entries apply only to even or only to odd
+//! rows, and the renderer draws the shifted rows to match.
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::sim::{ConfigurableParameters, SimulationParameters, parameters::ParameterValue};
+
+/// The largest number of offsets a kernel can have
+pub const MAX_KERNEL_ENTRIES: usize = 128;
+
+/// The largest radius of a `NeighbourhoodKind::Radius` kernel. A circle of
+/// this radius has fewer than `MAX_KERNEL_ENTRIES` cells.
+pub const MAX_NEIGHBOURHOOD_RADIUS: u32 = 6;
+
+/// The shape of the neighbourhood that fire spreads through
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u32)]
+pub enum NeighbourhoodKind {
+    /// The eight surrounding cells
+    #[default]
+    Moore = 0,
+    /// The four orthogonally adjacent cells
+    VonNeumann = 1,
+    /// Every cell within a Euclidean radius, weighted by distance
+    Radius = 2,
+    /// The six neighbours on a hexagonal grid
+    Hexagonal = 3,
+}
+
+impl ParameterValue for NeighbourhoodKind {
+    const KIND: &'static str = "choice";
+    const OPTIONS: &'static [&'static str] = &["moore", "von_neumann", "radius", "hexagonal"];
+
+    fn to_f64(self) -> f64 {
+        self as u32 as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        match value.round() as u32 {
+            1 => NeighbourhoodKind::VonNeumann,
+            2 => NeighbourhoodKind::Radius,
+            3 => NeighbourhoodKind::Hexagonal,
+            _ => NeighbourhoodKind::Moore,
+        }
+    }
+}
+
+/// A fully specified neighbourhood
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Neighbourhood {
+    pub kind: NeighbourhoodKind,
+    /// Radius in cells of a `Radius` neighbourhood
+    pub radius: u32,
+    /// Exponent of the distance weighting of a `Radius` neighbourhood. Each
+    /// cell is weighted by `distance^-falloff`, so 0 weights every cell
+    /// equally.
+    pub falloff: f32,
+}
+
+impl Default for Neighbourhood {
+    fn default() -> Self {
+        Self {
+            kind: NeighbourhoodKind::Moore,
+            radius: 1,
+            falloff: 0.0,
+        }
+    }
+}
+
+impl From<&ConfigurableParameters> for Neighbourhood {
+    fn from(config: &ConfigurableParameters) -> Self {
+        Self {
+            kind: config.neighbourhood,
+            radius: config.radius_cells(config.neighbourhood_radius_m),
+            falloff: config.neighbourhood_falloff,
+        }
+    }
+}
+
+/// Kernel entry applies to every row
+const PARITY_ALL: u32 = 0;
+/// Kernel entry applies to even rows only
+const PARITY_EVEN: u32 = 1;
+/// Kernel entry applies to odd rows only
+const PARITY_ODD: u32 = 2;
+
+/// A kernel offset as laid out in the GPU uniform
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct GpuKernelEntry {
+    pub dx: i32,
+    pub dy: i32,
+    pub weight: f32,
+    /// Which rows the entry applies to
+    pub parity: u32,
+}
+
+/// The neighbourhood kernel as laid out in the GPU uniform
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct GpuKernel {
+    pub count: u32,
+    pub _padding: [u32; 3],
+    pub entries: [GpuKernelEntry; MAX_KERNEL_ENTRIES],
+}
+
+impl Neighbourhood {
+    /// The neighbourhood that seeds are dispersed through
+    pub fn seed_dispersal(parameters: &SimulationParameters) -> Self {
+        Self {
+            kind: NeighbourhoodKind::Radius,
+            radius: parameters.seed_dispersal_radius,
+            falloff: parameters.seed_dispersal_falloff,
+        }
+    }
+
+    /// Whether cells are laid out on a hexagonal grid
+    pub fn is_hexagonal(&self) -> bool {
+        self.kind == NeighbourhoodKind::Hexagonal
+    }
+
+    /// Generate the kernel of weighted offsets for this neighbourhood
+    pub fn kernel(&self) -> GpuKernel {
+        let entry = |dx, dy, weight, parity| GpuKernelEntry {
+            dx,
+            dy,
+            weight,
+            parity,
+        };
+        let entries: Vec<GpuKernelEntry> = match self.kind {
+            NeighbourhoodKind::Moore => (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                .filter(|&offset| offset != (0, 0))
+                .map(|(dx, dy)| entry(dx, dy, 1.0, PARITY_ALL))
+                .collect(),
+            NeighbourhoodKind::VonNeumann => [(0, -1), (-1, 0), (1, 0), (0, 1)]
+                .into_iter()
+                .map(|(dx, dy)| entry(dx, dy, 1.0, PARITY_ALL))
+                .collect(),
+            NeighbourhoodKind::Radius => {
+                let radius = self.radius.clamp(1, MAX_NEIGHBOURHOOD_RADIUS) as i32;
+                (-radius..=radius)
+                    .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+                    .filter_map(|(dx, dy)| {
+                        let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                        (distance > 0.0 && distance <= radius as f32)
+                            .then(|| entry(dx, dy, distance.powf(-self.falloff), PARITY_ALL))
+                    })
+                    .collect()
+            }
+            NeighbourhoodKind::Hexagonal => {
+                // Odd rows are shifted right, so the diagonal neighbours of
+                // even rows lie to the left and those of odd rows to the right
+                let even = [(-1, 0), (1, 0), (-1, -1), (0, -1), (-1, 1), (0, 1)];
+                let odd = [(-1, 0), (1, 0), (0, -1), (1, -1), (0, 1), (1, 1)];
+                even.into_iter()
+                    .map(|(dx, dy)| entry(dx, dy, 1.0, PARITY_EVEN))
+                    .chain(
+                        odd.into_iter()
+                            .map(|(dx, dy)| entry(dx, dy, 1.0, PARITY_ODD)),
+                    )
+                    .collect()
+            }
+        };
+
+        let mut kernel = GpuKernel::zeroed();
+        let count = entries.len().min(MAX_KERNEL_ENTRIES);
+        kernel.count = count as u32;
+        kernel.entries[..count].copy_from_slice(&entries[..count]);
+        kernel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(kernel: &GpuKernel, parity: u32) -> Vec<(i32, i32)> {
+        kernel.entries[..kernel.count as usize]
+            .iter()
+            .filter(|e| e.parity == PARITY_ALL || e.parity == parity)
+            .map(|e| (e.dx, e.dy))
+            .collect()
+    }
+
+    #[test]
+    fn moore_kernel_is_the_eight_surrounding_cells() {
+        let kernel = Neighbourhood::default().kernel();
+        let mut cells = offsets(&kernel, PARITY_EVEN);
+        cells.sort();
+        assert_eq!(
+            cells,
+            [
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1)
+            ]
+        );
+        assert!(
+            kernel.entries[..8]
+                .iter()
+                .all(|e| e.weight == 1.0 && e.parity == PARITY_ALL)
+        );
+    }
+
+    #[test]
+    fn hexagonal_neighbours_are_mutual() {
+        let kernel = Neighbourhood {
+            kind: NeighbourhoodKind::Hexagonal,
+            ..Default::default()
+        }
+        .kernel();
+        for (row, parity) in [(0, PARITY_EVEN), (1, PARITY_ODD)] {
+            let cells = offsets(&kernel, parity);
+            assert_eq!(cells.len(), 6);
+            for (dx, dy) in cells {
+                // The neighbour's own kernel leads back to this cell
+                let back = if (row + dy) % 2 == 0 {
+                    PARITY_EVEN
+                } else {
+                    PARITY_ODD
+                };
+                assert!(
+                    offsets(&kernel, back).contains(&(-dx, -dy)),
+                    "({dx}, {dy}) from row {row}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn largest_radius_kernel_fits() {
+        let kernel = Neighbourhood {
+            kind: NeighbourhoodKind::Radius,
+            radius: MAX_NEIGHBOURHOOD_RADIUS + 1,
+            falloff: 1.0,
+        }
+        .kernel();
+        assert!((kernel.count as usize) < MAX_KERNEL_ENTRIES);
+        let corner = kernel.entries[..kernel.count as usize]
+            .iter()
+            .find(|e| (e.dx, e.dy) == (3, 4))
+            .unwrap();
+        assert_eq!(corner.weight, 0.2);
+    }
+
+    #[test]
+    fn radius_is_converted_from_metres() {
+        let config = ConfigurableParameters {
+            cell_size_m: 10.0,
+            neighbourhood_radius_m: 25.0,
+            ..Default::default()
+        };
+        assert_eq!(Neighbourhood::from(&config).radius, 3);
+        assert_eq!(config.radius_cells(1.0), 1);
+        assert_eq!(config.radius_cells(1000.0), MAX_NEIGHBOURHOOD_RADIUS);
+    }
+}
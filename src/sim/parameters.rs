@@ -0,0 +1,1420 @@
+//! The configurable parameters and their metadata
+//!
+//! `ConfigurableParameters` is declared through the `configurable_parameters!`
+//! macro, which also builds the [`PARAMETERS`] registry from the same
+//! declaration. Each registered field carries its label, unit and slider range
+//! in a `#[parameter(...)]` attribute, and its doc comment doubles as the
+//! description shown in the UI. The registry provides by-name getters and
+//! setters and the JSON schema that the frontend builds its controls from, so
+//! adding a parameter only means adding a field here.
+//!
+//! Registered parameters also declare the range of values that the
+//! simulation can run with, which `ConfigurableParameters::validate` and the
+//! by-name setters enforce. This is wider than the slider range.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::sim::neighbourhood::{MAX_NEIGHBOURHOOD_RADIUS, NeighbourhoodKind};
+
+/// Square metres in a hectare
+pub const SQUARE_METRES_PER_HECTARE: f32 = 10_000.0;
+/// Hectares in an acre
+pub const HECTARES_PER_ACRE: f32 = 0.404_686;
+
+/// The values a parameter is allowed to take
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValidRange {
+    pub min: f64,
+    pub max: f64,
+    /// Whether `min` itself is excluded
+    pub min_exclusive: bool,
+}
+
+impl ValidRange {
+    /// Any value greater than or equal to `min`
+    pub const fn at_least(min: f64) -> Self {
+        Self {
+            min,
+            max: f64::INFINITY,
+            min_exclusive: false,
+        }
+    }
+
+    /// Any value strictly greater than `min`
+    pub const fn above(min: f64) -> Self {
+        Self {
+            min,
+            max: f64::INFINITY,
+            min_exclusive: true,
+        }
+    }
+
+    /// Any value from `min` to `max` inclusive
+    pub const fn between(min: f64, max: f64) -> Self {
+        Self {
+            min,
+            max,
+            min_exclusive: false,
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        let above_min = if self.min_exclusive {
+            value > self.min
+        } else {
+            value >= self.min
+        };
+        above_min && value <= self.max
+    }
+}
+
+impl fmt::Display for ValidRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min_exclusive, self.max.is_finite()) {
+            (true, false) => write!(f, "greater than {}", self.min),
+            (false, false) => write!(f, "at least {}", self.min),
+            (true, true) => write!(f, "greater than {} and at most {}", self.min, self.max),
+            (false, true) => write!(f, "between {} and {}", self.min, self.max),
+        }
+    }
+}
+
+/// A parameter value that the simulation cannot run with
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParameterError {
+    /// There is no parameter with this name
+    Unknown { name: String },
+    /// The value is NaN or infinite
+    NotFinite { name: &'static str, value: f64 },
+    /// The value is outside the parameter's valid range
+    OutOfRange {
+        name: &'static str,
+        value: f64,
+        range: ValidRange,
+    },
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::Unknown { name } => write!(f, "unknown parameter `{name}`"),
+            ParameterError::NotFinite { name, value } => {
+                write!(f, "`{name}` must be a finite number, got {value}")
+            }
+            ParameterError::OutOfRange { name, value, range } => {
+                write!(f, "`{name}` must be {range}, got {value}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+/// A type that can be stored in a registered parameter
+pub trait ParameterValue: Copy {
+    /// The kind of value, as given in the schema
+    const KIND: &'static str;
+    /// Names of the options of a `"choice"` parameter, indexed by value
+    const OPTIONS: &'static [&'static str] = &[];
+
+    fn to_f64(self) -> f64;
+    fn from_f64(value: f64) -> Self;
+}
+
+impl ParameterValue for f32 {
+    const KIND: &'static str = "float";
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl ParameterValue for u32 {
+    const KIND: &'static str = "integer";
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        value.round() as u32
+    }
+}
+
+/// Which cells suppression crews work on first
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u32)]
+pub enum SuppressionStrategy {
+    /// No suppression
+    #[default]
+    Off = 0,
+    /// Every part of a fire's perimeter is as important as any other
+    Perimeter = 1,
+    /// The parts of the perimeter closest to assets first
+    Assets = 2,
+    /// The head of the fire, downwind of where it started, first
+    Downwind = 3,
+}
+
+impl ParameterValue for SuppressionStrategy {
+    const KIND: &'static str = "choice";
+    const OPTIONS: &'static [&'static str] = &["off", "perimeter", "assets", "downwind"];
+
+    fn to_f64(self) -> f64 {
+        self as u32 as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        match value.round() as u32 {
+            1 => SuppressionStrategy::Perimeter,
+            2 => SuppressionStrategy::Assets,
+            3 => SuppressionStrategy::Downwind,
+            _ => SuppressionStrategy::Off,
+        }
+    }
+}
+
+/// How cells at the edge of the grid see the world beyond it
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u32)]
+pub enum BoundaryMode {
+    /// Nothing beyond the edge, so fires die at the border
+    #[default]
+    Closed = 0,
+    /// The grid wraps around into a torus
+    Periodic = 1,
+    /// The grid is mirrored at each edge
+    Reflecting = 2,
+    /// Everything beyond the edge is permanently burning
+    FireSource = 3,
+}
+
+impl ParameterValue for BoundaryMode {
+    const KIND: &'static str = "choice";
+    const OPTIONS: &'static [&'static str] = &["closed", "periodic", "reflecting", "fire_source"];
+
+    fn to_f64(self) -> f64 {
+        self as u32 as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        match value.round() as u32 {
+            1 => BoundaryMode::Periodic,
+            2 => BoundaryMode::Reflecting,
+            3 => BoundaryMode::FireSource,
+            _ => BoundaryMode::Closed,
+        }
+    }
+}
+
+/// The shape of a tree's growth from sapling to maturity
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u32)]
+pub enum GrowthCurve {
+    /// Slow at first, fastest at half size, then levelling off
+    #[default]
+    Logistic = 0,
+    /// A constant amount every tick until fully grown
+    Linear = 1,
+}
+
+impl ParameterValue for GrowthCurve {
+    const KIND: &'static str = "choice";
+    const OPTIONS: &'static [&'static str] = &["logistic", "linear"];
+
+    fn to_f64(self) -> f64 {
+        self as u32 as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        match value.round() as u32 {
+            1 => GrowthCurve::Linear,
+            _ => GrowthCurve::Logistic,
+        }
+    }
+}
+
+/// How the chance of a tree dying naturally changes with its age
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u32)]
+pub enum MortalityCurve {
+    /// The same chance at every age
+    #[default]
+    Constant = 0,
+    /// A Weibull hazard, which rises as a power of age for shapes above 1
+    Weibull = 1,
+    /// A Gompertz hazard, which rises exponentially with age
+    Gompertz = 2,
+}
+
+impl ParameterValue for MortalityCurve {
+    const KIND: &'static str = "choice";
+    const OPTIONS: &'static [&'static str] = &["constant", "weibull", "gompertz"];
+
+    fn to_f64(self) -> f64 {
+        self as u32 as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        match value.round() as u32 {
+            1 => MortalityCurve::Weibull,
+            2 => MortalityCurve::Gompertz,
+            _ => MortalityCurve::Constant,
+        }
+    }
+}
+
+/// Metadata and accessors for a single registered parameter
+pub struct ParameterSpec {
+    /// Field name in `ConfigurableParameters`
+    pub name: &'static str,
+    pub label: &'static str,
+    /// Unit of the value, or an empty string if it is unitless
+    pub unit: &'static str,
+    /// The lines of the field's doc comment
+    pub doc: &'static [&'static str],
+    /// `"float"`, `"integer"` or `"choice"`
+    pub kind: &'static str,
+    /// Option names of a `"choice"` parameter, indexed by value
+    pub options: &'static [&'static str],
+    /// Slider range and step
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+    /// The values the simulation can run with
+    pub valid: ValidRange,
+    pub get: fn(&ConfigurableParameters) -> f64,
+    pub set: fn(&mut ConfigurableParameters, f64),
+}
+
+impl ParameterSpec {
+    /// The doc comment as a single paragraph
+    pub fn description(&self) -> String {
+        self.doc
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Look up a registered parameter by its field name
+    pub fn find(name: &str) -> Result<&'static ParameterSpec, ParameterError> {
+        PARAMETERS
+            .iter()
+            .find(|spec| spec.name == name)
+            .ok_or_else(|| ParameterError::Unknown {
+                name: name.to_string(),
+            })
+    }
+
+    /// Check that `value` is valid for this parameter
+    pub fn check(&self, value: f64) -> Result<(), ParameterError> {
+        check_value(self.name, value, self.valid)
+    }
+}
+
+fn check_value(name: &'static str, value: f64, range: ValidRange) -> Result<(), ParameterError> {
+    if !value.is_finite() {
+        Err(ParameterError::NotFinite { name, value })
+    } else if !range.contains(value) {
+        Err(ParameterError::OutOfRange { name, value, range })
+    } else {
+        Ok(())
+    }
+}
+
+/// Declares `ConfigurableParameters` and the `PARAMETERS` registry.
+///
+/// Fields before the `;` are plain fields that are not part of the registry.
+/// Fields after it are registered and must have a `#[parameter(...)]`
+/// attribute after their doc comment, giving the label, unit, slider range
+/// and step, and the `ValidRange` of the parameter.
+macro_rules! configurable_parameters {
+    (
+        $(#[$struct_meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$fixed_meta:meta])*
+                pub $fixed:ident: $fixed_ty:ty,
+            )*
+            ;
+            $(
+                $(#[doc = $doc:literal])*
+                #[parameter(
+                    label = $label:literal,
+                    unit = $unit:literal,
+                    min = $min:expr,
+                    max = $max:expr,
+                    step = $step:expr,
+                    valid = $valid:expr $(,)?
+                )]
+                pub $field:ident: $ty:ty,
+            )*
+        }
+    ) => {
+        $(#[$struct_meta])*
+        pub struct $name {
+            $(
+                $(#[$fixed_meta])*
+                pub $fixed: $fixed_ty,
+            )*
+            $(
+                $(#[doc = $doc])*
+                pub $field: $ty,
+            )*
+        }
+
+        /// Every registered parameter, in declaration order
+        pub static PARAMETERS: &[ParameterSpec] = &[
+            $(
+                ParameterSpec {
+                    name: stringify!($field),
+                    label: $label,
+                    unit: $unit,
+                    doc: &[$($doc),*],
+                    kind: <$ty as ParameterValue>::KIND,
+                    options: <$ty as ParameterValue>::OPTIONS,
+                    min: $min,
+                    max: $max,
+                    step: $step,
+                    valid: $valid,
+                    get: |params| ParameterValue::to_f64(params.$field),
+                    set: |params, value| params.$field = ParameterValue::from_f64(value),
+                },
+            )*
+        ];
+    };
+}
+
+configurable_parameters! {
+    /// Configuration parameters with realistic units and static forest properties.
+    /// These parameters are grounded in reality and are used to compute the internal
+    /// SimulationParameters struct.
+    ///
+    /// The forest size and seed are not (de)serialized, since scenario files give
+    /// them separately (see `crate::scenario::Scenario`).
+    #[derive(Clone, Serialize, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    #[wasm_bindgen]
+    pub struct ConfigurableParameters {
+        // Static parameters - forest size
+        /// Width of the forest in cells
+        #[serde(skip)]
+        pub forest_width: usize,
+        /// Height of the forest in cells
+        #[serde(skip)]
+        pub forest_height: usize,
+        /// Seed for the random number generator. Runs with the same seed and
+        /// parameters evolve identically.
+        #[serde(skip)]
+        pub seed: u32,
+        ;
+        // Time scale parameters
+        /// Number of simulation ticks per month
+        #[parameter(
+            label = "Resolution",
+            unit = "ticks/month",
+            min = 1.0,
+            max = 20.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub ticks_per_month: f32,
+        /// Number of months that pass per second of real time
+        #[parameter(
+            label = "Simulation Speed",
+            unit = "months/sec",
+            min = 1.0,
+            max = 1000.0,
+            step = 1.0,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub months_per_second: f32,
+
+        // Spatial scale
+        /// Length of the side of a cell in metres. Areas, distances and the
+        /// number of cells fire crosses each tick are worked out from this,
+        /// so a finer grid of the same forest burns the same way, as long as
+        /// fire crosses no more than `MAX_FIRE_SUBSTEPS` cells a tick and the
+        /// neighbourhoods stay within `MAX_NEIGHBOURHOOD_RADIUS` cells.
+        #[parameter(
+            label = "Cell Size",
+            unit = "m",
+            min = 1.0,
+            max = 100.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub cell_size_m: f32,
+
+        // Realistic configurable parameters
+        /// Lightning strike frequency in strikes per year per acre
+        #[parameter(
+            label = "Lightning",
+            unit = "strikes/year/acre",
+            min = 0.0,
+            max = 0.1,
+            step = 0.001,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub lightning_strikes_per_year_per_acre: f32,
+        /// Number of thunderstorms that pass over the forest each year. Storm
+        /// strikes come on top of the independent lightning strikes.
+        #[parameter(
+            label = "Storms",
+            unit = "storms/year",
+            min = 0.0,
+            max = 20.0,
+            step = 0.5,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub storms_per_year: f32,
+        /// Average number of lightning strikes dropped by a storm along its
+        /// whole track, including any that fall outside the forest
+        #[parameter(
+            label = "Strikes per Storm",
+            unit = "strikes",
+            min = 1.0,
+            max = 200.0,
+            step = 1.0,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub strikes_per_storm: f32,
+        /// How far from the centre of its track a storm drops strikes
+        #[parameter(
+            label = "Storm Radius",
+            unit = "m",
+            min = 10.0,
+            max = 5000.0,
+            step = 10.0,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub storm_radius_m: f32,
+        /// How far a storm travels while it drops strikes
+        #[parameter(
+            label = "Storm Track Length",
+            unit = "km",
+            min = 0.1,
+            max = 50.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub storm_track_km: f32,
+        /// Germination rate: average years for a sapling to take root in an
+        /// empty cell (e.g., 150.0 means 1/150 per year)
+        #[parameter(
+            label = "Germination",
+            unit = "years",
+            min = 10.0,
+            max = 500.0,
+            step = 10.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub tree_growth_years: f32,
+        /// The size (0 - 1) of a newly germinated sapling relative to a
+        /// mature tree
+        #[parameter(
+            label = "Sapling Size",
+            unit = "",
+            min = 0.01,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::between(0.001, 1.0),
+        )]
+        pub sapling_size: f32,
+        /// The time for a sapling to grow to (nearly) full size
+        #[parameter(
+            label = "Years to Maturity",
+            unit = "years",
+            min = 1.0,
+            max = 200.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub tree_maturity_years: f32,
+        /// How a tree's size increases towards maturity: logistic (slow, then
+        /// fast, then levelling off) or linear
+        #[parameter(
+            label = "Growth Curve",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub growth_curve: GrowthCurve,
+        /// Tree lifespan: with a constant mortality curve, the average years
+        /// for a tree to die naturally (e.g., 200.0 means 1/200 per year).
+        /// Otherwise the characteristic age at which the hazard is
+        /// `mortality_shape / tree_death_years` per year.
+        #[parameter(
+            label = "Tree Death",
+            unit = "years",
+            min = 50.0,
+            max = 500.0,
+            step = 10.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub tree_death_years: f32,
+        /// How the chance of a tree dying naturally changes with its age:
+        /// constant, Weibull (rising as a power of age) or Gompertz (rising
+        /// exponentially with age)
+        #[parameter(
+            label = "Mortality Curve",
+            unit = "",
+            min = 0.0,
+            max = 2.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, 2.0),
+        )]
+        pub mortality_curve: MortalityCurve,
+        /// The shape of the mortality curve. For the Weibull curve this is the
+        /// exponent `k` (1 is constant, larger values concentrate deaths around
+        /// `tree_death_years`), and for the Gompertz curve the hazard grows by
+        /// a factor of `e^k` every `tree_death_years`.
+        #[parameter(
+            label = "Mortality Shape",
+            unit = "",
+            min = 0.5,
+            max = 10.0,
+            step = 0.1,
+            valid = ValidRange::above(0.0),
+        )]
+        pub mortality_shape: f32,
+        /// The health (0 - 1) a tree loses each tick when its whole
+        /// neighbourhood is burning. Trees die when their health reaches 0,
+        /// and unhealthy trees are more likely to die naturally.
+        #[parameter(
+            label = "Scorch Damage",
+            unit = "health/tick",
+            min = 0.0,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub scorch_damage: f32,
+        /// The health (0 - 1) a tree loses each year when its whole
+        /// neighbourhood is full of mature trees
+        #[parameter(
+            label = "Crowding Stress",
+            unit = "health/year",
+            min = 0.0,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub crowding_stress: f32,
+        /// The health (0 - 1) a tree regains each year
+        #[parameter(
+            label = "Health Recovery",
+            unit = "health/year",
+            min = 0.0,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub health_recovery: f32,
+        /// How strongly neighbouring trees suppress germination. The
+        /// germination rate is multiplied by `1 - germination_competition *
+        /// density`, where density is the fraction (0 - 1) of the
+        /// neighbourhood covered by tree biomass.
+        #[parameter(
+            label = "Germination Competition",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub germination_competition: f32,
+        /// How strongly neighbouring trees slow growth. The growth rate is
+        /// multiplied by `1 - growth_competition * density`.
+        #[parameter(
+            label = "Growth Competition",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub growth_competition: f32,
+        /// How strongly neighbouring trees raise the chance of natural death.
+        /// The chance is multiplied by `1 + mortality_competition * density`.
+        #[parameter(
+            label = "Mortality Competition",
+            unit = "",
+            min = 0.0,
+            max = 5.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub mortality_competition: f32,
+        /// The fraction (0 - 1) by which a fully alerted tree's flammability is
+        /// reduced. Trees near a fire or in dense stands raise an alert that
+        /// is passed on through neighbouring trees (mycorrhizal signalling).
+        #[parameter(
+            label = "Alert Strength",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub alert_strength: f32,
+        /// The fraction (0 - 1) of a neighbouring tree's alert that is passed
+        /// on each tick. The alert weakens by this factor (and the decay) with
+        /// every tree it passes through, so higher values carry it further.
+        #[parameter(
+            label = "Alert Range",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub alert_range: f32,
+        /// The fraction (0 - 1) of a tree's alert that is lost each tick
+        #[parameter(
+            label = "Alert Decay",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub alert_decay: f32,
+        /// The alert raised each tick in a tree whose whole neighbourhood is
+        /// full of mature trees
+        #[parameter(
+            label = "Alert From Dense Stands",
+            unit = "",
+            min = 0.0,
+            max = 0.1,
+            step = 0.001,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub alert_stand_signal: f32,
+        /// How far seeds are carried from the trees that produce them.
+        /// Saplings can only germinate within this radius of a seed-bearing
+        /// tree, apart from background seed rain. The radius is rounded to
+        /// whole cells, between 1 and `MAX_NEIGHBOURHOOD_RADIUS`.
+        #[parameter(
+            label = "Seed Dispersal Radius",
+            unit = "m",
+            min = 1.0,
+            max = 100.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub seed_dispersal_radius_m: f32,
+        /// How quickly the seed supply falls off with distance. Each cell in
+        /// the dispersal radius is weighted by `distance^-seed_dispersal_falloff`.
+        #[parameter(
+            label = "Seed Dispersal Falloff",
+            unit = "",
+            min = 0.0,
+            max = 3.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub seed_dispersal_falloff: f32,
+        /// The smallest size (0 - 1) at which a tree produces seeds
+        #[parameter(
+            label = "Seed-Bearing Size",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub seed_bearing_size: f32,
+        /// The seed supply (0 - 1) that reaches every cell from far away,
+        /// relative to a cell surrounded by seed-bearing trees. At 1, every
+        /// cell gets a full supply and saplings don't need nearby trees.
+        #[parameter(
+            label = "Background Seed Rain",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub background_seed_rain: f32,
+        /// Serotiny: the fraction (0 - 1) of a burnt tree's size that is
+        /// released as a seed bank when its fire goes out. The seeds spread
+        /// into neighbouring burnt cells through the dispersal kernel.
+        #[parameter(
+            label = "Serotiny",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub serotiny: f32,
+        /// Average months for a cell with a full seed bank to germinate
+        #[parameter(
+            label = "Seed Bank Germination",
+            unit = "months",
+            min = 1.0,
+            max = 60.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub seed_bank_germination_months: f32,
+        /// Average years for the seeds in a seed bank to stop being viable
+        #[parameter(
+            label = "Seed Bank Lifetime",
+            unit = "years",
+            min = 0.5,
+            max = 20.0,
+            step = 0.5,
+            valid = ValidRange::above(0.0),
+        )]
+        pub seed_bank_years: f32,
+        /// Mean fuel moisture (as a fraction of dry weight) of the built-in
+        /// sinusoidal weather profile
+        #[parameter(
+            label = "Mean Fuel Moisture",
+            unit = "",
+            min = 0.0,
+            max = 0.5,
+            step = 0.01,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub mean_fuel_moisture: f32,
+        /// How far the fuel moisture of the sinusoidal weather profile swings
+        /// above and below its mean over the year
+        #[parameter(
+            label = "Fuel Moisture Amplitude",
+            unit = "",
+            min = 0.0,
+            max = 0.5,
+            step = 0.01,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub fuel_moisture_amplitude: f32,
+        /// The month (1 - 12) in which the sinusoidal weather profile is
+        /// driest
+        #[parameter(
+            label = "Driest Month",
+            unit = "",
+            min = 1.0,
+            max = 12.0,
+            step = 1.0,
+            valid = ValidRange::between(1.0, 12.0),
+        )]
+        pub driest_month: f32,
+        /// The fuel moisture above which fuel doesn't burn. Flammability,
+        /// spread and lightning ignitions all fall linearly to zero as the
+        /// moisture rises towards it.
+        #[parameter(
+            label = "Moisture of Extinction",
+            unit = "",
+            min = 0.05,
+            max = 0.6,
+            step = 0.01,
+            valid = ValidRange::above(0.0),
+        )]
+        pub moisture_of_extinction: f32,
+        /// The chance (0 - 1) that any given year is a drought year. Drought
+        /// years are drawn from the seed, so runs are reproducible.
+        #[parameter(
+            label = "Drought Probability",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub drought_probability: f32,
+        /// The fraction (0 - 1) by which fuel moisture is reduced throughout a
+        /// drought year
+        #[parameter(
+            label = "Drought Severity",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub drought_severity: f32,
+        /// The change in fuel moisture per century as a fraction of its
+        /// starting value, e.g. -0.2 for fuel that is 20% drier after a
+        /// hundred years
+        #[parameter(
+            label = "Moisture Trend",
+            unit = "/century",
+            min = -1.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::at_least(-1.0),
+        )]
+        pub moisture_trend_per_century: f32,
+        /// The chance (0 - 1) each tick that a burning cell lofts an ember,
+        /// per unit of fuel load (tree size + underbrush) above the ember
+        /// threshold. Embers can carry fire across firebreaks.
+        #[parameter(
+            label = "Ember Production",
+            unit = "",
+            min = 0.0,
+            max = 0.5,
+            step = 0.01,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub ember_rate: f32,
+        /// The fuel load (tree size + underbrush) a burning cell needs before
+        /// it lofts embers
+        #[parameter(
+            label = "Ember Fuel Threshold",
+            unit = "",
+            min = 0.0,
+            max = 3.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub ember_intensity_threshold: f32,
+        /// The mean distance embers travel before landing
+        #[parameter(
+            label = "Ember Distance",
+            unit = "m",
+            min = 1.0,
+            max = 500.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub ember_distance_m: f32,
+        /// The furthest distance embers can travel. Embers always land at
+        /// least one cell away.
+        #[parameter(
+            label = "Max Ember Distance",
+            unit = "m",
+            min = 1.0,
+            max = 2000.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub ember_max_distance_m: f32,
+        /// The chance (0 - 1) that an ember ignites the cell it lands in, at
+        /// full flammability. Damp or sparse fuel is less likely to ignite.
+        #[parameter(
+            label = "Ember Ignition",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub ember_ignition: f32,
+        /// The direction the wind blows towards, in degrees clockwise from
+        /// north (the top of the grid)
+        #[parameter(
+            label = "Wind Direction",
+            unit = "degrees",
+            min = 0.0,
+            max = 359.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, 360.0),
+        )]
+        pub wind_direction: f32,
+        /// How strongly (0 - 1) the wind carries embers downwind. At 0 embers
+        /// land in any direction, and at 1 they land directly downwind.
+        #[parameter(
+            label = "Wind Strength",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub wind_strength: f32,
+        /// The underbrush load beneath a tree at which a surface fire climbs
+        /// into the canopy and becomes a crown fire. Fires start on the
+        /// surface, where they burn the underbrush and only kill small trees.
+        #[parameter(
+            label = "Ladder Fuel Threshold",
+            unit = "",
+            min = 0.0,
+            max = 5.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub ladder_fuel_threshold: f32,
+        /// How many times more readily crown fires spread to their neighbours
+        /// than surface fires
+        #[parameter(
+            label = "Crown Fire Spread",
+            unit = "×",
+            min = 1.0,
+            max = 10.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub crown_fire_spread: f32,
+        /// Trees smaller than this size (0 - 1) are killed by surface fires.
+        /// Crown fires kill trees of every size.
+        #[parameter(
+            label = "Surface Fire Kill Size",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::between(0.0, 1.0),
+        )]
+        pub surface_fire_kill_size: f32,
+        /// The underbrush load that a surface fire consumes completely when
+        /// the fuel is bone dry. Lighter or damper underbrush burns cooler,
+        /// and the fire leaves some of it behind.
+        #[parameter(
+            label = "Complete Consumption Load",
+            unit = "",
+            min = 0.1,
+            max = 5.0,
+            step = 0.1,
+            valid = ValidRange::above(0.0),
+        )]
+        pub complete_consumption_load: f32,
+        /// Which cells suppression crews work on first: none (suppression is
+        /// off), the whole perimeter equally, the perimeter closest to the
+        /// assets of the management plan, or the head of the fire downwind
+        #[parameter(
+            label = "Suppression",
+            unit = "",
+            min = 0.0,
+            max = 3.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, 3.0),
+        )]
+        pub suppression_strategy: SuppressionStrategy,
+        /// The number of ticks a fire burns for before suppression starts
+        #[parameter(
+            label = "Detection Delay",
+            unit = "ticks",
+            min = 0.0,
+            max = 10.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, u32::MAX as f64),
+        )]
+        pub detection_delay: u32,
+        /// The number of cells suppression crews can put out or clear for
+        /// containment lines each tick, across all fires
+        #[parameter(
+            label = "Suppression Capacity",
+            unit = "cells/tick",
+            min = 0.0,
+            max = 500.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, u32::MAX as f64),
+        )]
+        pub suppression_capacity: u32,
+        /// The factor by which the tree growth rate is reduced with underbrush.
+        /// The final growth rate is
+        /// `(1.0 - underbrush_tree_growth_hindrance * underbrush) * tree_growth_rate`.
+        #[parameter(
+            label = "Underbrush Growth Hindrance",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub underbrush_tree_growth_hindrance: f32,
+        /// The base rate of underbrush accumulation per tick
+        #[parameter(
+            label = "Underbrush Generation",
+            unit = "",
+            min = 0.0,
+            max = 0.01,
+            step = 0.00001,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub tree_underbrush_generation: f32,
+        /// The amount of underbrush created when a tree dies naturally
+        #[parameter(
+            label = "Underbrush From Tree Death",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.01,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub tree_death_underbrush: f32,
+        /// The length a fully grown tree can support a fire for in ticks. Smaller
+        /// trees burn for proportionally less, but at least one tick
+        #[parameter(
+            label = "Tree Fire Duration",
+            unit = "ticks",
+            min = 1.0,
+            max = 10.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, u32::MAX as f64),
+        )]
+        pub tree_fire_duration: u32,
+        /// The length that underbrush can support a fire for in ticks. This is
+        /// multiplied by the amount of underbrush
+        #[parameter(
+            label = "Underbrush Fire Duration",
+            unit = "ticks",
+            min = 1.0,
+            max = 10.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, u32::MAX as f64),
+        )]
+        pub underbrush_fire_duration: u32,
+        /// How fast the head of a fire advances through fully flammable fuel
+        /// while it is actively spreading. Together with the active spread
+        /// time and the cell size this gives the number of cells fire crosses
+        /// each tick.
+        #[parameter(
+            label = "Rate of Spread",
+            unit = "m/min",
+            min = 0.0,
+            max = 100.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub rate_of_spread: f32,
+        /// How long fire actively spreads for each tick. Fires spread in
+        /// bursts when the weather allows and smoulder the rest of the time,
+        /// so this is usually much shorter than the length of a tick.
+        #[parameter(
+            label = "Active Spread Time",
+            unit = "min/tick",
+            min = 0.0,
+            max = 1440.0,
+            step = 1.0,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub spread_minutes_per_tick: f32,
+        /// The multiplier for fire spread rate for fully grown trees (multiplied by
+        /// the size of the tree)
+        #[parameter(
+            label = "Tree Flammability",
+            unit = "",
+            min = 0.0,
+            max = 1.0,
+            step = 0.05,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub tree_flammability: f32,
+        /// The multiplier for fire spread rate for underbrush (multiplied by the
+        /// amount of underbrush). This is added with the value from tree_flammability
+        /// to calculate the final chance
+        #[parameter(
+            label = "Underbrush Flammability",
+            unit = "",
+            min = 0.0,
+            max = 2.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub underbrush_flammability: f32,
+        /// How cells at the edge of the grid see the world beyond it: closed
+        /// (fires die at the border), periodic (the grid wraps around),
+        /// reflecting (the grid is mirrored) or fire source (the border is
+        /// always burning)
+        #[parameter(
+            label = "Boundary",
+            unit = "",
+            min = 0.0,
+            max = 3.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, 3.0),
+        )]
+        pub boundary_mode: BoundaryMode,
+        /// The cells that fire can spread to: the 8-cell Moore neighbourhood,
+        /// the 4-cell von Neumann neighbourhood, every cell within a radius,
+        /// or the 6 neighbours of a hexagonal grid
+        #[parameter(
+            label = "Neighbourhood",
+            unit = "",
+            min = 0.0,
+            max = 3.0,
+            step = 1.0,
+            valid = ValidRange::between(0.0, 3.0),
+        )]
+        pub neighbourhood: NeighbourhoodKind,
+        /// The radius of the radius neighbourhood. The radius is rounded to
+        /// whole cells, between 1 and `MAX_NEIGHBOURHOOD_RADIUS`.
+        #[parameter(
+            label = "Neighbourhood Radius",
+            unit = "m",
+            min = 1.0,
+            max = 100.0,
+            step = 1.0,
+            valid = ValidRange::above(0.0),
+        )]
+        pub neighbourhood_radius_m: f32,
+        /// How quickly the chance of spreading falls off with distance in the
+        /// radius neighbourhood. Each cell is weighted by
+        /// `distance^-neighbourhood_falloff`.
+        #[parameter(
+            label = "Neighbourhood Falloff",
+            unit = "",
+            min = 0.0,
+            max = 3.0,
+            step = 0.1,
+            valid = ValidRange::at_least(0.0),
+        )]
+        pub neighbourhood_falloff: f32,
+    }
+}
+
+impl ConfigurableParameters {
+    /// Create realistic default parameters for a forest of the given size.
+    /// Optional mechanisms, such as age-dependent mortality or competition,
+    /// start out switched off so that the defaults behave like the basic
+    /// model until a scenario turns them on.
+    pub fn realistic(
+        width: usize,
+        height: usize,
+        ticks_per_month: f32,
+        months_per_second: f32,
+    ) -> ConfigurableParameters {
+        let mut params = Self {
+            forest_width: 0,
+            forest_height: 0,
+            seed: 0,
+            ticks_per_month,
+            months_per_second,
+            cell_size_m: 1.0,
+            lightning_strikes_per_year_per_acre: 1.0 / 45.0, // ~1 strike per 45 acres per year
+            storms_per_year: 0.0,
+            strikes_per_storm: 20.0,
+            storm_radius_m: 100.0,
+            storm_track_km: 0.5,
+            tree_growth_years: 150.0,
+            sapling_size: 0.05,
+            tree_maturity_years: 30.0,
+            growth_curve: GrowthCurve::Logistic,
+            tree_death_years: 200.0,
+            mortality_curve: MortalityCurve::Constant,
+            mortality_shape: 3.0,
+            scorch_damage: 0.0,
+            crowding_stress: 0.0,
+            health_recovery: 0.1,
+            germination_competition: 0.0,
+            growth_competition: 0.0,
+            mortality_competition: 0.0,
+            alert_strength: 0.0,
+            alert_range: 0.8,
+            alert_decay: 0.1,
+            alert_stand_signal: 0.0,
+            seed_dispersal_radius_m: 2.0,
+            seed_dispersal_falloff: 1.0,
+            seed_bearing_size: 0.3,
+            background_seed_rain: 1.0,
+            serotiny: 0.0,
+            seed_bank_germination_months: 6.0,
+            seed_bank_years: 2.0,
+            mean_fuel_moisture: 0.12,
+            fuel_moisture_amplitude: 0.08,
+            driest_month: 8.0,
+            moisture_of_extinction: 0.3,
+            drought_probability: 0.0,
+            drought_severity: 0.4,
+            moisture_trend_per_century: 0.0,
+            ember_rate: 0.0,
+            ember_intensity_threshold: 1.0,
+            ember_distance_m: 5.0,
+            ember_max_distance_m: 30.0,
+            ember_ignition: 0.5,
+            wind_direction: 90.0,
+            wind_strength: 0.0,
+            ladder_fuel_threshold: 1.0,
+            crown_fire_spread: 3.0,
+            surface_fire_kill_size: 0.3,
+            complete_consumption_load: 1.0,
+            suppression_strategy: SuppressionStrategy::Off,
+            detection_delay: 1,
+            suppression_capacity: 20,
+            underbrush_tree_growth_hindrance: 0.0,
+            tree_underbrush_generation: 0.0001,
+            tree_death_underbrush: 0.01,
+            tree_fire_duration: 1,
+            underbrush_fire_duration: 1,
+            rate_of_spread: 1.0,
+            spread_minutes_per_tick: 1.0,
+            tree_flammability: 0.5,
+            underbrush_flammability: 1.0,
+            boundary_mode: BoundaryMode::Closed,
+            neighbourhood: NeighbourhoodKind::Moore,
+            neighbourhood_radius_m: 1.0,
+            neighbourhood_falloff: 0.0,
+        };
+        params.set_forest_size(width, height);
+        params
+    }
+
+    /// Set the size of the forest in cells
+    pub fn set_forest_size(&mut self, width: usize, height: usize) {
+        self.forest_width = width;
+        self.forest_height = height;
+    }
+
+    /// Area of a single cell in hectares
+    pub fn cell_hectares(&self) -> f32 {
+        self.cell_size_m * self.cell_size_m / SQUARE_METRES_PER_HECTARE
+    }
+
+    /// Area of the forest in hectares
+    pub fn forest_hectares(&self) -> f32 {
+        (self.forest_width * self.forest_height) as f32 * self.cell_hectares()
+    }
+
+    /// Area of the forest in acres
+    pub fn forest_acres(&self) -> f32 {
+        self.forest_hectares() / HECTARES_PER_ACRE
+    }
+
+    /// A neighbourhood radius in metres as a whole number of cells, between 1
+    /// and `MAX_NEIGHBOURHOOD_RADIUS`
+    pub fn radius_cells(&self, metres: f32) -> u32 {
+        ((metres / self.cell_size_m).round() as u32).clamp(1, MAX_NEIGHBOURHOOD_RADIUS)
+    }
+
+    /// Set a registered parameter by its field name. Integer parameters are
+    /// rounded. Invalid values are rejected and leave the parameter unchanged.
+    pub fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), ParameterError> {
+        let spec = ParameterSpec::find(name)?;
+        spec.check(value)?;
+        (spec.set)(self, value);
+        Ok(())
+    }
+
+    /// Get a registered parameter by its field name
+    pub fn get_parameter(&self, name: &str) -> Result<f64, ParameterError> {
+        Ok((ParameterSpec::find(name)?.get)(self))
+    }
+
+    /// Check that every parameter is one the simulation can run with,
+    /// returning the first that is not
+    pub fn validate(&self) -> Result<(), ParameterError> {
+        let size = ValidRange::at_least(1.0);
+        check_value("forest_width", self.forest_width as f64, size)?;
+        check_value("forest_height", self.forest_height as f64, size)?;
+        PARAMETERS
+            .iter()
+            .try_for_each(|spec| spec.check((spec.get)(self)))
+    }
+}
+
+impl Default for ConfigurableParameters {
+    fn default() -> Self {
+        Self::realistic(500, 500, 2.0, 36.0)
+    }
+}
+
+/// JSON description of every registered parameter, in declaration order.
+///
+/// Each entry has `name`, `label`, `unit`, `description`, `kind` (`"float"`,
+/// `"integer"` or `"choice"`), `options` (the names of a choice's values),
+/// `min`, `max`, `step` and `default`, plus the valid range
+/// as `valid_min`, `valid_min_exclusive` and `valid_max` (`null` if
+/// unbounded).
+#[wasm_bindgen]
+pub fn parameter_schema() -> String {
+    let defaults = ConfigurableParameters::default();
+    let schema: Vec<serde_json::Value> = PARAMETERS
+        .iter()
+        .map(|spec| {
+            serde_json::json!({
+                "name": spec.name,
+                "label": spec.label,
+                "unit": spec.unit,
+                "description": spec.description(),
+                "kind": spec.kind,
+                "options": spec.options,
+                "min": spec.min,
+                "max": spec.max,
+                "step": spec.step,
+                "default": (spec.get)(&defaults),
+                "valid_min": spec.valid.min,
+                "valid_min_exclusive": spec.valid.min_exclusive,
+                "valid_max": spec.valid.max.is_finite().then_some(spec.valid.max),
+            })
+        })
+        .collect();
+    serde_json::Value::Array(schema).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_range_bounds() {
+        assert!(ValidRange::at_least(0.0).contains(0.0));
+        assert!(!ValidRange::at_least(0.0).contains(-0.1));
+        assert!(!ValidRange::above(0.0).contains(0.0));
+        assert!(ValidRange::above(0.0).contains(0.1));
+        let months = ValidRange::between(1.0, 12.0);
+        assert!(months.contains(1.0) && months.contains(12.0));
+        assert!(!months.contains(12.5));
+        assert_eq!(months.to_string(), "between 1 and 12");
+        assert_eq!(ValidRange::above(0.0).to_string(), "greater than 0");
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(ConfigurableParameters::default().validate(), Ok(()));
+        let schema: serde_json::Value = serde_json::from_str(&parameter_schema()).unwrap();
+        assert_eq!(schema.as_array().unwrap().len(), PARAMETERS.len());
+    }
+
+    #[test]
+    fn validate_reports_the_first_bad_parameter() {
+        let params = ConfigurableParameters {
+            cell_size_m: 0.0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            params.validate(),
+            Err(ParameterError::OutOfRange {
+                name: "cell_size_m",
+                ..
+            })
+        ));
+
+        let params = ConfigurableParameters {
+            rate_of_spread: f32::NAN,
+            ..Default::default()
+        };
+        assert!(matches!(
+            params.validate(),
+            Err(ParameterError::NotFinite {
+                name: "rate_of_spread",
+                ..
+            })
+        ));
+
+        let mut params = ConfigurableParameters::default();
+        params.set_forest_size(0, 10);
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn set_parameter_checks_and_rounds() {
+        let mut params = ConfigurableParameters::default();
+        params.set_parameter("tree_fire_duration", 2.6).unwrap();
+        assert_eq!(params.tree_fire_duration, 3);
+
+        assert!(params.set_parameter("driest_month", 13.0).is_err());
+        assert_eq!(params.get_parameter("driest_month"), Ok(8.0));
+        assert_eq!(
+            params.set_parameter("no_such_parameter", 1.0),
+            Err(ParameterError::Unknown {
+                name: "no_such_parameter".to_string()
+            })
+        );
+    }
+}
@@ -1,13 +1,104 @@
-// Array stride: 16 bytes
+// Array stride: 40 bytes
 struct Cell {
     tree: f32,
     underbrush: f32,
     fire: u32,
-    @size(4) _padding: u32, // pad to 16 bytes
+    // ID of the fire this cell is burning in, or the last fire it burnt in
+    // once the fire has gone out. 0 if it hasn't burnt.
+    fire_id: u32,
+    // Age of the tree in years
+    age: f32,
+    // Health (0 - 1) of the tree
+    health: f32,
+    // Alert level (0 - 1) signalled to the tree by its neighbours
+    alert: f32,
+    // Viable seeds released into the cell, e.g. by serotinous cones
+    seeds: f32,
+    // Kind of fire burning in the cell (one of the FIRE_ constants)
+    fire_kind: u32,
+    // Severity (0 - 1) of the last fire to burn the cell: the fraction of
+    // its fuel that the fire consumed
+    severity: f32,
+}
+
+// Per-fire counters, read back into the fire catalogue
+struct FireRecord {
+    ignition_tick: u32,
+    // Index of the cell where the fire started
+    origin: u32,
+    // Total number of cells that have caught fire
+    area: atomic<u32>,
+    // Number of cells currently burning
+    burning: atomic<u32>,
+    // Last tick on which a cell of this fire burnt out
+    last_active_tick: atomic<u32>,
+    // Index + 1 of the prescribed burn in the treatment log that lit the
+    // fire, or 0 for a wildfire
+    treatment: u32,
+    // Number of cells the prescribed burn lit itself
+    lit: atomic<u32>,
+}
+
+struct FireLedger {
+    // Number of fire IDs handed out so far. IDs start at 1.
+    next_id: atomic<u32>,
+    records: array<FireRecord>,
+}
+
+// Something that happened to a cell, see `GpuEvent`
+struct Event {
+    step: u32,
+    // Index of the cell
+    cell: u32,
+    // One of the EVENT_ constants
+    kind: u32,
+    // ID of the fire involved, or 0
+    fire_id: u32,
+}
+
+struct EventLog {
+    // Number of events appended since the log was last read back, including
+    // those that didn't fit
+    count: atomic<u32>,
+    events: array<Event>,
+}
+
+// Cells to change between steps, see `GpuCellEdit`
+struct CellEdit {
+    // Which fields to set (EDIT_ flags)
+    flags: u32,
+    tree: f32,
+    underbrush: f32,
+    // Index + 1 of the prescribed burn in the treatment log
+    treatment: u32,
+    // ID of the fire the edit lights, filled in by `start_edit_fire`
+    fire_id: u32,
+    // Number of cells to edit
+    count: u32,
+    cells: array<u32>,
+}
+
+// Suppression tallies, see `GpuSuppression`
+struct Suppression {
+    // Total priority of the candidate cells in fixed point, by step parity
+    demand: array<atomic<u32>, 2>,
+    // Cells worked on so far this step
+    used: atomic<u32>,
+    // Burning cells put out since the start of the run
+    extinguished: atomic<u32>,
+    // Cells cleared of fuel for containment lines since the start of the run
+    line_cells: atomic<u32>,
+}
+
+struct Assets {
+    count: u32,
+    // Asset positions in cells in the first two components
+    @align(16) positions: array<vec4<f32>, 32>,
 }
 
 struct Parameters {
-    /// The base chance (0 - 1) that a tree will grow in a given cell each tick
+    /// The base chance (0 - 1) that a sapling will germinate in a given cell
+    /// each tick
     tree_growth_rate: f32,
     /// The factor by which the tree growth rate is reduced with underbrush.
     underbrush_tree_growth_hindrance: f32,
@@ -16,16 +107,21 @@ struct Parameters {
     /// The amount of underbrush created when a tree dies naturally
     tree_death_underbrush: f32,
     /// The chance (0 - 1) that a particular tree dies naturally each tick
+    /// with a constant mortality curve
     tree_death_rate: f32,
-    /// The length a single tree can support a fire for in ticks
+    /// The length a fully grown tree can support a fire for in ticks. Smaller
+    /// trees burn for proportionally less, but at least one tick
     tree_fire_duration: u32,
     /// The length that underbrush can support a fire for in ticks. This is
     /// multiplied by the amount of underbrush
     underbrush_fire_duration: u32,
     /// The base chance (0 - 1) that fire spreads from a particular cell to a
-    /// particular neighbor cell
+    /// particular neighbor cell, in each of the fire's substeps
     fire_spread_rate: f32,
-    /// The multiplier for fire spread rate for trees
+    /// The number of times fire gets to spread each tick
+    fire_substeps: u32,
+    /// The multiplier for fire spread rate for fully grown trees (multiplied by
+    /// the size of the tree)
     tree_flammability: f32,
     /// The multiplier for fire spread rate for underbrush (multiplied by the
     /// amount of underbrush). This is added with the value from tree_flammability
@@ -35,29 +131,252 @@ struct Parameters {
     lightning_frequency: f32,
     /// The tick rate in ticks per second (unused in this shader)
     tick_rate: u32,
+    /// Seed for the random number generator
+    seed: u32,
+    /// How the grid edges behave (one of the BOUNDARY_ constants)
+    boundary_mode: u32,
+    /// The size (0 - 1) of a newly germinated sapling
+    sapling_size: f32,
+    /// The growth rate per tick, whose meaning depends on the growth curve
+    tree_maturation_rate: f32,
+    /// How trees grow towards full size (one of the GROWTH_ constants)
+    growth_curve: u32,
+    /// The length of a tick in years
+    years_per_tick: f32,
+    /// The characteristic lifespan of a tree in years
+    tree_lifespan: f32,
+    /// How the chance of natural death changes with age (one of the
+    /// MORTALITY_ constants)
+    mortality_curve: u32,
+    /// The shape of the mortality curve
+    mortality_shape: f32,
+    /// The health lost each tick when the whole neighbourhood is burning
+    scorch_damage: f32,
+    /// The health lost each tick when the whole neighbourhood is full of
+    /// mature trees
+    crowding_stress: f32,
+    /// The health regained each tick
+    health_recovery: f32,
+    /// How strongly neighbouring trees suppress germination
+    germination_competition: f32,
+    /// How strongly neighbouring trees slow growth
+    growth_competition: f32,
+    /// How strongly neighbouring trees raise the chance of natural death
+    mortality_competition: f32,
+    /// The fraction (0 - 1) by which full alert reduces flammability
+    alert_strength: f32,
+    /// The fraction (0 - 1) of a neighbour's alert passed on each tick
+    alert_range: f32,
+    /// The fraction (0 - 1) of alert lost each tick
+    alert_decay: f32,
+    /// The alert raised each tick by a neighbourhood full of trees
+    alert_stand_signal: f32,
+    /// Radius of the seed dispersal kernel (used on the CPU side)
+    seed_dispersal_radius: u32,
+    /// Distance falloff of the seed dispersal kernel (used on the CPU side)
+    seed_dispersal_falloff: f32,
+    /// The smallest tree size (0 - 1) that produces seeds
+    seed_bearing_size: f32,
+    /// The seed supply that reaches every cell regardless of nearby trees
+    background_seed_rain: f32,
+    /// The fraction of a burnt tree's size released as seeds when its fire
+    /// goes out
+    serotiny: f32,
+    /// The chance (0 - 1) each tick that a cell with a full seed bank
+    /// germinates
+    seed_bank_germination: f32,
+    /// The fraction (0 - 1) of a seed bank that stops being viable each tick
+    seed_bank_decay: f32,
+    /// Mean fuel moisture of the sinusoidal weather profile (used on the CPU
+    /// side)
+    mean_fuel_moisture: f32,
+    /// Amplitude of the sinusoidal weather profile (used on the CPU side)
+    fuel_moisture_amplitude: f32,
+    /// Driest month of the sinusoidal weather profile (used on the CPU side)
+    driest_month: f32,
+    /// The fuel moisture above which fuel doesn't burn
+    moisture_of_extinction: f32,
+    /// Chance of a drought year (used on the CPU side)
+    drought_probability: f32,
+    /// Moisture reduction in drought years (used on the CPU side)
+    drought_severity: f32,
+    /// Fractional change in fuel moisture per year (used on the CPU side)
+    moisture_trend: f32,
+    /// The climate multiplier of the current year's fuel moisture, already
+    /// included in fuel_moisture
+    climate_moisture: f32,
+    /// Fuel moisture of the current tick
+    fuel_moisture: f32,
+    /// The chance (0 - 1) each tick that a burning cell lofts an ember, per
+    /// unit of fuel load above the threshold
+    ember_rate: f32,
+    /// The fuel load (tree + underbrush) a burning cell needs to loft embers
+    ember_intensity_threshold: f32,
+    /// Mean distance in cells that embers travel
+    ember_distance: f32,
+    /// Furthest distance in cells that embers travel
+    ember_max_distance: f32,
+    /// The chance (0 - 1) that an ember ignites a fully flammable cell
+    ember_ignition: f32,
+    /// Direction the wind blows towards, in radians clockwise from north
+    wind_direction: f32,
+    /// How strongly (0 - 1) embers are carried downwind
+    wind_strength: f32,
+    /// The underbrush load beneath a tree at which a surface fire climbs
+    /// into the canopy
+    ladder_fuel_threshold: f32,
+    /// The multiplier for fire spread from crown fires
+    crown_fire_spread: f32,
+    /// Trees smaller than this size (0 - 1) are killed by surface fires
+    surface_fire_kill_size: f32,
+    /// The dry underbrush load that a surface fire consumes completely
+    complete_consumption_load: f32,
+    /// Which cells suppression crews work on first (one of the SUPPRESSION_
+    /// constants)
+    suppression_strategy: u32,
+    /// The number of ticks a fire burns for before suppression starts
+    detection_delay: u32,
+    /// The number of cells suppression crews can work on each tick
+    suppression_capacity: u32,
+    /// The area of a cell in hectares
+    cell_hectares: f32,
+    /// The expected number of storms each tick
+    storm_frequency: f32,
+    /// The mean number of strikes per storm
+    strikes_per_storm: f32,
+    /// Radius of a storm's footprint in cells
+    storm_radius: f32,
+    /// Length of a storm's track in cells
+    storm_track_length: f32,
 }
 
+// A weighted neighbour offset
+struct KernelEntry {
+    offset: vec2<i32>,
+    weight: f32,
+    // Which rows the entry applies to (one of the PARITY_ constants)
+    parity: u32,
+}
+
+struct Kernel {
+    count: u32,
+    @align(16) entries: array<KernelEntry, 128>,
+}
+
+// Growth proportional to size * (1 - size)
+const GROWTH_LOGISTIC: u32 = 0u;
+// A constant amount every tick
+const GROWTH_LINEAR: u32 = 1u;
+
+// The same chance of death at every age
+const MORTALITY_CONSTANT: u32 = 0u;
+// Hazard (k / L) * (age / L)^(k - 1)
+const MORTALITY_WEIBULL: u32 = 1u;
+// Hazard (k / L) * e^(k * (age / L - 1))
+const MORTALITY_GOMPERTZ: u32 = 2u;
+
+// Not burning
+const FIRE_NONE: u32 = 0u;
+// Burning underbrush beneath the canopy
+const FIRE_SURFACE: u32 = 1u;
+// Burning through the canopy, killing the tree
+const FIRE_CROWN: u32 = 2u;
+
+// Ember slot value of a scripted ignition, which starts a new fire
+const IGNITION: u32 = 0xffffffffu;
+// Ember slot value of a storm's lightning strike
+const STRIKE: u32 = 0xfffffffeu;
+
+// Fields set by a cell edit, matching the `EDIT_` constants of gpucompute
+// Plant a tree of the given size
+const EDIT_TREE: u32 = 1u;
+const EDIT_UNDERBRUSH: u32 = 2u;
+// Set every cell with fuel that isn't already burning alight, as one fire
+const EDIT_IGNITE: u32 = 4u;
+
+// Kinds of event, matching `EventKind`
+const EVENT_LIGHTNING: u32 = 0u;
+const EVENT_SPREAD: u32 = 1u;
+const EVENT_SPOT_FIRE: u32 = 2u;
+const EVENT_IGNITION: u32 = 3u;
+const EVENT_BURNT_OUT: u32 = 4u;
+const EVENT_TREE_DIED: u32 = 5u;
+
+// No suppression
+const SUPPRESSION_OFF: u32 = 0u;
+// Every part of the perimeter is equally important
+const SUPPRESSION_PERIMETER: u32 = 1u;
+// The perimeter closest to assets first
+const SUPPRESSION_ASSETS: u32 = 2u;
+// The head of the fire, downwind of where it started, first
+const SUPPRESSION_DOWNWIND: u32 = 3u;
+
+// Fixed-point scale of the suppression demand
+const DEMAND_SCALE: f32 = 256.0;
+
+const PARITY_ALL: u32 = 0u;
+const PARITY_EVEN: u32 = 1u;
+const PARITY_ODD: u32 = 2u;
+
+// Nothing beyond the edge
+const BOUNDARY_CLOSED: u32 = 0u;
+// The grid wraps around into a torus
+const BOUNDARY_PERIODIC: u32 = 1u;
+// The grid is mirrored at each edge
+const BOUNDARY_REFLECTING: u32 = 2u;
+// Everything beyond the edge is permanently burning
+const BOUNDARY_FIRE_SOURCE: u32 = 3u;
+
 @group(0) @binding(0)
 var<storage, read_write> input: array<Cell>;
 // Output of the shader.  
 @group(0) @binding(1)
 var<storage, read_write> output: array<Cell>;
+// Fire ledger shared across steps
+@group(0) @binding(2)
+var<storage, read_write> fire_ledger: FireLedger;
+// Embers in flight: the ID of the fire an ember came from (or 0) for each
+// cell, in two halves that swap between writing and reading each step
+@group(0) @binding(3)
+var<storage, read_write> embers: array<atomic<u32>>;
+// Suppression tallies shared across cells and steps
+@group(0) @binding(4)
+var<storage, read_write> suppression: Suppression;
+// Events appended for readback
+@group(0) @binding(5)
+var<storage, read_write> event_log: EventLog;
+// Cells to change before the next step
+@group(0) @binding(6)
+var<storage, read_write> cell_edit: CellEdit;
 // Simulation parameters input
 @group(1) @binding(0)
 var<uniform> params: Parameters;
 // Size of the grid
 @group(2) @binding(0)
 var <uniform> size: vec2<u32>;
+// Neighbourhood that fire spreads through
+@group(2) @binding(1)
+var<uniform> kernel: Kernel;
+// Neighbourhood that seeds are dispersed through
+@group(2) @binding(2)
+var<uniform> dispersal: Kernel;
+// Assets that suppression protects first
+@group(2) @binding(3)
+var<uniform> assets: Assets;
 // Step count (use only for rng)
 @group(3) @binding(0)
 var <uniform> steps: u32;
+// Which of the fire's substeps is running, see `spread_substep`
+@group(3) @binding(1)
+var <uniform> substep: u32;
 
 fn random(s: u32, count: u32) -> f32 {
-    // 1. Combine all three inputs using bitwise XOR and large primes
+    // 1. Combine all the inputs using bitwise XOR and large primes
     // Each prime helps "spread" the bits of that specific variable
     var state = s;
     state ^= steps * 2654435769u;
     state ^= count * 3405691582u;
+    state ^= params.seed * 2246822519u;
 
     // 2. The PCG "Mixing" Stage
     state = state * 747796405u + 2891336453u;
@@ -68,10 +387,149 @@ fn random(s: u32, count: u32) -> f32 {
     return f32(result) / f32(0xffffffffu);
 }
 
+// Neighbourhood totals, weighted by the kernel
 struct NeighboringCellInfo {
-    trees: u32,
-    fires: u32,
+    trees: f32,
+    fires: f32,
+    // Weighted number of neighbours burning in a crown fire
+    crown_fires: f32,
     underbrush: f32,
+    // The highest fire ID among burning neighbours
+    fire_id: u32,
+    // Total weight of the kernel entries that apply to this cell
+    weight: f32,
+    // The highest alert among neighbouring trees, scaled by kernel weight
+    alert: f32,
+}
+
+fn fire_slot(id: u32) -> u32 {
+    return (id - 1u) % arrayLength(&fire_ledger.records);
+}
+
+// Allocate a new fire starting at the given cell and return its ID
+fn start_fire(global_x: u32) -> u32 {
+    let id = atomicAdd(&fire_ledger.next_id, 1u) + 1u;
+    let slot = fire_slot(id);
+    fire_ledger.records[slot].ignition_tick = steps;
+    fire_ledger.records[slot].origin = global_x;
+    atomicStore(&fire_ledger.records[slot].area, 1u);
+    atomicStore(&fire_ledger.records[slot].burning, 1u);
+    atomicStore(&fire_ledger.records[slot].last_active_tick, steps);
+    fire_ledger.records[slot].treatment = 0u;
+    atomicStore(&fire_ledger.records[slot].lit, 0u);
+    return id;
+}
+
+// Record that a cell has caught fire from an existing fire
+fn spread_fire(id: u32) {
+    let slot = fire_slot(id);
+    atomicAdd(&fire_ledger.records[slot].area, 1u);
+    atomicAdd(&fire_ledger.records[slot].burning, 1u);
+}
+
+// Record that a cell of a fire has burnt out
+fn end_fire(id: u32) {
+    let slot = fire_slot(id);
+    atomicSub(&fire_ledger.records[slot].burning, 1u);
+    atomicMax(&fire_ledger.records[slot].last_active_tick, steps);
+}
+
+// Append an event about a cell to the event log, if there is room
+fn log_event(global_x: u32, kind: u32, fire_id: u32) {
+    let index = atomicAdd(&event_log.count, 1u);
+    if (index < arrayLength(&event_log.events)) {
+        event_log.events[index] = Event(steps, global_x, kind, fire_id);
+    }
+}
+
+// Allocate the fire that a cell edit lights, which starts with no cells
+@compute @workgroup_size(1)
+fn start_edit_fire() {
+    let id = atomicAdd(&fire_ledger.next_id, 1u) + 1u;
+    let slot = fire_slot(id);
+    fire_ledger.records[slot].ignition_tick = steps;
+    fire_ledger.records[slot].origin = cell_edit.cells[0];
+    atomicStore(&fire_ledger.records[slot].area, 0u);
+    atomicStore(&fire_ledger.records[slot].burning, 0u);
+    atomicStore(&fire_ledger.records[slot].last_active_tick, steps);
+    fire_ledger.records[slot].treatment = cell_edit.treatment;
+    atomicStore(&fire_ledger.records[slot].lit, 0u);
+    cell_edit.fire_id = id;
+}
+
+// Change the cells listed in the cell edit in the buffer that the next step
+// reads from
+@compute @workgroup_size(64)
+fn apply_edit(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= cell_edit.count) {
+        return;
+    }
+    let index = cell_edit.cells[global_id.x];
+    if (index >= arrayLength(&input)) {
+        return;
+    }
+    if ((cell_edit.flags & EDIT_TREE) != 0u) {
+        input[index].tree = cell_edit.tree;
+        input[index].age = 0.0;
+        input[index].health = select(0.0, 1.0, cell_edit.tree > 0.0);
+    }
+    if ((cell_edit.flags & EDIT_UNDERBRUSH) != 0u) {
+        input[index].underbrush = cell_edit.underbrush;
+    }
+    let cell = input[index];
+    if ((cell_edit.flags & EDIT_IGNITE) != 0u && cell.fire == 0u && cell.tree + cell.underbrush > 0.0) {
+        input[index].fire = 1u;
+        input[index].fire_kind = FIRE_SURFACE;
+        input[index].fire_id = cell_edit.fire_id;
+        spread_fire(cell_edit.fire_id);
+        atomicAdd(&fire_ledger.records[fire_slot(cell_edit.fire_id)].lit, 1u);
+        log_event(index, EVENT_IGNITION, cell_edit.fire_id);
+    }
+}
+
+// Give fire another chance to spread within the same tick, for fires that
+// cross more than one cell a tick. The main step is the first substep.
+@compute @workgroup_size(64)
+fn spread_substep(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let global_x = global_id.x;
+    if (global_x >= arrayLength(&input)) {
+        return;
+    }
+    output[global_x] = input[global_x];
+    if (input[global_x].fire > 0u) {
+        return;
+    }
+    let neighboring_cell_info = get_neighboring_cell_info(global_x);
+    if (!fire_spreads(global_x, neighboring_cell_info, 10u + substep)) {
+        return;
+    }
+    let duration = surface_burn_duration(global_x);
+    if (duration == 0u) {
+        return;
+    }
+    output[global_x].fire = duration;
+    output[global_x].fire_kind = FIRE_SURFACE;
+    if (neighboring_cell_info.fire_id != 0u) {
+        spread_fire(neighboring_cell_info.fire_id);
+        output[global_x].fire_id = neighboring_cell_info.fire_id;
+        log_event(global_x, EVENT_SPREAD, output[global_x].fire_id);
+    } else {
+        output[global_x].fire_id = start_fire(global_x);
+        log_event(global_x, EVENT_IGNITION, output[global_x].fire_id);
+    }
+}
+
+// Put out every fire in the ledger, for when the grid is resized and the
+// cells that were burning are gone
+@compute @workgroup_size(64)
+fn end_all_fires(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= arrayLength(&fire_ledger.records)) {
+        return;
+    }
+    if (atomicLoad(&fire_ledger.records[global_id.x].burning) > 0u) {
+        atomicStore(&fire_ledger.records[global_id.x].burning, 0u);
+        atomicMax(&fire_ledger.records[global_id.x].last_active_tick, steps);
+    }
 }
 
 // Ideal workgroup size depends on the hardware, the workload, and other factors. However, it should
@@ -106,91 +564,444 @@ fn apply_rules(global_x: u32) {
     // Extinguish burnt-out fires
     if (input[global_x].fire > 0u) {
         output[global_x].fire = input[global_x].fire - 1;
+        if (input[global_x].fire_kind == FIRE_SURFACE && input[global_x].tree > 0.0 && input[global_x].underbrush >= params.ladder_fuel_threshold) {
+            // Ladder fuel carries the surface fire up into the canopy, which
+            // burns for as long as the tree and underbrush last
+            output[global_x].fire_kind = FIRE_CROWN;
+            output[global_x].fire = burn_duration(global_x);
+        }
         if (output[global_x].fire == 0u) {
-            output[global_x].tree = 0.0;
-            output[global_x].underbrush = 0.0;
+            let crown = input[global_x].fire_kind == FIRE_CROWN;
+            // Crown fires consume all of the underbrush, while surface fires
+            // consume more of it the more (and the drier) there is
+            let consumed = select(underbrush_consumption(input[global_x].underbrush), 1.0, crown);
+            output[global_x].underbrush = input[global_x].underbrush * (1.0 - consumed);
+            var fuel_consumed = input[global_x].underbrush * consumed;
+            // Crown fires kill the tree, while surface fires only kill small
+            // trees
+            if (crown || input[global_x].tree < params.surface_fire_kill_size) {
+                fuel_consumed += input[global_x].tree;
+                // Serotinous cones open in the heat and release their seeds
+                output[global_x].seeds = input[global_x].seeds + params.serotiny * input[global_x].tree;
+                output[global_x].tree = 0.0;
+                output[global_x].age = 0.0;
+                output[global_x].health = 0.0;
+                output[global_x].alert = 0.0;
+            }
+            output[global_x].severity = fuel_consumed / max(input[global_x].tree + input[global_x].underbrush, 1e-6);
+            if (input[global_x].fire_id != 0u) {
+                end_fire(input[global_x].fire_id);
+            }
+            log_event(global_x, EVENT_BURNT_OUT, input[global_x].fire_id);
+            // The cell keeps the fire's ID, so the fire can't burn it again
+            output[global_x].fire_kind = FIRE_NONE;
         }
     }
-    // Handle fire spreading
-    var total_flammability: f32 = input[global_x].underbrush * params.underbrush_flammability + input[global_x].tree * params.tree_flammability;
+    // Handle fire spreading. Scripted ignitions and storm lightning strikes
+    // arrive in the ember slot.
+    let landed = land_ember(global_x);
+    let ignited = landed == IGNITION;
+    let storm_strike = landed == STRIKE;
+    let ember_id = select(landed, 0u, ignited || storm_strike);
+    let dryness = fuel_dryness();
+    let total_flammability = flammability(global_x);
     let already_burning = input[global_x].fire > 0u;
-    let catches_fire = random(global_x, 0) < (f32(neighboring_cell_info.fires) / 8.0) * params.fire_spread_rate * total_flammability
-        || random(global_x, 3) < params.lightning_frequency / f32(size.x * size.y);
+    let spreads = fire_spreads(global_x, neighboring_cell_info, 0u);
+    let struck = random(global_x, 3) < params.lightning_frequency * dryness / f32(size.x * size.y) || (storm_strike && random(global_x, 9) < dryness);
+    let spotted = ember_id != 0u && ember_id != input[global_x].fire_id && random(global_x, 7) < params.ember_ignition * total_flammability;
+    let catches_fire = spreads || struck || spotted || ignited;
+    // The fire lightning started, if it started one
+    var lightning_fire_id = 0u;
     if (catches_fire && !already_burning) {
-        output[global_x].fire = burn_duration(global_x);
+        // Every fire starts on the surface
+        let duration = surface_burn_duration(global_x);
+        output[global_x].fire = duration;
+        output[global_x].fire_kind = FIRE_SURFACE;
+        if (duration > 0u) {
+            // Spreading takes precedence so that a strike inside a fire
+            // doesn't split it into two. Fire spreading from an untracked
+            // source (such as a fire source boundary) starts a new fire.
+            if (spreads && neighboring_cell_info.fire_id != 0u) {
+                spread_fire(neighboring_cell_info.fire_id);
+                output[global_x].fire_id = neighboring_cell_info.fire_id;
+                log_event(global_x, EVENT_SPREAD, output[global_x].fire_id);
+            } else if (spotted) {
+                // A spot fire belongs to the fire that lofted the ember
+                spread_fire(ember_id);
+                output[global_x].fire_id = ember_id;
+                log_event(global_x, EVENT_SPOT_FIRE, ember_id);
+            } else {
+                output[global_x].fire_id = start_fire(global_x);
+                if (struck) {
+                    lightning_fire_id = output[global_x].fire_id;
+                } else {
+                    log_event(global_x, EVENT_IGNITION, output[global_x].fire_id);
+                }
+            }
+        }
+    }
+    if (struck) {
+        log_event(global_x, EVENT_LIGHTNING, lightning_fire_id);
     }
+    let density = tree_density(neighboring_cell_info);
+    if (already_burning) {
+        loft_ember(global_x);
+    }
+
     var tree_dies = false;
-    // Handle natural tree death
-    if (input[global_x].tree > 0.0f && random(global_x, 1) < params.tree_death_rate) {
+    // Handle natural tree death. Trees whose health has run out always die.
+    if (input[global_x].tree > 0.0f && (input[global_x].health <= 0.0 || random(global_x, 1) < death_chance(input[global_x], density))) {
         output[global_x].tree = 0.0;
+        output[global_x].age = 0.0;
+        output[global_x].health = 0.0;
+        output[global_x].alert = 0.0;
         tree_dies = true;
+        log_event(global_x, EVENT_TREE_DIED, 0u);
     }
 
     if (!already_burning && !catches_fire) {
-        // Handle tree growth
-        if (input[global_x].tree == 0.0 && random(global_x, 2) < params.tree_growth_rate * (1.0 - params.underbrush_tree_growth_hindrance * input[global_x].underbrush)) {
-            output[global_x].tree = 1.0;
+        // Handle germination in empty cells and growth of existing trees
+        output[global_x].seeds = input[global_x].seeds * (1.0 - params.seed_bank_decay);
+        if (input[global_x].tree == 0.0 && random(global_x, 2) < germination_chance(global_x, density)) {
+            output[global_x].tree = params.sapling_size;
+            output[global_x].seeds = 0.0;
+            output[global_x].age = 0.0;
+            output[global_x].health = 1.0;
+        } else if (!tree_dies && input[global_x].tree > 0.0) {
+            output[global_x].tree = grow_tree(input[global_x].tree, density);
+            output[global_x].age = input[global_x].age + params.years_per_tick;
+            output[global_x].health = update_health(input[global_x].health, neighboring_cell_info);
+            output[global_x].alert = update_alert(input[global_x].alert, neighboring_cell_info);
         }
 
-        // Underbrush generation
-        output[global_x].underbrush = input[global_x].underbrush + (output[global_x].tree + f32(neighboring_cell_info.trees)) * params.tree_underbrush_generation;
+        // Underbrush generation. Neighbouring trees are scaled to the eight
+        // cells of a Moore neighbourhood so that the rate doesn't depend on
+        // the size of the kernel.
+        let neighboring_trees = neighboring_cell_info.trees * 8.0 / max(neighboring_cell_info.weight, 1e-6);
+        output[global_x].underbrush = input[global_x].underbrush + (output[global_x].tree + neighboring_trees) * params.tree_underbrush_generation;
         if (tree_dies) {
             output[global_x].underbrush += params.tree_death_underbrush;
         }
     }
+
+    if (params.suppression_strategy != SUPPRESSION_OFF) {
+        suppress(global_x, neighboring_cell_info);
+    }
+}
+
+// Whether suppression crews have been sent to the fire with the given ID
+fn fire_detected(id: u32) -> bool {
+    return id != 0u && steps - fire_ledger.records[fire_slot(id)].ignition_tick >= params.detection_delay;
+}
+
+// How urgently (0 - 1) suppression crews want to work on a cell of a fire
+fn suppression_priority(global_x: u32, fire_id: u32) -> f32 {
+    let position = vec2<f32>(f32(global_x % size.x), f32(global_x / size.x)) + 0.5;
+    switch params.suppression_strategy {
+        case SUPPRESSION_ASSETS: {
+            if (assets.count == 0u) {
+                return 1.0;
+            }
+            var nearest = distance(position, assets.positions[0].xy);
+            for (var i = 1u; i < assets.count; i++) {
+                nearest = min(nearest, distance(position, assets.positions[i].xy));
+            }
+            return 1.0 / (1.0 + nearest);
+        }
+        case SUPPRESSION_DOWNWIND: {
+            let origin = fire_ledger.records[fire_slot(fire_id)].origin;
+            let from_origin = position - vec2<f32>(f32(origin % size.x), f32(origin / size.x)) - 0.5;
+            if (length(from_origin) == 0.0) {
+                return 1.0;
+            }
+            let downwind = vec2<f32>(sin(params.wind_direction), -cos(params.wind_direction));
+            // From 1 at the head of the fire down to 1 - wind_strength at its
+            // back, but never quite zero
+            let alignment = dot(normalize(from_origin), downwind);
+            return max(1.0 - params.wind_strength * (1.0 - alignment) * 0.5, 0.05);
+        }
+        default: {
+            return 1.0;
+        }
+    }
+}
+
+// Suppression crews put out burning cells on the perimeter of detected fires
+// and clear the fuel from unburnt cells next to them to build containment
+// lines. Capacity is shared out between candidate cells in proportion to
+// their priority, based on the total priority of the last step.
+fn suppress(global_x: u32, info: NeighboringCellInfo) {
+    let cell = output[global_x];
+    let burning = cell.fire > 0u;
+    // A burning cell with a neighbour that isn't burning is on the perimeter
+    let on_perimeter = burning && info.fires < info.weight;
+    let on_line = !burning && cell.tree + cell.underbrush > 0.0;
+    let fire_id = select(info.fire_id, cell.fire_id, burning);
+    if (!(on_perimeter || on_line) || !fire_detected(fire_id)) {
+        return;
+    }
+    let priority = suppression_priority(global_x, fire_id);
+    let parity = steps & 1u;
+    atomicAdd(&suppression.demand[parity], u32(ceil(priority * DEMAND_SCALE)));
+    let last_demand = f32(atomicLoad(&suppression.demand[1u - parity])) / DEMAND_SCALE;
+    if (random(global_x, 8) >= f32(params.suppression_capacity) * priority / max(last_demand, 1e-6)) {
+        return;
+    }
+    if (atomicAdd(&suppression.used, 1u) >= params.suppression_capacity) {
+        return;
+    }
+    if (burning) {
+        output[global_x].fire = 0u;
+        output[global_x].fire_kind = FIRE_NONE;
+        end_fire(cell.fire_id);
+        atomicAdd(&suppression.extinguished, 1u);
+    } else {
+        output[global_x].tree = 0.0;
+        output[global_x].underbrush = 0.0;
+        output[global_x].age = 0.0;
+        output[global_x].health = 0.0;
+        output[global_x].alert = 0.0;
+        atomicAdd(&suppression.line_cells, 1u);
+    }
+}
+
+// Seeds reaching a cell through the dispersal kernel
+struct SeedSupply {
+    // Weighted fraction (0 - 1) of the kernel covered by seed-bearing trees
+    trees: f32,
+    // Weighted average seed bank of the cell and the kernel
+    seeds: f32,
+}
+
+fn get_seed_supply(global_x: u32) -> SeedSupply {
+    let row = i32(global_x / size.x);
+    let col = i32(global_x % size.x);
+    var trees = 0.0;
+    var seeds = input[global_x].seeds;
+    var weight = 0.0;
+    for (var i = 0u; i < dispersal.count; i++) {
+        let entry = dispersal.entries[i];
+        weight += entry.weight;
+        let x = resolve_coordinate(col + entry.offset.x, i32(size.x));
+        let y = resolve_coordinate(row + entry.offset.y, i32(size.y));
+        if (x < 0 || y < 0) {
+            continue;
+        }
+        let neighbor = input[u32(y) * size.x + u32(x)];
+        if (neighbor.tree >= params.seed_bearing_size && neighbor.fire == 0u) {
+            trees += neighbor.tree * entry.weight;
+        }
+        seeds += neighbor.seeds * entry.weight;
+    }
+    return SeedSupply(trees / max(weight, 1e-6), seeds / (1.0 + weight));
+}
+
+// The chance that a sapling germinates in an empty cell this tick. Seeds come
+// from nearby seed-bearing trees, background seed rain and the seed bank.
+fn germination_chance(global_x: u32, density: f32) -> f32 {
+    let supply = get_seed_supply(global_x);
+    let seed_rain = min(params.background_seed_rain + supply.trees, 1.0);
+    let chance = params.tree_growth_rate * seed_rain + params.seed_bank_germination * supply.seeds;
+    return chance * (1.0 - params.underbrush_tree_growth_hindrance * input[global_x].underbrush) * competition(params.germination_competition, density);
+}
+
+// Index of a cell's ember slot in the half of the ember buffer that is
+// written to this step
+fn ember_slot(index: u32, writing: bool) -> u32 {
+    let write_half = steps & 1u;
+    let half = select(1u - write_half, write_half, writing);
+    return half * size.x * size.y + index;
+}
+
+// Take the ember (if any) that landed on this cell during the last step, and
+// clear the slot so that it can be written to in the next step
+fn land_ember(global_x: u32) -> u32 {
+    return atomicExchange(&embers[ember_slot(global_x, false)], 0u);
+}
+
+// Burning cells with enough fuel may loft an ember, which lands at a random
+// distance and bearing, biased downwind
+fn loft_ember(global_x: u32) {
+    let cell = input[global_x];
+    // Surface fires only burn the underbrush
+    let fuel = select(cell.underbrush, cell.tree + cell.underbrush, cell.fire_kind == FIRE_CROWN);
+    let intensity = fuel - params.ember_intensity_threshold;
+    if (cell.fire_id == 0u || intensity <= 0.0 || random(global_x, 4) >= params.ember_rate * intensity * fuel_dryness()) {
+        return;
+    }
+    // Exponentially distributed distance, at least one cell away
+    let distance = clamp(-log(max(random(global_x, 5), 1e-6)) * params.ember_distance, 1.0, params.ember_max_distance);
+    // A bearing within a cone around the wind direction that narrows as the
+    // wind strengthens
+    let spread = (random(global_x, 6) - 0.5) * 6.2831853 * (1.0 - params.wind_strength);
+    let bearing = params.wind_direction + spread;
+    let col = i32(global_x % size.x) + i32(round(sin(bearing) * distance));
+    let row = i32(global_x / size.x) - i32(round(cos(bearing) * distance));
+    let x = resolve_coordinate(col, i32(size.x));
+    let y = resolve_coordinate(row, i32(size.y));
+    if (x < 0 || y < 0) {
+        return;
+    }
+    atomicMax(&embers[ember_slot(u32(y) * size.x + u32(x), true)], cell.fire_id);
+}
+
+// How readily a cell catches fire. Alerted trees resist catching fire, and
+// damp fuel resists everything.
+fn flammability(global_x: u32) -> f32 {
+    return (input[global_x].underbrush * params.underbrush_flammability + input[global_x].tree * params.tree_flammability) * (1.0 - params.alert_strength * input[global_x].alert) * fuel_dryness();
+}
+
+// Whether fire spreads into a cell from its burning neighbours, using the
+// given random number stream. Fuel moisture only counts once, through the
+// cell's flammability.
+fn fire_spreads(global_x: u32, neighboring_cell_info: NeighboringCellInfo, count: u32) -> bool {
+    // Crown fires count for more than surface fires
+    let burning_fraction = (neighboring_cell_info.fires + (params.crown_fire_spread - 1.0) * neighboring_cell_info.crown_fires) / max(neighboring_cell_info.weight, 1e-6);
+    // A fire doesn't burn back into what it left of the cell's fuel
+    let reburn = input[global_x].fire_id != 0u && neighboring_cell_info.fire_id == input[global_x].fire_id;
+    return !reburn && random(global_x, count) < burning_fraction * params.fire_spread_rate * flammability(global_x);
+}
+
+// How dry the fuel is, from 0 (too damp to burn) to 1 (bone dry)
+fn fuel_dryness() -> f32 {
+    return clamp(1.0 - params.fuel_moisture / max(params.moisture_of_extinction, 1e-6), 0.0, 1.0);
+}
+
+// The weighted fraction (0 - 1) of the neighbourhood covered by tree biomass
+fn tree_density(info: NeighboringCellInfo) -> f32 {
+    return info.trees / max(info.weight, 1e-6);
+}
+
+// The factor by which competition from neighbouring trees reduces a rate
+fn competition(strength: f32, density: f32) -> f32 {
+    return max(1.0 - strength * density, 0.0);
+}
+
+// The size of a tree after one tick of growth, slowed by competition
+fn grow_tree(size: f32, density: f32) -> f32 {
+    let rate = params.tree_maturation_rate * competition(params.growth_competition, density);
+    switch params.growth_curve {
+        case GROWTH_LINEAR: {
+            return min(size + rate, 1.0);
+        }
+        default: {
+            return min(size + rate * size * (1.0 - size), 1.0);
+        }
+    }
 }
 
+// The chance that a tree dies naturally this tick. Unhealthy trees and trees
+// competing with dense neighbours are more likely to die.
+fn death_chance(cell: Cell, density: f32) -> f32 {
+    let k = params.mortality_shape;
+    let relative_age = max(cell.age / params.tree_lifespan, 1e-6);
+    var chance: f32;
+    switch params.mortality_curve {
+        case MORTALITY_WEIBULL: {
+            chance = k / params.tree_lifespan * pow(relative_age, k - 1.0) * params.years_per_tick;
+        }
+        case MORTALITY_GOMPERTZ: {
+            chance = k / params.tree_lifespan * exp(k * (relative_age - 1.0)) * params.years_per_tick;
+        }
+        default: {
+            chance = params.tree_death_rate;
+        }
+    }
+    return chance * (1.0 + params.mortality_competition * density) / max(cell.health, 0.05);
+}
+
+// The health of a tree after one tick of recovery, crowding and scorching by
+// neighbouring fires
+fn update_health(health: f32, info: NeighboringCellInfo) -> f32 {
+    let burning = info.fires / max(info.weight, 1e-6);
+    return clamp(health + params.health_recovery - params.crowding_stress * tree_density(info) - params.scorch_damage * burning, 0.0, 1.0);
+}
+
+// The alert of a tree after one tick. Alert is raised by burning neighbours
+// and dense stands, and is passed on from tree to tree, weakening with each
+// step.
+fn update_alert(alert: f32, info: NeighboringCellInfo) -> f32 {
+    let burning = info.fires / max(info.weight, 1e-6);
+    let received = max(alert, params.alert_range * info.alert) * (1.0 - params.alert_decay);
+    return clamp(received + burning + params.alert_stand_signal * tree_density(info), 0.0, 1.0);
+}
+
+// How long a surface fire burns for, which is at least one tick if there is
+// any fuel at all
+fn surface_burn_duration(global_x: u32) -> u32 {
+    if (input[global_x].tree + input[global_x].underbrush <= 0.0) {
+        return 0u;
+    }
+    return max(u32(round(input[global_x].underbrush)) * params.underbrush_fire_duration, 1u);
+}
+
+// The fraction (0 - 1) of a cell's underbrush that a surface fire consumes.
+// Light or damp fuel burns cooler and leaves some of the underbrush behind.
+fn underbrush_consumption(underbrush: f32) -> f32 {
+    return clamp(underbrush * fuel_dryness() / max(params.complete_consumption_load, 1e-6), 0.0, 1.0);
+}
+
+// How long a crown fire burns for. Any tree burns for at least one tick, and
+// larger trees burn for longer
 fn burn_duration(global_x: u32) -> u32 {
-    return u32(round(input[global_x].underbrush)) * params.underbrush_fire_duration + u32(round(input[global_x].tree)) * params.tree_fire_duration;
+    return u32(round(input[global_x].underbrush)) * params.underbrush_fire_duration + u32(ceil(input[global_x].tree * f32(params.tree_fire_duration)));
+}
+
+fn add_neighbor(info: ptr<function, NeighboringCellInfo>, index: u32, weight: f32) {
+    (*info).trees += input[index].tree * weight;
+    (*info).fires += f32(min(1u, input[index].fire)) * weight;
+    (*info).crown_fires += f32(input[index].fire > 0u && input[index].fire_kind == FIRE_CROWN) * weight;
+    (*info).underbrush += input[index].underbrush * weight;
+    (*info).fire_id = max((*info).fire_id, select(0u, input[index].fire_id, input[index].fire > 0u));
+    (*info).alert = max((*info).alert, input[index].alert * min(weight, 1.0));
+}
+
+// Map a coordinate that may lie beyond the edge of the grid back onto it,
+// according to the boundary mode. Returns -1 if there is no cell there.
+fn resolve_coordinate(c: i32, extent: i32) -> i32 {
+    if (c >= 0 && c < extent) {
+        return c;
+    }
+    switch params.boundary_mode {
+        case BOUNDARY_PERIODIC: {
+            return ((c % extent) + extent) % extent;
+        }
+        case BOUNDARY_REFLECTING: {
+            if (c < 0) {
+                return min(-c - 1, extent - 1);
+            }
+            return max(2 * extent - c - 1, 0);
+        }
+        default: {
+            return -1;
+        }
+    }
+}
+
+fn visit_neighbor(info: ptr<function, NeighboringCellInfo>, col: i32, row: i32, weight: f32) {
+    let x = resolve_coordinate(col, i32(size.x));
+    let y = resolve_coordinate(row, i32(size.y));
+    if (x < 0 || y < 0) {
+        if (params.boundary_mode == BOUNDARY_FIRE_SOURCE) {
+            (*info).fires += weight;
+        }
+        return;
+    }
+    add_neighbor(info, u32(y) * size.x + u32(x), weight);
 }
 
 fn get_neighboring_cell_info(global_x: u32) -> NeighboringCellInfo {
-    var total_trees: u32 = 0;
-    var total_fires: u32 = 0;
-    var total_underbrush: f32 = 0;
-    let row = global_x / size.x;
-    let col = global_x % size.x;
-    let width = size.x;
-    let height = size.y;
-    if (col > 0) {
-        if (row > 0) {
-            total_trees += u32(ceil(input[global_x - width - 1].tree));
-            total_fires += min(1, input[global_x - width - 1].fire);
-            total_underbrush += input[global_x - width - 1].underbrush;
-        }
-        if (row < size.y - 1) {
-            total_trees += u32(ceil(input[global_x + width - 1].tree));
-            total_fires += min(1, input[global_x + width - 1].fire);
-            total_underbrush += input[global_x + width - 1].underbrush;
-        }
-        total_trees += u32(ceil(input[global_x - 1].tree));
-        total_fires += min(1, input[global_x - 1].fire);
-        total_underbrush += input[global_x - 1].underbrush;
-    }
-    if (col < size.x - 1) {
-        if (row > 0) {
-            total_trees += u32(ceil(input[global_x - width + 1].tree));
-            total_fires += min(1, input[global_x - width + 1].fire);
-            total_underbrush += input[global_x - width + 1].underbrush;
-        }
-        if (row < size.y) {
-            total_trees += u32(ceil(input[global_x + width + 1].tree));
-            total_fires += min(1, input[global_x + width + 1].fire);
-            total_underbrush += input[global_x + width + 1].underbrush;
-        }
-        total_trees += u32(ceil(input[global_x + 1].tree));
-        total_fires += min(1, input[global_x + 1].fire);
-        total_underbrush += input[global_x + 1].underbrush;
-    }
-    if (row > 0) {
-        total_trees += u32(ceil(input[global_x - width].tree));
-        total_fires += min(1, input[global_x - width].fire);
-        total_underbrush += input[global_x - width].underbrush;
-    }
-    if (row < size.y - 1) {
-        total_trees += u32(ceil(input[global_x + width].tree));
-        total_fires += min(1, input[global_x + width].fire);
-        total_underbrush += input[global_x + width].underbrush;
-    }
-    return NeighboringCellInfo(total_trees, total_fires, total_underbrush);
+    var info = NeighboringCellInfo(0.0, 0.0, 0.0, 0.0, 0u, 0.0, 0.0);
+    let row = i32(global_x / size.x);
+    let col = i32(global_x % size.x);
+    let parity = select(PARITY_EVEN, PARITY_ODD, (row & 1) == 1);
+    for (var i = 0u; i < kernel.count; i++) {
+        let entry = kernel.entries[i];
+        if (entry.parity != PARITY_ALL && entry.parity != parity) {
+            continue;
+        }
+        info.weight += entry.weight;
+        visit_neighbor(&info, col + entry.offset.x, row + entry.offset.y, entry.weight);
+    }
+    return info;
 }
@@ -0,0 +1,170 @@
+//! Summary statistics computed from simulation frames
+
+use wasm_bindgen::prelude::*;
+
+use crate::sim::{BurnState, FireKind, SimulationFrame};
+
+/// Whole-grid summary of a single frame
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameSummary {
+    /// The simulation step the frame was taken on
+    pub step: u32,
+    /// The fraction (0 - 1) of cells that contain a tree of any size
+    pub tree_cover: f32,
+    /// The average tree size per cell, counting empty cells as 0
+    pub mean_biomass: f32,
+    /// The average amount of underbrush per cell
+    pub mean_underbrush: f32,
+    /// The fraction (0 - 1) of cells that are burning
+    pub burning_fraction: f32,
+    /// The fraction (0 - 1) of cells that are burning in a crown fire
+    pub crown_fraction: f32,
+    /// The fraction (0 - 1) of cells whose last fire was of high severity
+    pub high_severity_fraction: f32,
+    /// The fuel moisture on the frame's step
+    pub fuel_moisture: f32,
+    /// The climate multiplier of the fuel moisture in the frame's year
+    pub climate_moisture: f32,
+    /// Whether the frame's year is a drought year
+    pub drought: bool,
+}
+
+impl From<&SimulationFrame> for FrameSummary {
+    fn from(frame: &SimulationFrame) -> Self {
+        let cells = frame.grid.len().max(1) as f32;
+        let (trees, biomass, underbrush, burning, crown, high_severity) = frame.grid.iter().fold(
+            (0usize, 0.0f32, 0.0f32, 0usize, 0usize, 0usize),
+            |(trees, biomass, underbrush, burning, crown, high_severity), cell| {
+                (
+                    trees + (cell.tree > 0.0) as usize,
+                    biomass + cell.tree,
+                    underbrush + cell.underbrush,
+                    burning + matches!(cell.burning, BurnState::Burning { .. }) as usize,
+                    crown
+                        + matches!(
+                            cell.burning,
+                            BurnState::Burning {
+                                kind: FireKind::Crown,
+                                ..
+                            }
+                        ) as usize,
+                    high_severity
+                        + (SeverityClass::of(cell.severity) == SeverityClass::High) as usize,
+                )
+            },
+        );
+        Self {
+            tree_cover: trees as f32 / cells,
+            mean_biomass: biomass / cells,
+            mean_underbrush: underbrush / cells,
+            burning_fraction: burning as f32 / cells,
+            crown_fraction: crown as f32 / cells,
+            high_severity_fraction: high_severity as f32 / cells,
+            ..Default::default()
+        }
+    }
+}
+
+/// Burn severity classes, with the same boundaries as the dNBR classes of
+/// remote-sensed burn severity maps, applied to the fraction of fuel consumed
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum SeverityClass {
+    Unburned = 0,
+    Low = 1,
+    ModerateLow = 2,
+    ModerateHigh = 3,
+    High = 4,
+}
+
+impl SeverityClass {
+    /// The class of a cell's burn severity (0 - 1)
+    pub fn of(severity: f32) -> Self {
+        match severity {
+            s if s >= 0.66 => SeverityClass::High,
+            s if s >= 0.44 => SeverityClass::ModerateHigh,
+            s if s >= 0.27 => SeverityClass::ModerateLow,
+            s if s >= 0.1 => SeverityClass::Low,
+            _ => SeverityClass::Unburned,
+        }
+    }
+}
+
+/// Summaries of the frames of a run, in step order
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct TimeSeries {
+    samples: Vec<FrameSummary>,
+}
+
+impl TimeSeries {
+    pub fn push(&mut self, sample: FrameSummary) {
+        self.samples.push(sample);
+    }
+
+    pub fn samples(&self) -> &[FrameSummary] {
+        &self.samples
+    }
+}
+
+#[wasm_bindgen]
+impl TimeSeries {
+    /// Number of samples
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether the series has no samples
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Get a sample by index
+    #[wasm_bindgen]
+    pub fn get(&self, index: usize) -> Option<FrameSummary> {
+        self.samples.get(index).copied()
+    }
+
+    /// Format the series as CSV with a header row
+    #[wasm_bindgen]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "step,tree_cover,mean_biomass,mean_underbrush,burning_fraction,crown_fraction,high_severity_fraction,fuel_moisture,climate_moisture,drought\n",
+        );
+        for s in &self.samples {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                s.step,
+                s.tree_cover,
+                s.mean_biomass,
+                s.mean_underbrush,
+                s.burning_fraction,
+                s.crown_fraction,
+                s.high_severity_fraction,
+                s.fuel_moisture,
+                s.climate_moisture,
+                s.drought as u32
+            ));
+        }
+        csv
+    }
+}
+
+/// Mean and (population) standard deviation of a series of values
+pub fn mean_and_std(values: impl IntoIterator<Item = f32>) -> (f32, f32) {
+    let (n, sum, sum_sq) = values
+        .into_iter()
+        .fold((0usize, 0.0f64, 0.0f64), |(n, sum, sum_sq), v| {
+            (n + 1, sum + v as f64, sum_sq + (v as f64) * (v as f64))
+        });
+    if n == 0 {
+        return (f32::NAN, f32::NAN);
+    }
+    let mean = sum / n as f64;
+    let variance = (sum_sq / n as f64 - mean * mean).max(0.0);
+    (mean as f32, variance.sqrt() as f32)
+}
@@ -0,0 +1,289 @@
+//! Thunderstorms that drop clusters of lightning strikes
+//!
+//! Besides the independent strikes of `lightning_strikes_per_year_per_acre`,
+//! lightning can arrive in storms. Storms arrive at random at
+//! `storms_per_year`, each during a single tick. A storm travels in a straight
+//! track through a random point of the forest, heading roughly downwind, and
+//! drops its strikes at random within `storm_radius_m` of the track. Many
+//! fires can start on the same tick, which is what stresses suppression.
+//!
+//! Storms are drawn from the simulation seed, so a run is reproducible. Recent
+//! storm tracks are kept for the renderer to draw.
+
+use std::collections::VecDeque;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::sim::SimulationParameters;
+
+/// The most storm tracks the renderer draws at once
+pub const MAX_STORM_TRACKS: usize = 16;
+
+/// Number of steps a storm track stays on screen, fading out
+pub const STORM_TRACK_FADE_STEPS: u32 = 24;
+
+/// Most storms drawn on a single step
+const MAX_STORMS_PER_STEP: u32 = 8;
+
+/// Most strikes drawn for a single storm
+const MAX_STRIKES_PER_STORM: u32 = 1000;
+
+/// A storm that passed over the forest
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Storm {
+    /// The step the storm passed on
+    pub step: u32,
+    /// Where the track starts and ends, in cells
+    pub start: [f32; 2],
+    pub end: [f32; 2],
+    /// Radius of the storm's footprint in cells
+    pub radius: f32,
+    /// The number of strikes that landed in the forest
+    pub strikes: u32,
+}
+
+/// A storm track as laid out in the render uniform
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct GpuStormTrack {
+    /// Start and end of the track in cells
+    pub path: [f32; 4],
+    pub radius: f32,
+    /// How visible (0 - 1) the track still is
+    pub fade: f32,
+    pub _pad: [f32; 2],
+}
+
+/// Storm tracks to draw, as laid out in the render uniform
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct GpuStormTracks {
+    pub count: u32,
+    pub _pad: [u32; 3],
+    pub tracks: [GpuStormTrack; MAX_STORM_TRACKS],
+}
+
+impl Default for GpuStormTracks {
+    fn default() -> Self {
+        Zeroable::zeroed()
+    }
+}
+
+/// Draws the storms of each step and remembers the recent ones
+#[derive(Default)]
+pub struct StormProcess {
+    recent: VecDeque<Storm>,
+}
+
+impl StormProcess {
+    /// Forget every storm, e.g. when a new frame is loaded
+    pub fn reset(&mut self) {
+        self.recent.clear();
+    }
+
+    /// The storms that have passed recently, oldest first
+    pub fn recent(&self) -> impl Iterator<Item = &Storm> {
+        self.recent.iter()
+    }
+
+    /// Draw the storms that pass on the given step, returning the indices of
+    /// the cells they strike
+    pub fn strikes(
+        &mut self,
+        step: u32,
+        parameters: &SimulationParameters,
+        width: usize,
+        height: usize,
+    ) -> Vec<usize> {
+        self.recent
+            .retain(|storm| step.saturating_sub(storm.step) < STORM_TRACK_FADE_STEPS);
+        let mut rng = StepRandom::new(parameters.seed, step);
+        let storms = rng.poisson(parameters.storm_frequency, MAX_STORMS_PER_STEP);
+        let mut cells = Vec::new();
+        for _ in 0..storms {
+            // The track runs through a random point of the forest, within a
+            // quarter turn of downwind
+            let centre = [rng.uniform() * width as f32, rng.uniform() * height as f32];
+            let heading =
+                parameters.wind_direction + (rng.uniform() - 0.5) * std::f32::consts::FRAC_PI_2;
+            let half = [
+                heading.sin() * parameters.storm_track_length * 0.5,
+                -heading.cos() * parameters.storm_track_length * 0.5,
+            ];
+            let start = [centre[0] - half[0], centre[1] - half[1]];
+            let end = [centre[0] + half[0], centre[1] + half[1]];
+
+            let mut landed = 0;
+            for _ in 0..rng.poisson(parameters.strikes_per_storm, MAX_STRIKES_PER_STORM) {
+                // Uniformly along the track and within the footprint
+                let along = rng.uniform();
+                let distance = rng.uniform().sqrt() * parameters.storm_radius;
+                let bearing = rng.uniform() * std::f32::consts::TAU;
+                let x = start[0] + (end[0] - start[0]) * along + distance * bearing.cos();
+                let y = start[1] + (end[1] - start[1]) * along + distance * bearing.sin();
+                if (0.0..width as f32).contains(&x) && (0.0..height as f32).contains(&y) {
+                    cells.push(y as usize * width + x as usize);
+                    landed += 1;
+                }
+            }
+            self.recent.push_back(Storm {
+                step,
+                start,
+                end,
+                radius: parameters.storm_radius,
+                strikes: landed,
+            });
+        }
+        while self.recent.len() > MAX_STORM_TRACKS {
+            self.recent.pop_front();
+        }
+        cells
+    }
+
+    /// The recent storm tracks to draw after the given step
+    pub fn tracks(&self, step: u32) -> GpuStormTracks {
+        let mut tracks = GpuStormTracks::default();
+        for (track, storm) in tracks.tracks.iter_mut().zip(&self.recent) {
+            let age = step.saturating_sub(storm.step + 1);
+            *track = GpuStormTrack {
+                path: [storm.start[0], storm.start[1], storm.end[0], storm.end[1]],
+                radius: storm.radius,
+                fade: 1.0 - age as f32 / STORM_TRACK_FADE_STEPS as f32,
+                _pad: [0.0; 2],
+            };
+        }
+        tracks.count = self.recent.len().min(MAX_STORM_TRACKS) as u32;
+        tracks
+    }
+}
+
+/// A stream of uniform random numbers for one step of a seeded run
+struct StepRandom {
+    state: u64,
+}
+
+impl StepRandom {
+    fn new(seed: u32, step: u32) -> Self {
+        Self {
+            state: ((seed as u64) << 32 | step as u64) ^ 0x5851f42d4c957f2d,
+        }
+    }
+
+    /// A uniform random number in `[0, 1)`
+    fn uniform(&mut self) -> f32 {
+        // SplitMix64
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A Poisson distributed count with the given mean, but no more than
+    /// `max`
+    fn poisson(&mut self, mean: f32, max: u32) -> u32 {
+        // Count arrivals of unit rate until the mean is used up, which works
+        // for any mean without underflowing
+        let mut count = 0;
+        let mut elapsed = 0.0;
+        while count < max {
+            elapsed -= (1.0 - self.uniform() as f64).ln();
+            if elapsed > mean as f64 {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stormy(seed: u32) -> SimulationParameters {
+        SimulationParameters {
+            seed,
+            storm_frequency: 2.0,
+            strikes_per_storm: 20.0,
+            storm_radius: 3.0,
+            storm_track_length: 10.0,
+            ..Zeroable::zeroed()
+        }
+    }
+
+    #[test]
+    fn uniform_is_reproducible_and_in_range() {
+        let draw = |seed, step| {
+            let mut rng = StepRandom::new(seed, step);
+            (0..1000).map(|_| rng.uniform()).collect::<Vec<_>>()
+        };
+        let values = draw(1, 2);
+        assert_eq!(values, draw(1, 2));
+        assert_ne!(values, draw(2, 2));
+        assert_ne!(values, draw(1, 3));
+        assert!(values.iter().all(|&v| (0.0..1.0).contains(&v)));
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        assert!((mean - 0.5).abs() < 0.05, "mean {mean}");
+    }
+
+    #[test]
+    fn poisson_has_the_given_mean() {
+        let mut rng = StepRandom::new(7, 0);
+        assert_eq!(rng.poisson(0.0, 10), 0);
+        assert!((0..100).all(|_| rng.poisson(1000.0, 5) == 5));
+
+        for mean in [0.05, 1.0, 30.0] {
+            let draws = 20_000;
+            let total: u32 = (0..draws).map(|_| rng.poisson(mean, u32::MAX)).sum();
+            let sample_mean = total as f32 / draws as f32;
+            assert!(
+                (sample_mean - mean).abs() < 0.05 * mean.max(1.0),
+                "mean {mean} sampled as {sample_mean}"
+            );
+        }
+    }
+
+    #[test]
+    fn storms_strike_inside_the_forest_reproducibly() {
+        let (width, height) = (20, 15);
+        let run = |seed| {
+            let mut storms = StormProcess::default();
+            let cells: Vec<_> = (0..50)
+                .flat_map(|step| storms.strikes(step, &stormy(seed), width, height))
+                .collect();
+            (cells, storms.recent().copied().collect::<Vec<_>>())
+        };
+        let (cells, recent) = run(3);
+        assert_eq!((cells.clone(), recent.clone()), run(3));
+        assert!(!cells.is_empty());
+        assert!(cells.iter().all(|&cell| cell < width * height));
+        assert!(
+            recent
+                .iter()
+                .map(|storm| storm.strikes as usize)
+                .sum::<usize>()
+                <= cells.len()
+        );
+
+        // Old tracks fade out
+        assert!(
+            recent
+                .iter()
+                .all(|storm| 49 - storm.step < STORM_TRACK_FADE_STEPS)
+        );
+        assert!(recent.len() <= MAX_STORM_TRACKS);
+    }
+
+    #[test]
+    fn no_storms_without_a_storm_frequency() {
+        let mut storms = StormProcess::default();
+        let parameters = SimulationParameters {
+            storm_frequency: 0.0,
+            ..stormy(1)
+        };
+        assert!((0..100).all(|step| storms.strikes(step, &parameters, 10, 10).is_empty()));
+        assert_eq!(storms.tracks(100).count, 0);
+    }
+}
@@ -0,0 +1,62 @@
+//! Fire suppression
+//!
+//! Once a fire has burnt for `detection_delay` ticks, suppression crews start
+//! work on it. Each tick they either put out burning cells on the fire's
+//! perimeter or clear the fuel from unburnt cells next to it to build a
+//! containment line. They can work on at most `suppression_capacity` cells per
+//! tick. The `suppression_strategy` parameter decides which cells are worked
+//! on first. Crews spread their effort over the candidate cells in proportion
+//! to each cell's priority.
+//!
+//! All of this happens on the GPU. The effort spent is counted there and read
+//! back as [`SuppressionEffort`].
+
+use bytemuck::{Pod, Zeroable};
+use wasm_bindgen::prelude::*;
+
+/// The suppression counters as laid out in the GPU buffer
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct GpuSuppression {
+    /// Total priority of the candidate cells in fixed point, indexed by step
+    /// parity
+    pub demand: [u32; 2],
+    /// Cells worked on so far in the current step
+    pub used: u32,
+    /// Burning cells put out since the start of the run
+    pub extinguished: u32,
+    /// Cells cleared of fuel for containment lines since the start of the run
+    pub line_cells: u32,
+}
+
+impl GpuSuppression {
+    /// Byte offset of the demand of the given step's parity
+    pub(crate) fn demand_offset(step: u32) -> wgpu::BufferAddress {
+        (std::mem::offset_of!(GpuSuppression, demand) + (step as usize & 1) * 4)
+            as wgpu::BufferAddress
+    }
+
+    /// Byte offset of the count of cells worked on in the current step
+    pub(crate) fn used_offset() -> wgpu::BufferAddress {
+        std::mem::offset_of!(GpuSuppression, used) as wgpu::BufferAddress
+    }
+}
+
+/// Suppression effort spent since the start of a run
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SuppressionEffort {
+    /// Burning cells put out
+    pub cells_extinguished: u32,
+    /// Cells cleared of fuel for containment lines
+    pub line_cells: u32,
+}
+
+impl From<&GpuSuppression> for SuppressionEffort {
+    fn from(counters: &GpuSuppression) -> Self {
+        Self {
+            cells_extinguished: counters.extinguished,
+            line_cells: counters.line_cells,
+        }
+    }
+}
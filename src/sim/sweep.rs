@@ -0,0 +1,576 @@
+//! Parameter sweeps and Monte Carlo ensembles
+//!
+//! A sweep runs every combination of a grid of parameter values once per seed,
+//! headlessly on the GPU, and collects summary statistics of each run into a
+//! single [`SweepTable`]. Several runs share the device at once.
+
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    scenario::{Landscape, Scenario},
+    sim::{
+        Climate, ConfigurableParameters, Management, Neighbourhood, ParameterSpec,
+        SimulationParameters, Timeline, WeatherSeries,
+        gpucompute::{GpuResources, SimulationPipeline},
+        stats::{FrameSummary, TimeSeries, mean_and_std},
+    },
+};
+
+/// Summary columns that follow the swept parameter columns in a [`SweepTable`]
+pub const SUMMARY_COLUMNS: &[&str] = &[
+    "seed",
+    "final_tree_cover",
+    "mean_tree_cover",
+    "std_tree_cover",
+    "mean_underbrush",
+    "drought_years",
+    "fires",
+    "mean_fire_hectares",
+    "max_fire_hectares",
+    "treated_hectares",
+    "escapes",
+];
+
+/// A single swept parameter and the values it takes
+#[derive(Clone, Debug)]
+pub struct SweepAxis {
+    /// Field name of the parameter in `ConfigurableParameters`
+    pub parameter: String,
+    pub values: Vec<f32>,
+}
+
+/// Description of a parameter sweep
+#[derive(Clone)]
+pub struct SweepSpec {
+    /// Parameters shared by every run. Swept parameters and the seed are
+    /// overwritten per run.
+    pub base: ConfigurableParameters,
+    /// Parameters to sweep. Every combination of values is run.
+    pub axes: Vec<SweepAxis>,
+    /// The initial landscape of every run, generated with the run's seed
+    pub landscape: Landscape,
+    /// Where the fuel moisture of each tick comes from
+    pub weather: WeatherSeries,
+    /// Scripted drought years
+    pub climate: Climate,
+    /// Scheduled fuel treatments
+    pub management: Management,
+    /// Scripted events, replayed in every run
+    pub timeline: Timeline,
+    /// Seeds to run each combination with
+    pub seeds: Vec<u32>,
+    /// Simulated years per run
+    pub years: f32,
+    /// Simulated years between samples of the grid
+    pub sample_interval_years: f32,
+    /// Samples taken before this many simulated years are left out of the
+    /// averages, so the forest can reach equilibrium first
+    pub burn_in_years: f32,
+    /// The number of runs that share the device at once
+    pub concurrency: usize,
+}
+
+impl SweepSpec {
+    pub fn new(base: ConfigurableParameters) -> Self {
+        Self {
+            base,
+            axes: Vec::new(),
+            landscape: Landscape::Empty,
+            weather: WeatherSeries::default(),
+            climate: Climate::default(),
+            management: Management::default(),
+            timeline: Timeline::default(),
+            seeds: vec![0],
+            years: 100.0,
+            sample_interval_years: 1.0,
+            burn_in_years: 0.0,
+            concurrency: 4,
+        }
+    }
+
+    /// Expand the sweep into one parameter set per run, along with the swept
+    /// values of that run
+    fn runs(&self) -> Result<Vec<(Vec<f32>, ConfigurableParameters)>, anyhow::Error> {
+        let mut combinations = vec![(Vec::new(), self.base.clone())];
+        for axis in &self.axes {
+            let mut next = Vec::with_capacity(combinations.len() * axis.values.len());
+            for (values, params) in &combinations {
+                for &value in &axis.values {
+                    let mut params = params.clone();
+                    params.set_parameter(&axis.parameter, value as f64)?;
+                    let mut values = values.clone();
+                    values.push(value);
+                    next.push((values, params));
+                }
+            }
+            combinations = next;
+        }
+
+        let mut runs = Vec::with_capacity(combinations.len() * self.seeds.len());
+        for (values, params) in combinations {
+            for &seed in &self.seeds {
+                let mut params = params.clone();
+                params.seed = seed;
+                runs.push((values.clone(), params));
+            }
+        }
+        Ok(runs)
+    }
+}
+
+impl From<&Scenario> for SweepSpec {
+    /// A single run of the scenario, sampled once per simulated year
+    fn from(scenario: &Scenario) -> Self {
+        let mut spec = Self::new(scenario.parameters());
+        spec.landscape = scenario.landscape.clone();
+        spec.weather = scenario.weather.clone();
+        spec.climate = scenario.climate.clone();
+        spec.management = scenario.management.clone();
+        spec.timeline = scenario.timeline.clone();
+        spec.seeds = vec![scenario.seed];
+        if let Some(years) = scenario.years {
+            spec.years = years;
+        }
+        spec
+    }
+}
+
+/// A single headless run within a sweep
+struct SweepRun {
+    values: Vec<f32>,
+    seed: u32,
+    /// The run's parameters, as changed by the timeline so far
+    config: ConfigurableParameters,
+    pipeline: SimulationPipeline,
+    total_steps: u32,
+    sample_interval: u32,
+    burn_in_steps: u32,
+    samples: Vec<FrameSummary>,
+    last_sample: FrameSummary,
+    /// Every sample, including those taken during the burn-in
+    series: TimeSeries,
+    /// Number of drought years in the run
+    drought_years: usize,
+}
+
+impl SweepRun {
+    fn new(
+        resources: &GpuResources,
+        spec: &SweepSpec,
+        values: Vec<f32>,
+        config: &ConfigurableParameters,
+    ) -> Result<Self, anyhow::Error> {
+        let parameters = SimulationParameters::from(config);
+        let ticks_per_year = config.ticks_per_month * 12.0;
+        let start =
+            spec.landscape
+                .generate(config.forest_width, config.forest_height, config.seed)?;
+        let mut pipeline = SimulationPipeline::new(
+            Arc::clone(&resources.device),
+            Arc::clone(&resources.queue),
+            &start,
+            parameters,
+            Neighbourhood::from(config),
+        )?;
+        pipeline.set_weather(spec.weather.clone());
+        pipeline.set_climate(spec.climate.clone());
+        pipeline.set_management(spec.management.clone());
+        pipeline.set_timeline(spec.timeline.clone());
+        let drought_years = (0..spec.years.ceil() as u32)
+            .filter(|&year| spec.climate.is_drought(year, &parameters))
+            .count();
+        Ok(Self {
+            values,
+            seed: config.seed,
+            config: config.clone(),
+            pipeline,
+            total_steps: (spec.years * ticks_per_year).round() as u32,
+            sample_interval: ((spec.sample_interval_years * ticks_per_year).round() as u32).max(1),
+            burn_in_steps: (spec.burn_in_years * ticks_per_year).round() as u32,
+            samples: Vec::new(),
+            last_sample: FrameSummary::default(),
+            series: TimeSeries::default(),
+            drought_years,
+        })
+    }
+
+    fn finished(&self) -> bool {
+        self.pipeline.steps() >= self.total_steps
+    }
+
+    /// Submit steps up to the next sample point. Returns false if the run was
+    /// already finished.
+    fn advance(&mut self) -> bool {
+        if self.finished() {
+            return false;
+        }
+        let steps = self.pipeline.steps();
+        let next_sample =
+            ((steps / self.sample_interval + 1) * self.sample_interval).min(self.total_steps);
+        for _ in steps..next_sample {
+            self.pipeline.advance_timeline(&mut self.config);
+            self.pipeline
+                .set_neighbourhood(Neighbourhood::from(&self.config));
+            self.pipeline.step(SimulationParameters::from(&self.config));
+        }
+        true
+    }
+
+    async fn sample(&mut self) -> Result<(), anyhow::Error> {
+        self.last_sample = self.pipeline.read_summary().await?;
+        self.series.push(self.last_sample);
+        if self.pipeline.steps() >= self.burn_in_steps {
+            self.samples.push(self.last_sample);
+        }
+        Ok(())
+    }
+
+    fn row(&self) -> Vec<f64> {
+        let (mean_cover, std_cover) = mean_and_std(self.samples.iter().map(|s| s.tree_cover));
+        let (mean_underbrush, _) = mean_and_std(self.samples.iter().map(|s| s.mean_underbrush));
+        let catalogue = self
+            .pipeline
+            .fire_catalogue()
+            .lock()
+            .expect("failed to lock fire catalogue")
+            .clone();
+        if let Err(e) = catalogue.check_areas(
+            &self
+                .pipeline
+                .event_log()
+                .lock()
+                .expect("failed to lock event log"),
+        ) {
+            log::warn!("sweep run with seed {}: {e}", self.seed);
+        }
+        let fires = catalogue.hectares();
+        let treatments = self
+            .pipeline
+            .treatment_log()
+            .lock()
+            .expect("failed to lock treatment log")
+            .total(&catalogue);
+        let (mean_area, _) = mean_and_std(fires.iter().copied());
+        let max_area = fires.iter().copied().fold(0.0, f32::max);
+
+        let mut row: Vec<f64> = self.values.iter().map(|&v| v as f64).collect();
+        row.extend([
+            self.seed as f64,
+            self.last_sample.tree_cover as f64,
+            mean_cover as f64,
+            std_cover as f64,
+            mean_underbrush as f64,
+            self.drought_years as f64,
+            fires.len() as f64,
+            mean_area as f64,
+            max_area as f64,
+            treatments.hectares as f64,
+            treatments.escapes as f64,
+        ]);
+        row
+    }
+}
+
+/// Run a parameter sweep on the given device
+pub async fn run_sweep(
+    resources: &GpuResources,
+    spec: &SweepSpec,
+) -> Result<SweepTable, anyhow::Error> {
+    let runs = spec.runs()?;
+    let mut columns: Vec<String> = spec.axes.iter().map(|a| a.parameter.clone()).collect();
+    columns.extend(SUMMARY_COLUMNS.iter().map(|c| c.to_string()));
+    let mut table = SweepTable {
+        columns,
+        rows: Vec::with_capacity(runs.len()),
+        series: Vec::with_capacity(runs.len()),
+    };
+
+    for (batch_index, batch) in runs.chunks(spec.concurrency.max(1)).enumerate() {
+        log::info!(
+            "Sweep batch {} ({} of {} runs done)",
+            batch_index + 1,
+            table.rows.len(),
+            runs.len()
+        );
+        let mut batch_runs = batch
+            .iter()
+            .map(|(values, config)| SweepRun::new(resources, spec, values.clone(), config))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Submit work for every run before waiting on any of them, so the
+        // runs in a batch execute on the device together
+        loop {
+            let mut advanced = Vec::new();
+            for (i, run) in batch_runs.iter_mut().enumerate() {
+                if run.advance() {
+                    advanced.push(i);
+                }
+            }
+            if advanced.is_empty() {
+                break;
+            }
+            for i in advanced {
+                batch_runs[i].sample().await?;
+            }
+        }
+        // The ledger and events of the last few steps haven't been read back
+        for run in &batch_runs {
+            run.pipeline.flush().await?;
+        }
+        table.rows.extend(batch_runs.iter().map(SweepRun::row));
+        table
+            .series
+            .extend(batch_runs.into_iter().map(|run| run.series));
+    }
+    Ok(table)
+}
+
+/// Table of sweep results, one row per run
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct SweepTable {
+    columns: Vec<String>,
+    rows: Vec<Vec<f64>>,
+    /// The sampled time series of each run, in row order
+    series: Vec<TimeSeries>,
+}
+
+impl SweepTable {
+    /// All rows, in the column order given by `columns`
+    pub fn rows(&self) -> &[Vec<f64>] {
+        &self.rows
+    }
+}
+
+#[wasm_bindgen]
+impl SweepTable {
+    /// Column names: the swept parameters followed by the summary statistics
+    #[wasm_bindgen]
+    pub fn columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    /// Number of rows
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the table has no rows
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Get a row by index
+    #[wasm_bindgen]
+    pub fn row(&self, index: usize) -> Option<Vec<f64>> {
+        self.rows.get(index).cloned()
+    }
+
+    /// Get every value of a column by name
+    #[wasm_bindgen]
+    pub fn column(&self, name: &str) -> Option<Vec<f64>> {
+        let index = self.columns.iter().position(|c| c == name)?;
+        Some(self.rows.iter().map(|row| row[index]).collect())
+    }
+
+    /// Get the sampled time series of the run in a row
+    #[wasm_bindgen]
+    pub fn series(&self, index: usize) -> Option<TimeSeries> {
+        self.series.get(index).cloned()
+    }
+
+    /// Format the table as CSV with a header row
+    #[wasm_bindgen]
+    pub fn to_csv(&self) -> String {
+        let mut csv = self.columns.join(",");
+        csv.push('\n');
+        for row in &self.rows {
+            let fields: Vec<String> = row.iter().map(f64::to_string).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Run a scenario file (TOML or JSON) headlessly on a new device, returning
+/// one row of summary statistics. The `run_scenario` binary does the same
+/// natively.
+#[wasm_bindgen]
+pub async fn run_scenario(source: String) -> Result<SweepTable, JsValue> {
+    let scenario = Scenario::parse(&source).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    ParameterSweep {
+        spec: SweepSpec::from(&scenario),
+    }
+    .run()
+    .await
+}
+
+/// Builder for running a parameter sweep from JavaScript
+#[wasm_bindgen]
+pub struct ParameterSweep {
+    spec: SweepSpec,
+}
+
+#[wasm_bindgen]
+impl ParameterSweep {
+    /// Create a sweep where every run starts from `base`
+    #[wasm_bindgen(constructor)]
+    pub fn new(base: ConfigurableParameters) -> Self {
+        Self {
+            spec: SweepSpec::new(base),
+        }
+    }
+
+    /// Create a sweep where every run starts from a scenario file (TOML or
+    /// JSON). The scenario's seed and run length become the sweep's defaults.
+    #[wasm_bindgen]
+    pub fn from_scenario(source: &str) -> Result<ParameterSweep, JsValue> {
+        let scenario = Scenario::parse(source).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(Self {
+            spec: SweepSpec::from(&scenario),
+        })
+    }
+
+    /// Sweep a parameter (by its `ConfigurableParameters` field name) over the
+    /// given values
+    #[wasm_bindgen]
+    pub fn add_axis(&mut self, parameter: String, values: Vec<f32>) -> Result<(), JsValue> {
+        let spec =
+            ParameterSpec::find(&parameter).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        for &value in &values {
+            spec.check(value as f64)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
+        self.spec.axes.push(SweepAxis { parameter, values });
+        Ok(())
+    }
+
+    /// Run each combination with each of these seeds
+    #[wasm_bindgen]
+    pub fn set_seeds(&mut self, seeds: Vec<u32>) {
+        self.spec.seeds = seeds;
+    }
+
+    /// Run each combination with seeds `0..count`
+    #[wasm_bindgen]
+    pub fn set_seed_count(&mut self, count: u32) {
+        self.spec.seeds = (0..count).collect();
+    }
+
+    /// Set the number of simulated years per run
+    #[wasm_bindgen]
+    pub fn set_years(&mut self, years: f32) {
+        self.spec.years = years;
+    }
+
+    /// Set the number of simulated years between samples
+    #[wasm_bindgen]
+    pub fn set_sample_interval_years(&mut self, years: f32) {
+        self.spec.sample_interval_years = years;
+    }
+
+    /// Set the number of simulated years left out of the averages
+    #[wasm_bindgen]
+    pub fn set_burn_in_years(&mut self, years: f32) {
+        self.spec.burn_in_years = years;
+    }
+
+    /// Set the number of runs that share the device at once
+    #[wasm_bindgen]
+    pub fn set_concurrency(&mut self, runs: usize) {
+        self.spec.concurrency = runs;
+    }
+
+    /// The total number of runs in the sweep
+    #[wasm_bindgen]
+    pub fn run_count(&self) -> usize {
+        self.spec
+            .axes
+            .iter()
+            .map(|a| a.values.len())
+            .product::<usize>()
+            * self.spec.seeds.len()
+    }
+
+    /// Run the sweep on a new device
+    #[wasm_bindgen]
+    pub async fn run(self) -> Result<SweepTable, JsValue> {
+        let resources = GpuResources::new()
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        run_sweep(&resources, &self.spec)
+            .await
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axis(parameter: &str, values: &[f32]) -> SweepAxis {
+        SweepAxis {
+            parameter: parameter.to_string(),
+            values: values.to_vec(),
+        }
+    }
+
+    #[test]
+    fn runs_cover_every_combination_with_every_seed() {
+        let mut spec = SweepSpec::new(ConfigurableParameters::default());
+        spec.axes = vec![
+            axis("ember_rate", &[0.1, 0.2]),
+            axis("driest_month", &[6.0, 7.0, 8.0]),
+        ];
+        spec.seeds = vec![3, 4];
+        let runs = spec.runs().unwrap();
+        assert_eq!(runs.len(), 12);
+
+        // The last axis varies fastest, then the seed within each combination
+        let (values, params) = &runs[0];
+        assert_eq!(values, &[0.1, 6.0]);
+        assert_eq!(
+            (params.ember_rate, params.driest_month, params.seed),
+            (0.1, 6.0, 3)
+        );
+        assert_eq!(runs[1].1.seed, 4);
+        assert_eq!(runs[2].0, [0.1, 7.0]);
+        assert_eq!(runs[11].0, [0.2, 8.0]);
+        assert_eq!(
+            runs[11].1.tree_growth_years,
+            ConfigurableParameters::default().tree_growth_years
+        );
+    }
+
+    #[test]
+    fn runs_reject_bad_axes() {
+        let mut spec = SweepSpec::new(ConfigurableParameters::default());
+        spec.axes = vec![axis("driest_month", &[8.0, 13.0])];
+        assert!(spec.runs().is_err());
+        spec.axes = vec![axis("no_such_parameter", &[1.0])];
+        assert!(spec.runs().is_err());
+
+        // Without axes there is one run per seed
+        spec.axes.clear();
+        spec.seeds = vec![1, 2, 3];
+        assert_eq!(spec.runs().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn table_columns_and_csv() {
+        let table = SweepTable {
+            columns: vec!["ember_rate".to_string(), "seed".to_string()],
+            rows: vec![vec![0.5, 1.0], vec![0.25, 2.0]],
+            series: Vec::new(),
+        };
+        assert_eq!(table.column("seed"), Some(vec![1.0, 2.0]));
+        assert_eq!(table.column("fires"), None);
+        assert_eq!(table.to_csv(), "ember_rate,seed\n0.5,1\n0.25,2\n");
+        assert_eq!(SweepTable::default().to_csv(), "\n");
+    }
+}
@@ -0,0 +1,488 @@
+//! Scripted event timelines
+//!
+//! A timeline lists events, each at a time in simulated years since the start
+//! of the run, e.g. "at year 50 ignite (120, 300), at year 80 raise the
+//! lightning frequency". The player adds up the simulated time of every step
+//! at the `ticks_per_month` it ran with, so changing the resolution mid-run
+//! doesn't move pending events, and an event runs just before the step that
+//! starts closest to its time:
+//!
+//! - a parameter change keeps applying for the rest of the run, so it wins
+//!   over changes made to the same parameter from outside the timeline
+//! - an ignition starts a new fire in one cell, if the cell has fuel
+//! - an edit overwrites the trees or underbrush of a rectangle of cells
+//! - a snapshot reads back the whole grid into a [`SnapshotLog`]
+//!
+//! Events at the same time run in the order they are listed in.
+
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::sim::{ConfigurableParameters, ParameterSpec, SimulationFrame, stats::FrameSummary};
+
+/// Events to run at set times during a run
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Timeline {
+    pub events: Vec<TimedEvent>,
+}
+
+/// An event and when it happens
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimedEvent {
+    /// Simulated years since the start of the run
+    pub year: f32,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+}
+
+/// Something that happens at a point on a timeline
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TimelineEvent {
+    /// Set a parameter by its `ConfigurableParameters` field name
+    SetParameter { parameter: String, value: f64 },
+    /// Start a fire in the cell at `(x, y)`
+    Ignite { x: u32, y: u32 },
+    /// Overwrite the cells of the rectangle with its top left corner at
+    /// `(x, y)`. Fields that are left out are not changed. Setting `tree`
+    /// plants a new tree of that size (or clears the tree if it is 0).
+    Edit {
+        x: u32,
+        y: u32,
+        #[serde(default = "default_extent")]
+        width: u32,
+        #[serde(default = "default_extent")]
+        height: u32,
+        #[serde(default)]
+        tree: Option<f32>,
+        #[serde(default)]
+        underbrush: Option<f32>,
+    },
+    /// Read back the whole grid, to be found in the snapshot log under `label`
+    Snapshot {
+        #[serde(default)]
+        label: String,
+    },
+}
+
+fn default_extent() -> u32 {
+    1
+}
+
+impl Timeline {
+    /// Parse a timeline from JSON (if the text starts with `{`) or TOML
+    pub fn parse(source: &str) -> Result<Self, anyhow::Error> {
+        Ok(if source.trim_start().starts_with('{') {
+            serde_json::from_str(source)?
+        } else {
+            toml::from_str(source)?
+        })
+    }
+
+    /// Add an event to the timeline
+    pub fn push(&mut self, year: f32, event: TimelineEvent) {
+        self.events.push(TimedEvent { year, event });
+    }
+
+    /// Check that every event can run on a grid of the given size
+    pub fn validate(&self, width: usize, height: usize) -> Result<(), anyhow::Error> {
+        for event in &self.events {
+            event.validate(width, height)?;
+        }
+        Ok(())
+    }
+}
+
+impl TimedEvent {
+    /// Check that the event can run on a grid of the given size
+    pub fn validate(&self, width: usize, height: usize) -> Result<(), anyhow::Error> {
+        let TimedEvent { year, event } = self;
+        if !(year.is_finite() && *year >= 0.0) {
+            return Err(anyhow::anyhow!(
+                "timeline events must happen at a year of 0 or more, not {year}"
+            ));
+        }
+        let outside = |x: u32, y: u32| x as usize >= width || y as usize >= height;
+        match event {
+            TimelineEvent::SetParameter { parameter, value } => {
+                ParameterSpec::find(parameter)?.check(*value)?;
+            }
+            TimelineEvent::Ignite { x, y } if outside(*x, *y) => {
+                return Err(anyhow::anyhow!(
+                    "ignition at ({x}, {y}) in year {year} is outside the {width}x{height} grid"
+                ));
+            }
+            TimelineEvent::Edit {
+                x,
+                y,
+                width: w,
+                height: h,
+                tree,
+                underbrush,
+            } => {
+                if *w == 0 || *h == 0 || outside(x.saturating_add(w - 1), y.saturating_add(h - 1)) {
+                    return Err(anyhow::anyhow!(
+                        "edit of {w}x{h} cells at ({x}, {y}) in year {year} doesn't fit in the {width}x{height} grid"
+                    ));
+                }
+                if tree.is_some_and(|tree| !(0.0..=1.0).contains(&tree)) {
+                    return Err(anyhow::anyhow!(
+                        "edits can only set the tree size to between 0 and 1"
+                    ));
+                }
+                if underbrush.is_some_and(|underbrush| !underbrush.is_finite() || underbrush < 0.0)
+                {
+                    return Err(anyhow::anyhow!(
+                        "edits can only set the underbrush to 0 or more"
+                    ));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// The state of the grid at a scripted point in a run
+#[derive(Clone)]
+pub struct Snapshot {
+    pub label: String,
+    /// The number of steps run before the snapshot was taken
+    pub step: u32,
+    pub frame: SimulationFrame,
+}
+
+/// Snapshots taken by the timeline since the start of the run. Snapshots are
+/// read back in the background, so they appear here shortly after their step
+/// has run.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct SnapshotLog {
+    snapshots: Vec<Snapshot>,
+}
+
+impl SnapshotLog {
+    /// All snapshots taken so far, in order
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// The first snapshot with the given label
+    pub fn find(&self, label: &str) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|s| s.label == label)
+    }
+
+    pub(crate) fn push(&mut self, snapshot: Snapshot) {
+        self.snapshots.push(snapshot);
+    }
+}
+
+#[wasm_bindgen]
+impl SnapshotLog {
+    /// Number of snapshots taken
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Whether no snapshots have been taken yet
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Get the label of a snapshot by index
+    #[wasm_bindgen]
+    pub fn label(&self, index: usize) -> Option<String> {
+        self.snapshots.get(index).map(|s| s.label.clone())
+    }
+
+    /// Get a whole-grid summary of a snapshot by index
+    #[wasm_bindgen]
+    pub fn summary(&self, index: usize) -> Option<FrameSummary> {
+        self.snapshots.get(index).map(|s| FrameSummary {
+            step: s.step,
+            ..FrameSummary::from(&s.frame)
+        })
+    }
+}
+
+/// Plays a timeline back, one step at a time
+pub struct TimelinePlayer {
+    /// The events in the order they happen
+    events: Vec<TimedEvent>,
+    /// Index of the next event to run
+    next: usize,
+    /// Simulated years from the start of the run to the next step
+    years: f64,
+    /// Parameters set so far, in the order they were set
+    overrides: Vec<(String, f64)>,
+    snapshots: Arc<Mutex<SnapshotLog>>,
+}
+
+impl TimelinePlayer {
+    /// Create a player for the given timeline, which starts `snapshots` over
+    pub fn new(timeline: Timeline, snapshots: Arc<Mutex<SnapshotLog>>) -> Self {
+        let mut events = timeline.events;
+        events.sort_by(|a, b| a.year.total_cmp(&b.year));
+        *snapshots.lock().expect("failed to lock snapshot log") = SnapshotLog::default();
+        Self {
+            events,
+            next: 0,
+            years: 0.0,
+            overrides: Vec::new(),
+            snapshots,
+        }
+    }
+
+    /// Start the timeline over, e.g. when a new frame is loaded
+    pub fn reset(&mut self) {
+        self.next = 0;
+        self.years = 0.0;
+        self.overrides.clear();
+        *self.snapshots.lock().expect("failed to lock snapshot log") = SnapshotLog::default();
+    }
+
+    /// The snapshots taken so far
+    pub fn snapshots(&self) -> &Arc<Mutex<SnapshotLog>> {
+        &self.snapshots
+    }
+
+    /// Take snapshots into a different log from now on
+    pub fn set_snapshots(&mut self, snapshots: Arc<Mutex<SnapshotLog>>) {
+        self.snapshots = snapshots;
+    }
+
+    /// Drop the events still to run that can't run on a grid of the given
+    /// size, e.g. after the grid shrinks
+    pub fn fit_to(&mut self, width: usize, height: usize) {
+        let mut index = 0;
+        self.events.retain(|event| {
+            index += 1;
+            if index <= self.next {
+                return true;
+            }
+            match event.validate(width, height) {
+                Ok(()) => true,
+                Err(e) => {
+                    log::warn!("dropping timeline event after resizing the grid: {e}");
+                    false
+                }
+            }
+        });
+    }
+
+    /// Add an event to the timeline after any others at the same time. An
+    /// event whose time has already passed runs before the next step.
+    pub fn schedule(&mut self, event: TimedEvent) {
+        let at = self.next + self.events[self.next..].partition_point(|e| e.year <= event.year);
+        self.events.insert(at, event);
+    }
+
+    /// The events to run before the next step, which must be called once
+    /// before every step. Parameter changes are applied to `config` here,
+    /// along with every earlier one, so they are left out of the returned
+    /// events.
+    pub fn due(&mut self, config: &mut ConfigurableParameters) -> Vec<TimelineEvent> {
+        let half_tick = 0.5 / ticks_per_year(config);
+        let mut due = Vec::new();
+        while let Some(event) = self.events.get(self.next)
+            && (event.year as f64) < self.years + half_tick
+        {
+            match &event.event {
+                TimelineEvent::SetParameter { parameter, value } => {
+                    self.overrides.retain(|(name, _)| name != parameter);
+                    self.overrides.push((parameter.clone(), *value));
+                }
+                other => due.push(other.clone()),
+            }
+            self.next += 1;
+        }
+        for (name, value) in &self.overrides {
+            if let Err(e) = config.set_parameter(name, *value) {
+                log::warn!("timeline could not set {name}: {e}");
+            }
+        }
+        // The step runs at the resolution the timeline leaves it with
+        self.years += 1.0 / ticks_per_year(config);
+        due
+    }
+}
+
+/// The number of ticks in a simulated year
+fn ticks_per_year(config: &ConfigurableParameters) -> f64 {
+    config.ticks_per_month as f64 * 12.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+[[events]]
+year = 2
+type = "ignite"
+x = 3
+y = 4
+
+[[events]]
+year = 1
+type = "set_parameter"
+parameter = "ember_rate"
+value = 0.2
+
+[[events]]
+year = 3
+type = "edit"
+x = 0
+y = 0
+width = 2
+tree = 0.5
+"#;
+
+    #[test]
+    fn toml_and_json_parse_the_same() {
+        let timeline = Timeline::parse(TOML).unwrap();
+        let json = serde_json::to_string(&timeline).unwrap();
+        assert_eq!(Timeline::parse(&json).unwrap(), timeline);
+        assert_eq!(
+            timeline.events[2].event,
+            TimelineEvent::Edit {
+                x: 0,
+                y: 0,
+                width: 2,
+                height: 1,
+                tree: Some(0.5),
+                underbrush: None,
+            }
+        );
+        assert!(Timeline::parse("[[events]]\nyear = 1\ntype = \"flood\"\n").is_err());
+        assert!(
+            Timeline::parse("[[events]]\nyear = 1\ntype = \"ignite\"\nx = 1\ny = 1\nz = 1\n")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_checks_events_fit_the_grid() {
+        let timeline = Timeline::parse(TOML).unwrap();
+        assert!(timeline.validate(5, 5).is_ok());
+        assert!(timeline.validate(4, 4).is_err());
+
+        let check = |year, event| {
+            let mut timeline = Timeline::default();
+            timeline.push(year, event);
+            timeline.validate(5, 5)
+        };
+        assert!(check(-1.0, TimelineEvent::Ignite { x: 0, y: 0 }).is_err());
+        let edit = |width, underbrush| TimelineEvent::Edit {
+            x: 4,
+            y: 0,
+            width,
+            height: 1,
+            tree: None,
+            underbrush,
+        };
+        assert!(check(0.0, edit(1, Some(2.0))).is_ok());
+        assert!(check(0.0, edit(2, None)).is_err());
+        assert!(check(0.0, edit(0, None)).is_err());
+        assert!(check(0.0, edit(1, Some(-1.0))).is_err());
+        let set = |parameter: &str, value| TimelineEvent::SetParameter {
+            parameter: parameter.to_string(),
+            value,
+        };
+        assert!(check(0.0, set("no_such_parameter", 1.0)).is_err());
+        assert!(check(0.0, set("driest_month", 13.0)).is_err());
+    }
+
+    fn player(timeline: Timeline) -> TimelinePlayer {
+        TimelinePlayer::new(timeline, Arc::new(Mutex::new(SnapshotLog::default())))
+    }
+
+    /// The events due over the given number of steps
+    fn run(
+        player: &mut TimelinePlayer,
+        config: &mut ConfigurableParameters,
+        steps: u32,
+    ) -> Vec<TimelineEvent> {
+        (0..steps).flat_map(|_| player.due(config)).collect()
+    }
+
+    fn ticks_per_year(config: &ConfigurableParameters) -> u32 {
+        (config.ticks_per_month * 12.0) as u32
+    }
+
+    #[test]
+    fn player_runs_events_in_time_order() {
+        let mut player = player(Timeline::parse(TOML).unwrap());
+        let mut config = ConfigurableParameters::default();
+        let year = ticks_per_year(&config);
+
+        assert!(run(&mut player, &mut config, year).is_empty());
+        assert_ne!(config.ember_rate, 0.2);
+        assert!(player.due(&mut config).is_empty());
+        assert_eq!(config.ember_rate, 0.2);
+
+        // The change keeps winning over changes from outside the timeline
+        config.ember_rate = 0.0;
+        assert!(run(&mut player, &mut config, year - 1).is_empty());
+        assert_eq!(config.ember_rate, 0.2);
+        assert_eq!(
+            player.due(&mut config),
+            [TimelineEvent::Ignite { x: 3, y: 4 }]
+        );
+
+        // An event scheduled in the past runs before the next step
+        player.schedule(TimedEvent {
+            year: 0.0,
+            event: TimelineEvent::Ignite { x: 1, y: 1 },
+        });
+        assert_eq!(
+            player.due(&mut config),
+            [TimelineEvent::Ignite { x: 1, y: 1 }]
+        );
+        assert_eq!(run(&mut player, &mut config, year).len(), 1);
+    }
+
+    #[test]
+    fn changing_the_resolution_keeps_events_in_simulated_time() {
+        let mut config = ConfigurableParameters::default();
+        let year = ticks_per_year(&config);
+        let mut timeline = Timeline::default();
+        timeline.push(
+            1.0,
+            TimelineEvent::SetParameter {
+                parameter: "ticks_per_month".to_string(),
+                value: config.ticks_per_month as f64 * 2.0,
+            },
+        );
+        timeline.push(2.0, TimelineEvent::Ignite { x: 0, y: 0 });
+        let mut player = player(timeline);
+
+        // A year at the starting resolution, then a year at double it
+        assert!(run(&mut player, &mut config, 3 * year).is_empty());
+        assert_eq!(ticks_per_year(&config), 2 * year);
+        assert_eq!(player.due(&mut config).len(), 1);
+    }
+
+    #[test]
+    fn fitting_to_a_smaller_grid_drops_pending_events_that_no_longer_fit() {
+        let mut config = ConfigurableParameters::default();
+        let mut timeline = Timeline::default();
+        timeline.push(0.0, TimelineEvent::Ignite { x: 8, y: 8 });
+        timeline.push(1.0, TimelineEvent::Ignite { x: 8, y: 8 });
+        timeline.push(1.0, TimelineEvent::Ignite { x: 2, y: 2 });
+        let mut player = player(timeline);
+        assert_eq!(player.due(&mut config).len(), 1);
+
+        player.fit_to(5, 5);
+        let year = ticks_per_year(&config);
+        assert_eq!(
+            run(&mut player, &mut config, year),
+            [TimelineEvent::Ignite { x: 2, y: 2 }]
+        );
+    }
+}
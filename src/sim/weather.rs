@@ -0,0 +1,189 @@
+//! Seasonal weather and fuel moisture
+//!
+//! The weather driver turns the simulation calendar into a fuel moisture value
+//! for every tick. Dry fuel is more flammable, carries fire further and is
+//! more easily ignited by lightning, so fire seasons emerge from the moisture
+//! cycle. Moisture comes from one of three sources:
+//!
+//! - a built-in sinusoidal profile, configured by the `mean_fuel_moisture`,
+//!   `fuel_moisture_amplitude` and `driest_month` parameters
+//! - a table of twelve monthly values, interpolated between mid-months
+//! - a daily series, e.g. imported from a CSV file, which repeats once it
+//!   runs out
+//!
+//! Moisture is a fraction of the fuel's dry weight, compared against the
+//! `moisture_of_extinction` parameter above which fuel doesn't burn at all.
+
+use std::f32::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sim::SimulationParameters;
+
+/// Days in the simulation calendar's year
+const DAYS_PER_YEAR: f32 = 365.0;
+
+/// Where the fuel moisture of each tick comes from
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WeatherSeries {
+    /// A yearly sine wave set by the moisture parameters
+    #[default]
+    Sinusoidal,
+    /// Mean fuel moisture of each month, January first
+    Monthly { moisture: Vec<f32> },
+    /// Fuel moisture of each day, starting on the 1st of January
+    Daily { moisture: Vec<f32> },
+}
+
+impl WeatherSeries {
+    /// Parse a daily series from CSV. Each line holds one day, and the
+    /// moisture is taken from the last column, so both `moisture` and
+    /// `date,moisture` files work. Lines whose last field isn't a number,
+    /// such as a header, are skipped.
+    pub fn from_csv(source: &str) -> Result<Self, anyhow::Error> {
+        let moisture: Vec<f32> = source
+            .lines()
+            .filter_map(|line| line.split(',').next_back()?.trim().parse().ok())
+            .collect();
+        let series = WeatherSeries::Daily { moisture };
+        series.validate()?;
+        Ok(series)
+    }
+
+    /// Check that the series has values and that they are usable
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let values = match self {
+            WeatherSeries::Sinusoidal => return Ok(()),
+            WeatherSeries::Monthly { moisture } => {
+                if moisture.len() != 12 {
+                    return Err(anyhow::anyhow!(
+                        "monthly weather needs 12 moisture values but has {}",
+                        moisture.len()
+                    ));
+                }
+                moisture
+            }
+            WeatherSeries::Daily { moisture } => {
+                if moisture.is_empty() {
+                    return Err(anyhow::anyhow!("daily weather has no moisture values"));
+                }
+                moisture
+            }
+        };
+        match values.iter().position(|m| !m.is_finite() || *m < 0.0) {
+            Some(i) => Err(anyhow::anyhow!(
+                "fuel moisture {} at index {i} must be a non-negative number",
+                values[i]
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// The fuel moisture on the given step
+    pub fn fuel_moisture(&self, step: u32, parameters: &SimulationParameters) -> f32 {
+        let years = step as f32 * parameters.years_per_tick;
+        // Months since the start of the year, where month `m` (1-based) spans
+        // `m - 1 .. m`
+        let month = years.fract() * 12.0;
+        match self {
+            WeatherSeries::Sinusoidal => {
+                let phase = (month - (parameters.driest_month - 0.5)) / 12.0 * TAU;
+                parameters.mean_fuel_moisture - parameters.fuel_moisture_amplitude * phase.cos()
+            }
+            WeatherSeries::Monthly { moisture } => {
+                // Interpolate between the middles of adjacent months
+                let position = (month - 0.5).rem_euclid(12.0);
+                let before = position.floor() as usize % 12;
+                let after = (before + 1) % 12;
+                let t = position.fract();
+                moisture[before] * (1.0 - t) + moisture[after] * t
+            }
+            WeatherSeries::Daily { moisture } => {
+                let day = (years * DAYS_PER_YEAR).floor() as usize;
+                moisture[day % moisture.len()]
+            }
+        }
+        .max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::ConfigurableParameters;
+
+    fn parameters(years_per_tick: f32) -> SimulationParameters {
+        SimulationParameters {
+            years_per_tick,
+            ..SimulationParameters::from(&ConfigurableParameters::default())
+        }
+    }
+
+    #[test]
+    fn csv_takes_the_last_column_and_skips_headers() {
+        let series = WeatherSeries::from_csv("date,moisture\n2020-01-01,0.1\n2020-01-02, 0.2\n\n");
+        assert_eq!(
+            series.unwrap(),
+            WeatherSeries::Daily {
+                moisture: vec![0.1, 0.2]
+            }
+        );
+        assert_eq!(
+            WeatherSeries::from_csv("0.3\n").unwrap(),
+            WeatherSeries::Daily {
+                moisture: vec![0.3]
+            }
+        );
+    }
+
+    #[test]
+    fn csv_must_have_usable_values() {
+        assert!(WeatherSeries::from_csv("date,moisture\n").is_err());
+        assert!(WeatherSeries::from_csv("2020-01-01,-0.1\n").is_err());
+        assert!(WeatherSeries::from_csv("2020-01-01,NaN\n").is_err());
+    }
+
+    #[test]
+    fn monthly_needs_twelve_values() {
+        let series = WeatherSeries::Monthly {
+            moisture: vec![0.1; 11],
+        };
+        assert!(series.validate().is_err());
+    }
+
+    #[test]
+    fn daily_series_repeats() {
+        let series = WeatherSeries::Daily {
+            moisture: vec![0.1, 0.2],
+        };
+        // One and a half days a step
+        let parameters = parameters(1.5 / DAYS_PER_YEAR);
+        assert_eq!(series.fuel_moisture(0, &parameters), 0.1);
+        assert_eq!(series.fuel_moisture(1, &parameters), 0.2);
+        assert_eq!(series.fuel_moisture(3, &parameters), 0.1);
+    }
+
+    #[test]
+    fn monthly_series_interpolates_between_mid_months() {
+        let mut moisture = vec![0.0; 12];
+        moisture[0] = 0.1;
+        moisture[1] = 0.3;
+        let series = WeatherSeries::Monthly { moisture };
+        // Two steps a month
+        let parameters = parameters(1.0 / 24.0);
+        assert!((series.fuel_moisture(1, &parameters) - 0.1).abs() < 1e-5);
+        assert!((series.fuel_moisture(2, &parameters) - 0.2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sinusoid_is_driest_in_the_driest_month() {
+        let parameters = parameters(1.0 / 24.0);
+        let moisture = |step| WeatherSeries::Sinusoidal.fuel_moisture(step, &parameters);
+        // The middle of the driest month
+        let driest = (parameters.driest_month * 2.0 - 1.0) as u32;
+        let lowest = parameters.mean_fuel_moisture - parameters.fuel_moisture_amplitude;
+        assert!((moisture(driest) - lowest).abs() < 1e-5);
+        assert!((0..24).all(|step| moisture(step) >= lowest - 1e-5));
+    }
+}